@@ -6,7 +6,7 @@ use futures::Stream;
 use serde_json::Value;
 
 use crate::{
-    CacheControlEphemeral, Citation, ContentBlock, ContentBlockDelta, Error, Message,
+    CacheControlEphemeral, CompactionBlock, ContentBlock, ContentBlockDelta, Error, Message,
     MessageStreamEvent, ServerToolUseBlock, StopReason, TextBlock, TextCitation, ThinkingBlock,
     ToolUseBlock,
 };
@@ -81,6 +81,7 @@ impl AccumulatingStream {
                     if delta_event.delta.stop_sequence.is_some() {
                         msg.stop_sequence = delta_event.delta.stop_sequence.clone();
                     }
+                    // The count from message_start stands unless the delta revises it.
                     if let Some(input_tokens) = delta_event.usage.input_tokens {
                         msg.usage.input_tokens = input_tokens;
                     }
@@ -97,7 +98,7 @@ impl AccumulatingStream {
                 }
             }
             MessageStreamEvent::MessageStop(_) => {}
-            MessageStreamEvent::Ping => {}
+            MessageStreamEvent::Ping | MessageStreamEvent::Unknown { .. } => {}
         }
     }
 
@@ -116,6 +117,15 @@ impl AccumulatingStream {
         Ok(msg)
     }
 
+    /// Returns the best-effort input of the tool use block at `index` before it has finished.
+    ///
+    /// Only top-level keys whose values have been completely streamed are included, so the
+    /// result grows as `input_json_delta` events arrive.  Returns `None` when the block at
+    /// `index` is not a client tool use.  This never affects the accumulated message.
+    pub fn partial_input(&self, index: usize) -> Option<Value> {
+        self.content_blocks.get(index)?.partial_input()
+    }
+
     /// Finalizes the currently accumulated message without draining the stream.
     pub fn finalize_partial(&mut self) -> Result<Message, Error> {
         self.message_tx.take();
@@ -172,6 +182,10 @@ enum ContentBlockBuilder {
         thinking: String,
         signature: String,
     },
+    Compaction {
+        content: Option<String>,
+        cache_control: Option<CacheControlEphemeral>,
+    },
     Complete(ContentBlock),
 }
 
@@ -201,6 +215,10 @@ impl ContentBlockBuilder {
                 thinking: thinking.thinking,
                 signature: thinking.signature,
             },
+            ContentBlock::Compaction(compaction) => ContentBlockBuilder::Compaction {
+                content: compaction.content,
+                cache_control: compaction.cache_control,
+            },
             other => ContentBlockBuilder::Complete(other),
         }
     }
@@ -214,15 +232,9 @@ impl ContentBlockBuilder {
                 ContentBlockBuilder::Text { citations, .. },
                 ContentBlockDelta::CitationsDelta(citations_delta),
             ) => {
-                let citation = match citations_delta.citation {
-                    Citation::CharLocation(loc) => TextCitation::CharLocation(loc),
-                    Citation::PageLocation(loc) => TextCitation::PageLocation(loc),
-                    Citation::ContentBlockLocation(loc) => TextCitation::ContentBlockLocation(loc),
-                    Citation::WebSearchResultLocation(loc) => {
-                        TextCitation::WebSearchResultLocation(loc)
-                    }
-                };
-                citations.get_or_insert_with(Vec::new).push(citation);
+                citations
+                    .get_or_insert_with(Vec::new)
+                    .push(TextCitation::from(citations_delta.citation));
             }
             (
                 ContentBlockBuilder::ToolUse {
@@ -247,10 +259,33 @@ impl ContentBlockBuilder {
             ) => {
                 signature.push_str(&sig_delta.signature);
             }
+            (
+                ContentBlockBuilder::Compaction { content, .. },
+                ContentBlockDelta::CompactionDelta(compaction_delta),
+            ) => {
+                if let Some(fragment) = compaction_delta.content {
+                    content.get_or_insert_with(String::new).push_str(&fragment);
+                }
+            }
             _ => {}
         }
     }
 
+    fn partial_input(&self) -> Option<Value> {
+        match self {
+            ContentBlockBuilder::ToolUse {
+                input_json,
+                input_value,
+                saw_delta,
+                ..
+            } => Some(match input_value {
+                Some(input) if !*saw_delta => input.clone(),
+                _ => partial_json_object(input_json),
+            }),
+            _ => None,
+        }
+    }
+
     fn build(self, stop_reason: Option<StopReason>) -> Result<Option<ContentBlock>, Error> {
         match self {
             ContentBlockBuilder::Empty => Ok(None),
@@ -325,18 +360,99 @@ impl ContentBlockBuilder {
                 thinking,
                 signature,
             }))),
+            ContentBlockBuilder::Compaction {
+                content,
+                cache_control,
+            } => Ok(Some(ContentBlock::Compaction(CompactionBlock {
+                content,
+                cache_control,
+            }))),
             ContentBlockBuilder::Complete(block) => Ok(Some(block)),
         }
     }
 }
 
+/// Parses the completed top-level members of a possibly truncated JSON object.
+///
+/// A member is complete once the comma or closing brace that follows its value has arrived;
+/// members still being streamed, and anything after malformed input, are left out.
+fn partial_json_object(json: &str) -> Value {
+    let mut object = serde_json::Map::new();
+    let bytes = json.as_bytes();
+    let mut pos = skip_whitespace(bytes, 0);
+    if bytes.get(pos) != Some(&b'{') {
+        return Value::Object(object);
+    }
+    pos += 1;
+    loop {
+        pos = skip_whitespace(bytes, pos);
+        let Some(key_end) = scan_value(bytes, pos) else {
+            break;
+        };
+        let Ok(key) = serde_json::from_str::<String>(&json[pos..key_end]) else {
+            break;
+        };
+        pos = skip_whitespace(bytes, key_end);
+        if bytes.get(pos) != Some(&b':') {
+            break;
+        }
+        let value_start = pos + 1;
+        let Some(value_end) = scan_value(bytes, value_start) else {
+            break;
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&json[value_start..value_end]) else {
+            break;
+        };
+        object.insert(key, value);
+        match bytes[value_end] {
+            b',' => pos = value_end + 1,
+            _ => break,
+        }
+    }
+    Value::Object(object)
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        pos += 1;
+    }
+    pos
+}
+
+/// Returns the offset of the `,`, `:`, or `}` that terminates the value starting at `start`,
+/// or `None` if the input ends before the value does.
+fn scan_value(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (offset, &byte) in bytes[start..].iter().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth > 0 => depth -= 1,
+            b',' | b':' | b'}' if depth == 0 => return Some(start + offset),
+            _ => {}
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        ContentBlockDeltaEvent, ContentBlockStartEvent, ContentBlockStopEvent, InputJsonDelta,
-        KnownModel, MessageDelta, MessageDeltaEvent, MessageDeltaUsage, MessageStartEvent, Model,
-        TextDelta, Usage,
+        CitationCharLocation, CitationPageLocation, CitationsDelta, ContentBlockDeltaEvent,
+        ContentBlockStartEvent, ContentBlockStopEvent, InputJsonDelta, KnownModel, MessageDelta,
+        MessageDeltaEvent, MessageDeltaUsage, MessageStartEvent, Model, TextDelta, Usage,
     };
     use futures::stream;
 
@@ -421,6 +537,40 @@ mod tests {
         );
     }
 
+    /// Verifies that input tokens from message_start survive a delta that omits them.
+    #[tokio::test]
+    async fn input_tokens_from_message_start_preserved() {
+        use futures::StreamExt;
+
+        let start_message = Message::new(
+            "msg_input".to_string(),
+            Vec::new(),
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(1234, 1),
+        );
+        let delta_usage = MessageDeltaUsage::new(7);
+        assert_eq!(delta_usage.input_tokens, None);
+        let events = vec![
+            Ok(MessageStreamEvent::MessageStart(MessageStartEvent::new(
+                start_message,
+            ))),
+            Ok(MessageStreamEvent::MessageDelta(MessageDeltaEvent::new(
+                MessageDelta::new().with_stop_reason(StopReason::EndTurn),
+                delta_usage,
+            ))),
+        ];
+
+        let (mut acc_stream, rx) = AccumulatingStream::new(stream::iter(events));
+        while acc_stream.next().await.is_some() {}
+        let message = rx
+            .await
+            .expect("channel closed")
+            .expect("accumulation failed");
+
+        assert_eq!(message.usage.input_tokens, 1234);
+        assert_eq!(message.usage.output_tokens, 7);
+    }
+
     /// Verifies that tool use with empty input JSON becomes an empty object, not null.
     #[tokio::test]
     async fn empty_tool_input_becomes_empty_object() {
@@ -552,4 +702,316 @@ mod tests {
         );
         println!("tool_use.input: {:?}", tool_use.input);
     }
+
+    /// Verifies that citations deltas accumulate onto the text block with every field intact.
+    #[tokio::test]
+    async fn citations_deltas_accumulate_into_text_block() {
+        use futures::StreamExt;
+
+        let start_message = Message::new(
+            "msg_test".to_string(),
+            Vec::new(),
+            Model::Known(KnownModel::Claude37SonnetLatest),
+            Usage::new(100, 0),
+        );
+        let char_location = CitationCharLocation::new(
+            "The sky is blue.".to_string(),
+            0,
+            12,
+            28,
+            Some("Notes".to_string()),
+        );
+        let page_location = CitationPageLocation::new("Grass is green.".to_string(), 1, 3, 4, None);
+        let delta =
+            |delta| MessageStreamEvent::ContentBlockDelta(ContentBlockDeltaEvent::new(delta, 0));
+        let events = vec![
+            Ok(MessageStreamEvent::MessageStart(MessageStartEvent::new(
+                start_message,
+            ))),
+            Ok(MessageStreamEvent::ContentBlockStart(
+                ContentBlockStartEvent::new(ContentBlock::Text(TextBlock::new(String::new())), 0),
+            )),
+            Ok(delta(ContentBlockDelta::from_citations_delta(
+                CitationsDelta::with_char_location(char_location.clone()),
+            ))),
+            Ok(delta(ContentBlockDelta::TextDelta(TextDelta::new(
+                "The sky is blue".to_string(),
+            )))),
+            Ok(delta(ContentBlockDelta::from_citations_delta(
+                CitationsDelta::with_page_location(page_location.clone()),
+            ))),
+            Ok(delta(ContentBlockDelta::TextDelta(TextDelta::new(
+                " and grass is green.".to_string(),
+            )))),
+            Ok(MessageStreamEvent::ContentBlockStop(
+                ContentBlockStopEvent::new(0),
+            )),
+            Ok(MessageStreamEvent::MessageDelta(MessageDeltaEvent::new(
+                MessageDelta::new().with_stop_reason(StopReason::EndTurn),
+                MessageDeltaUsage::new(10),
+            ))),
+        ];
+
+        let (mut acc_stream, rx) = AccumulatingStream::new(stream::iter(events));
+        while acc_stream.next().await.is_some() {}
+        let message = rx
+            .await
+            .expect("channel closed")
+            .expect("accumulation failed");
+
+        let text = message.content[0].as_text().expect("Expected TextBlock");
+        assert_eq!(text.text, "The sky is blue and grass is green.");
+        assert_eq!(
+            text.citations,
+            Some(vec![
+                TextCitation::CharLocation(char_location),
+                TextCitation::PageLocation(page_location),
+            ])
+        );
+    }
+
+    /// Verifies that a stop sequence reported in message_delta ends up on the message.
+    #[tokio::test]
+    async fn stop_sequence_from_message_delta_preserved() {
+        use futures::StreamExt;
+
+        let start_message = Message::new(
+            "msg_test".to_string(),
+            Vec::new(),
+            Model::Known(KnownModel::Claude37SonnetLatest),
+            Usage::new(100, 0),
+        );
+        let events = vec![
+            Ok(MessageStreamEvent::MessageStart(MessageStartEvent::new(
+                start_message,
+            ))),
+            Ok(MessageStreamEvent::ContentBlockStart(
+                ContentBlockStartEvent::new(ContentBlock::Text(TextBlock::new(String::new())), 0),
+            )),
+            Ok(MessageStreamEvent::ContentBlockDelta(
+                ContentBlockDeltaEvent::new(
+                    ContentBlockDelta::TextDelta(TextDelta::new("before".to_string())),
+                    0,
+                ),
+            )),
+            Ok(MessageStreamEvent::ContentBlockStop(
+                ContentBlockStopEvent::new(0),
+            )),
+            Ok(MessageStreamEvent::MessageDelta(MessageDeltaEvent::new(
+                MessageDelta::new()
+                    .with_stop_reason(StopReason::StopSequence)
+                    .with_stop_sequence("###".to_string()),
+                MessageDeltaUsage::new(3),
+            ))),
+        ];
+
+        let (mut acc_stream, rx) = AccumulatingStream::new(stream::iter(events));
+        while acc_stream.next().await.is_some() {}
+        let message = rx
+            .await
+            .expect("channel closed")
+            .expect("accumulation failed");
+
+        assert_eq!(message.stop_reason, Some(StopReason::StopSequence));
+        assert_eq!(message.stop_sequence(), Some("###"));
+    }
+
+    #[test]
+    fn partial_json_object_exposes_completed_keys() {
+        let json = r#"{"path": "src/lib.rs", "range": [1, {"end": 20}], "note": "a, \"b\" }", "limit": 42}"#;
+        let mut seen = Vec::new();
+        for end in 0..=json.len() {
+            let partial = partial_json_object(&json[..end]);
+            let keys: Vec<String> = partial.as_object().unwrap().keys().cloned().collect();
+            if seen.last() != Some(&keys) {
+                seen.push(keys);
+            }
+        }
+        assert_eq!(
+            seen,
+            vec![
+                vec![],
+                vec!["path".to_string()],
+                vec!["path".to_string(), "range".to_string()],
+                vec!["path".to_string(), "range".to_string(), "note".to_string()],
+                vec![
+                    "path".to_string(),
+                    "range".to_string(),
+                    "note".to_string(),
+                    "limit".to_string()
+                ],
+            ]
+        );
+        assert_eq!(
+            partial_json_object(json),
+            serde_json::from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn partial_json_object_ignores_non_objects() {
+        assert_eq!(partial_json_object(""), serde_json::json!({}));
+        assert_eq!(partial_json_object("[1, 2]"), serde_json::json!({}));
+        assert_eq!(
+            partial_json_object(r#"{"a": 1, oops, "b": 2}"#),
+            serde_json::json!({"a": 1})
+        );
+    }
+
+    /// Verifies that partial tool input grows with each delta without changing the final input.
+    #[tokio::test]
+    async fn partial_input_tracks_input_json_deltas() {
+        use futures::StreamExt;
+
+        let start_message = Message::new(
+            "msg_test".to_string(),
+            Vec::new(),
+            Model::Known(KnownModel::Claude37SonnetLatest),
+            Usage::new(100, 0),
+        );
+        let tool_use_block = ContentBlock::ToolUse(ToolUseBlock::new(
+            "tool_123",
+            "search",
+            serde_json::json!({}),
+        ));
+        let mut events = vec![
+            Ok(MessageStreamEvent::MessageStart(MessageStartEvent::new(
+                start_message,
+            ))),
+            Ok(MessageStreamEvent::ContentBlockStart(
+                ContentBlockStartEvent::new(tool_use_block, 0),
+            )),
+        ];
+        for chunk in [r#"{"query": "ru"#, r#"st", "li"#, r#"mit": 5"#, "}"] {
+            events.push(Ok(MessageStreamEvent::ContentBlockDelta(
+                ContentBlockDeltaEvent::new(
+                    ContentBlockDelta::InputJsonDelta(InputJsonDelta::new(chunk.to_string())),
+                    0,
+                ),
+            )));
+        }
+        events.push(Ok(MessageStreamEvent::ContentBlockStop(
+            ContentBlockStopEvent::new(0),
+        )));
+
+        let (mut acc_stream, rx) = AccumulatingStream::new(stream::iter(events));
+        let mut partials = Vec::new();
+        while acc_stream.next().await.is_some() {
+            partials.push(acc_stream.partial_input(0));
+        }
+
+        assert_eq!(
+            partials,
+            vec![
+                None,
+                Some(serde_json::json!({})),
+                Some(serde_json::json!({})),
+                Some(serde_json::json!({"query": "rust"})),
+                Some(serde_json::json!({"query": "rust"})),
+                Some(serde_json::json!({"query": "rust", "limit": 5})),
+                Some(serde_json::json!({"query": "rust", "limit": 5})),
+            ]
+        );
+        let message = rx
+            .await
+            .expect("channel closed")
+            .expect("accumulation failed");
+        let tool_use = message.content[0]
+            .as_tool_use()
+            .expect("Expected ToolUseBlock");
+        assert_eq!(
+            tool_use.input,
+            serde_json::json!({"query": "rust", "limit": 5})
+        );
+    }
+
+    /// Verifies that unrecognized event types are passed through and don't end accumulation.
+    #[tokio::test]
+    async fn unknown_events_are_ignored() {
+        let start_message = Message::new(
+            "msg_test".to_string(),
+            Vec::new(),
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(10, 0),
+        );
+        let unknown = MessageStreamEvent::Unknown {
+            r#type: "message_checkpoint".to_string(),
+            raw: serde_json::json!({"type": "message_checkpoint"}),
+        };
+        let events = vec![
+            Ok(MessageStreamEvent::MessageStart(MessageStartEvent::new(
+                start_message,
+            ))),
+            Ok(MessageStreamEvent::ContentBlockStart(
+                ContentBlockStartEvent::new(ContentBlock::Text(TextBlock::new("")), 0),
+            )),
+            Ok(unknown.clone()),
+            Ok(MessageStreamEvent::ContentBlockDelta(
+                ContentBlockDeltaEvent::new(
+                    ContentBlockDelta::TextDelta(TextDelta::new("still here".to_string())),
+                    0,
+                ),
+            )),
+            Ok(MessageStreamEvent::ContentBlockStop(
+                ContentBlockStopEvent::new(0),
+            )),
+        ];
+        let (acc_stream, rx) = AccumulatingStream::new(stream::iter(events));
+
+        use futures::StreamExt;
+        let seen: Vec<_> = acc_stream.collect().await;
+        assert_eq!(seen.len(), 5);
+        assert_eq!(seen[2].as_ref().unwrap(), &unknown);
+
+        let message = rx
+            .await
+            .expect("channel closed")
+            .expect("accumulation failed");
+        assert_eq!(
+            message.content,
+            vec![ContentBlock::Text(TextBlock::new("still here"))]
+        );
+    }
+
+    /// Verifies that a compaction block streams into the final message alongside unknown
+    /// compaction events.
+    #[tokio::test]
+    async fn compaction_events_accumulate() {
+        let fixture = [
+            r#"{"type":"message_start","message":{"id":"msg_compact","type":"message","role":"assistant","content":[],"model":"claude-haiku-4-5","stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":150000,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"compaction","content":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"compaction_delta","content":"The user is "}}"#,
+            r#"{"type":"compaction_progress","index":0,"tokens_summarized":120000}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"compaction_delta","content":"refactoring a parser."}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Continuing."}}"#,
+            r#"{"type":"content_block_stop","index":1}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn","stop_sequence":null},"usage":{"output_tokens":12}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let events: Vec<Result<MessageStreamEvent, Error>> = fixture
+            .iter()
+            .map(|line| Ok(serde_json::from_str(line).expect("fixture event should parse")))
+            .collect();
+        assert!(matches!(events[3], Ok(MessageStreamEvent::Unknown { .. })));
+        let (acc_stream, rx) = AccumulatingStream::new(stream::iter(events));
+
+        use futures::StreamExt;
+        let seen: Vec<_> = acc_stream.collect().await;
+        assert_eq!(seen.len(), fixture.len());
+
+        let message = rx
+            .await
+            .expect("channel closed")
+            .expect("accumulation failed");
+        assert_eq!(
+            message.compaction(),
+            Some(&CompactionBlock::new("The user is refactoring a parser."))
+        );
+        assert_eq!(
+            message.content[1],
+            ContentBlock::Text(TextBlock::new("Continuing."))
+        );
+    }
 }
@@ -1,9 +1,12 @@
 use std::any::Any;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::marker::PhantomData;
 use std::ops::ControlFlow;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use futures::StreamExt;
 use utf8path::Path;
@@ -13,14 +16,14 @@ use crate::cache_control::{
 };
 use crate::observability::{
     AGENT_TOOL_CALLS, AGENT_TOOL_DURATION, AGENT_TOOL_ERRORS, AGENT_TURN_DURATION,
-    AGENT_TURN_REQUESTS,
+    AGENT_TURN_REQUESTS, ToolCallTrace,
 };
 use crate::{
     AccumulatingStream, AgentStreamContext, Anthropic, CacheControlEphemeral, ContentBlock,
     ContentBlockDelta, Error, KnownModel, Message, MessageCreateParams, MessageParam,
     MessageParamContent, MessageRole, MessageStreamEvent, Metadata, Model, Renderer, StopReason,
-    StreamContext, SystemPrompt, ThinkingConfig, ToolBash20241022, ToolBash20250124, ToolChoice,
-    ToolParam, ToolResultBlock, ToolResultBlockContent, ToolTextEditor20250124,
+    StreamContext, SystemPrompt, ThinkingConfig, TokenRates, ToolBash20241022, ToolBash20250124,
+    ToolChoice, ToolParam, ToolResultBlock, ToolResultBlockContent, ToolTextEditor20250124,
     ToolTextEditor20250429, ToolTextEditor20250728, ToolUnionParam, ToolUseBlock, Usage,
     WebSearchTool20250305, push_or_merge_message,
 };
@@ -121,6 +124,35 @@ pub trait Tool<A: Agent>: Send + Sync {
     fn callback(&self) -> Box<dyn ToolCallback<A> + '_>;
     /// Converts the tool to a parameter format for the API.
     fn to_param(&self) -> ToolUnionParam;
+    /// Returns true if a successful call to this tool ends the turn.
+    ///
+    /// Use this for tools like `finish` that the model calls to hand back control.  Once every
+    /// tool in the response has run and the results are appended to the conversation, the turn
+    /// stops through [`Agent::handle_end_turn`] instead of asking the model to continue.  A
+    /// call whose result has `is_error` set does not end the turn.
+    fn ends_turn(&self) -> bool {
+        false
+    }
+
+    /// Returns a human-readable summary of the tool: its name, description, and the
+    /// pretty-printed input schema sent to the API.
+    fn describe(&self) -> String {
+        let param = self.to_param();
+        let description = match &param {
+            ToolUnionParam::CustomTool(tool) => tool.description.as_deref(),
+            _ => None,
+        };
+        let schema = match param.input_schema_json() {
+            serde_json::Value::Null => "(built-in)".to_string(),
+            schema => serde_json::to_string_pretty(&schema).unwrap_or_else(|_| schema.to_string()),
+        };
+        format!(
+            "name: {}\ndescription: {}\nschema: {}",
+            self.name(),
+            description.unwrap_or("(none)"),
+            schema
+        )
+    }
 }
 
 struct ToolNotFound(String);
@@ -210,12 +242,12 @@ impl<A: Agent> ToolCallback<A> for BashCallback {
         tool_use: &ToolUseBlock,
     ) -> Box<dyn IntermediateToolResult> {
         #[derive(serde::Deserialize)]
-        struct BashTool {
-            command: String,
+        struct BashTool<'a> {
+            command: &'a str,
             #[serde(default)]
             restart: bool,
         }
-        let bash: BashTool = match serde_json::from_value(tool_use.input.clone()) {
+        let bash: BashTool = match tool_use.parse_input() {
             Ok(input) => input,
             Err(err) => {
                 return Box::new(ControlFlow::Continue(Err(ToolResultBlock {
@@ -226,7 +258,7 @@ impl<A: Agent> ToolCallback<A> for BashCallback {
                 })));
             }
         };
-        match agent.bash(&bash.command, bash.restart).await {
+        match agent.bash(bash.command, bash.restart).await {
             Ok(answer) => Box::new(ControlFlow::Continue(Ok(ToolResultBlock {
                 tool_use_id: tool_use.id.clone(),
                 content: Some(ToolResultBlockContent::String(answer.to_string())),
@@ -347,10 +379,10 @@ impl<A: Agent> ToolCallback<A> for SearchFilesystemCallback {
         tool_use: &ToolUseBlock,
     ) -> Box<dyn IntermediateToolResult> {
         #[derive(serde::Deserialize)]
-        struct SearchTool {
-            query: String,
+        struct SearchTool<'a> {
+            query: &'a str,
         }
-        let search: SearchTool = match serde_json::from_value(tool_use.input.clone()) {
+        let search: SearchTool = match tool_use.parse_input() {
             Ok(input) => input,
             Err(err) => {
                 return Box::new(ControlFlow::Continue(Err(ToolResultBlock {
@@ -361,7 +393,7 @@ impl<A: Agent> ToolCallback<A> for SearchFilesystemCallback {
                 })));
             }
         };
-        match agent.search(&search.query).await {
+        match agent.search(search.query).await {
             Ok(answer) => Box::new(ControlFlow::Continue(Ok(ToolResultBlock {
                 tool_use_id: tool_use.id.clone(),
                 content: Some(ToolResultBlockContent::String(answer.to_string())),
@@ -494,6 +526,322 @@ impl<A: Agent> Tool<A> for ToolSearchFileSystem {
     }
 }
 
+/// Creates a custom tool that runs `handler` on the tool's input.
+///
+/// `handler` receives the `input` of each tool use.  `Ok` text becomes a successful tool result
+/// and `Err` text an error result, so simple function tools need no [`Tool`] or
+/// [`ToolCallback`] implementation.  The handler runs in the compute phase; the apply phase
+/// only hands back what it produced.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use claudius::{Agent, Tool, fn_tool};
+/// struct Echo;
+///
+/// #[async_trait::async_trait]
+/// impl Agent for Echo {
+///     async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+///         vec![Arc::from(fn_tool(
+///             "echo",
+///             "Echo the text back.",
+///             serde_json::json!({
+///                 "type": "object",
+///                 "properties": {"text": {"type": "string"}},
+///                 "required": ["text"]
+///             }),
+///             |input| {
+///                 input["text"]
+///                     .as_str()
+///                     .map(str::to_string)
+///                     .ok_or_else(|| "text is required".to_string())
+///             },
+///         ))]
+///     }
+/// }
+/// ```
+pub fn fn_tool<A, F>(
+    name: impl Into<String>,
+    description: impl Into<String>,
+    input_schema: serde_json::Value,
+    handler: F,
+) -> Box<dyn Tool<A>>
+where
+    A: Agent,
+    F: Fn(serde_json::Value) -> Result<String, String> + Send + Sync + 'static,
+{
+    Box::new(FnTool {
+        name: name.into(),
+        description: description.into(),
+        input_schema,
+        handler,
+    })
+}
+
+struct FnTool<F> {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+    handler: F,
+}
+
+impl<A, F> Tool<A> for FnTool<F>
+where
+    A: Agent,
+    F: Fn(serde_json::Value) -> Result<String, String> + Send + Sync,
+{
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn callback(&self) -> Box<dyn ToolCallback<A> + '_> {
+        Box::new(FnToolCallback::new(|_: &A, input| {
+            std::future::ready((self.handler)(input))
+        }))
+    }
+
+    fn to_param(&self) -> ToolUnionParam {
+        ToolUnionParam::CustomTool(
+            ToolParam::new(self.name.clone(), self.input_schema.clone())
+                .with_description(self.description.clone()),
+        )
+    }
+}
+
+/// Adapts an async `handler` over the agent and the tool input into a [`ToolCallback`].
+///
+/// Backs [`fn_tool`], [`fn_tool_stateful`], and [`CustomToolWithHandler`]:  `Ok` text becomes the
+/// tool result and `Err` text becomes an error result.
+struct FnToolCallback<H, Fut> {
+    handler: H,
+    _future: PhantomData<fn() -> Fut>,
+}
+
+impl<H, Fut> FnToolCallback<H, Fut> {
+    fn new<A>(handler: H) -> Self
+    where
+        H: Fn(&A, serde_json::Value) -> Fut,
+    {
+        Self {
+            handler,
+            _future: PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<A, H, Fut> ToolCallback<A> for FnToolCallback<H, Fut>
+where
+    A: Agent,
+    H: Fn(&A, serde_json::Value) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, String>> + Send,
+{
+    async fn compute_tool_result(
+        &self,
+        _client: &Anthropic,
+        agent: &A,
+        tool_use: &ToolUseBlock,
+    ) -> Box<dyn IntermediateToolResult> {
+        let result: ToolResult = match (self.handler)(agent, tool_use.input.clone()).await {
+            Ok(text) => ControlFlow::Continue(Ok(ToolResultBlock::ok(tool_use.id.clone(), text))),
+            Err(message) => {
+                ControlFlow::Continue(Err(ToolResultBlock::error(tool_use.id.clone(), message)))
+            }
+        };
+        Box::new(result)
+    }
+
+    async fn apply_tool_result(
+        &self,
+        _client: &Anthropic,
+        _agent: &mut A,
+        _tool_use: &ToolUseBlock,
+        intermediate: Box<dyn IntermediateToolResult>,
+    ) -> ToolResult {
+        let Some(intermediate) = intermediate.as_any().downcast_ref::<ToolResult>() else {
+            return ControlFlow::Break(Error::unknown(
+                "intermediate tool result fails to deserialize",
+            ));
+        };
+        intermediate.clone()
+    }
+}
+
+/// Creates a custom tool whose async `handler` can read the agent.
+///
+/// Like [`fn_tool`], but `handler` also receives `&A` during the compute phase, so it can
+/// consult agent state without mutating it.  The returned future cannot borrow the agent;
+/// copy what it needs out of `&A` before the `async` block.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use claudius::{Agent, Tool, fn_tool_stateful};
+/// struct Greeter {
+///     name: String,
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl Agent for Greeter {
+///     async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+///         vec![Arc::from(fn_tool_stateful(
+///             "whoami",
+///             "Report the assistant's name.",
+///             serde_json::json!({"type": "object"}),
+///             |agent: &Greeter, _input| {
+///                 let name = agent.name.clone();
+///                 async move { Ok(name) }
+///             },
+///         ))]
+///     }
+/// }
+/// ```
+pub fn fn_tool_stateful<A, F, Fut>(
+    name: impl Into<String>,
+    description: impl Into<String>,
+    input_schema: serde_json::Value,
+    handler: F,
+) -> Box<dyn Tool<A>>
+where
+    A: Agent,
+    F: Fn(&A, serde_json::Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String, String>> + Send + 'static,
+{
+    Box::new(StatefulFnTool {
+        name: name.into(),
+        description: description.into(),
+        input_schema,
+        handler,
+        _future: PhantomData,
+    })
+}
+
+struct StatefulFnTool<F, Fut> {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+    handler: F,
+    _future: PhantomData<fn() -> Fut>,
+}
+
+impl<A, F, Fut> Tool<A> for StatefulFnTool<F, Fut>
+where
+    A: Agent,
+    F: Fn(&A, serde_json::Value) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, String>> + Send,
+{
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn callback(&self) -> Box<dyn ToolCallback<A> + '_> {
+        Box::new(FnToolCallback::new::<A>(&self.handler))
+    }
+
+    fn to_param(&self) -> ToolUnionParam {
+        ToolUnionParam::CustomTool(
+            ToolParam::new(self.name.clone(), self.input_schema.clone())
+                .with_description(self.description.clone()),
+        )
+    }
+}
+
+/// A custom tool definition paired with the async handler that runs it.
+///
+/// [`fn_tool`] builds the [`ToolParam`] for you; this adapter takes one that was built
+/// elsewhere, such as the `ToolUnionParam::CustomTool` handed to the combinators, so the same
+/// definition can be registered on an [`Agent`].  Every field of the param, including `strict`
+/// and `cache_control`, is sent unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use claudius::{Agent, CustomToolWithHandler, Tool, ToolUnionParam};
+/// struct Shouter;
+///
+/// #[async_trait::async_trait]
+/// impl Agent for Shouter {
+///     async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+///         let shout = ToolUnionParam::new_custom_tool(
+///             "shout".to_string(),
+///             serde_json::json!({
+///                 "type": "object",
+///                 "properties": {"text": {"type": "string"}}
+///             }),
+///         );
+///         let handler = |input: serde_json::Value| async move {
+///             Ok(input["text"].as_str().unwrap_or_default().to_uppercase())
+///         };
+///         vec![Arc::new(
+///             CustomToolWithHandler::from_union(shout, handler).unwrap(),
+///         )]
+///     }
+/// }
+/// ```
+pub struct CustomToolWithHandler<F, Fut> {
+    param: ToolParam,
+    handler: F,
+    _future: PhantomData<fn() -> Fut>,
+}
+
+impl<F, Fut> CustomToolWithHandler<F, Fut>
+where
+    F: Fn(serde_json::Value) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, String>> + Send,
+{
+    /// Pairs `param` with the `handler` that computes its results.
+    ///
+    /// The handler receives the tool input; `Ok` text becomes the tool result and `Err` text
+    /// becomes an error result.
+    pub fn new(param: ToolParam, handler: F) -> Self {
+        Self {
+            param,
+            handler,
+            _future: PhantomData,
+        }
+    }
+
+    /// Pairs a `ToolUnionParam::CustomTool` with `handler`.
+    ///
+    /// Returns a validation error for any other kind of tool.
+    pub fn from_union(tool: ToolUnionParam, handler: F) -> Result<Self, Error> {
+        match tool {
+            ToolUnionParam::CustomTool(param) => Ok(Self::new(param, handler)),
+            _ => Err(Error::validation(
+                "only custom tools can be paired with a handler",
+                Some("tool".to_string()),
+            )),
+        }
+    }
+
+    /// Returns the tool definition sent to the API.
+    pub fn param(&self) -> &ToolParam {
+        &self.param
+    }
+}
+
+impl<A, F, Fut> Tool<A> for CustomToolWithHandler<F, Fut>
+where
+    A: Agent,
+    F: Fn(serde_json::Value) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, String>> + Send,
+{
+    fn name(&self) -> String {
+        self.param.name.clone()
+    }
+
+    fn callback(&self) -> Box<dyn ToolCallback<A> + '_> {
+        Box::new(FnToolCallback::new(|_: &A, input| (self.handler)(input)))
+    }
+
+    fn to_param(&self) -> ToolUnionParam {
+        ToolUnionParam::CustomTool(self.param.clone())
+    }
+}
+
 ////////////////////////////////////////////// Budget //////////////////////////////////////////////
 
 /// # Budget Management System
@@ -747,6 +1095,8 @@ impl<A: Agent> Tool<A> for ToolSearchFileSystem {
 /// - Cache creation tokens: The cost for creating prompt caches
 /// - Cache read tokens: The reduced cost for reading from prompt caches
 ///
+/// Web search requests can also be charged per request; see [`Budget::with_web_search_rate`].
+///
 /// # Thread Safety
 ///
 /// `Budget` is designed for concurrent access across multiple threads or async tasks.
@@ -786,6 +1136,9 @@ pub struct Budget {
     output_token_rate_micro_cents: u64,
     cache_creation_token_rate_micro_cents: u64,
     cache_read_token_rate_micro_cents: u64,
+    web_search_request_rate_micro_cents: u64,
+    /// Woken whenever an allocation returns unused budget.
+    released: Arc<tokio::sync::Notify>,
 }
 
 /// Token categories used for cost accounting.
@@ -831,9 +1184,30 @@ impl Budget {
             output_token_rate_micro_cents,
             cache_creation_token_rate_micro_cents,
             cache_read_token_rate_micro_cents,
+            web_search_request_rate_micro_cents: 0,
+            released: Arc::new(tokio::sync::Notify::new()),
         }
     }
 
+    /// Charges `micro_cents` for each web search request the server reports in
+    /// [`Usage::server_tool_use`](crate::Usage::server_tool_use).
+    ///
+    /// Budgets charge nothing for server tools unless a rate is set here.  Allocations are
+    /// still sized by tokens alone, so a turn with many searches can exhaust its allocation.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use claudius::{Budget, ServerToolUsage, Usage};
+    /// // $10 per thousand searches is one cent, or 1,000,000 micro-cents, per search.
+    /// let budget = Budget::new_flat_rate(100_000_000, 1).with_web_search_rate(1_000_000);
+    /// let usage = Usage::new(10, 10).with_server_tool_use(ServerToolUsage::new(2));
+    /// assert_eq!(budget.calculate_cost(&usage), 2_000_020);
+    /// ```
+    pub fn with_web_search_rate(mut self, micro_cents: u64) -> Self {
+        self.web_search_request_rate_micro_cents = micro_cents;
+        self
+    }
+
     /// Creates a new budget with a simplified flat rate per token.
     ///
     /// # Arguments
@@ -893,6 +1267,25 @@ impl Budget {
         Self::new_flat_rate(budget_micro_cents, token_rate_micro_cents)
     }
 
+    /// Creates a budget from dollars using the list price of `model`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use claudius::{Budget, KnownModel};
+    /// let budget = Budget::for_model(5.0, KnownModel::ClaudeSonnet45);
+    /// assert_eq!(budget.total_micro_cents(), 500_000_000);
+    /// ```
+    pub fn for_model(budget_dollars: f64, model: KnownModel) -> Self {
+        let rates = model.default_pricing();
+        Self::from_dollars_with_rates(
+            budget_dollars,
+            rates.input_micro_cents,
+            rates.output_micro_cents,
+            rates.cache_creation_micro_cents,
+            rates.cache_read_micro_cents,
+        )
+    }
+
     /// Legacy constructor for backward compatibility - creates a token-based budget.
     /// This converts tokens to micro-cents using a default rate.
     #[deprecated(note = "Use new_with_rates or new_flat_rate instead")]
@@ -949,20 +1342,36 @@ impl Budget {
     /// of tokens with extremely high rates. All practical API usage scenarios are
     /// well within safe bounds.
     pub fn calculate_cost(&self, usage: &crate::Usage) -> u64 {
-        let input_cost =
-            (usage.input_tokens.max(0) as u64).saturating_mul(self.input_token_rate_micro_cents);
-        let output_cost =
-            (usage.output_tokens.max(0) as u64).saturating_mul(self.output_token_rate_micro_cents);
-        let cache_creation_cost = (usage.cache_creation_input_tokens.unwrap_or(0).max(0) as u64)
-            .saturating_mul(self.cache_creation_token_rate_micro_cents);
-        let cache_read_cost = (usage.cache_read_input_tokens.unwrap_or(0).max(0) as u64)
-            .saturating_mul(self.cache_read_token_rate_micro_cents);
-
-        input_cost
-            .checked_add(output_cost)
-            .and_then(|sum| sum.checked_add(cache_creation_cost))
-            .and_then(|sum| sum.checked_add(cache_read_cost))
-            .unwrap_or(u64::MAX)
+        usage.cost(&self.rates())
+    }
+
+    /// Calculates the cost of `usage` in dollars.
+    ///
+    /// This is [`calculate_cost`](Self::calculate_cost) converted from micro-cents, for
+    /// display and reporting; budget accounting stays in integer micro-cents.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use claudius::{Budget, KnownModel, Usage};
+    ///
+    /// let budget = Budget::for_model(1.0, KnownModel::ClaudeHaiku45);
+    /// let cost = budget.cost_dollars(&Usage::new(1_000_000, 0));
+    /// assert!((cost - 1.0).abs() < 1e-9);
+    /// ```
+    pub fn cost_dollars(&self, usage: &crate::Usage) -> f64 {
+        self.calculate_cost(usage) as f64 / Self::MICRO_CENTS_PER_DOLLAR
+    }
+
+    /// Returns the per-token and per-request rates this budget charges.
+    pub fn rates(&self) -> TokenRates {
+        TokenRates::new(
+            self.input_token_rate_micro_cents,
+            self.output_token_rate_micro_cents,
+            self.cache_creation_token_rate_micro_cents,
+            self.cache_read_token_rate_micro_cents,
+        )
+        .with_web_search_request_micro_cents(self.web_search_request_rate_micro_cents)
     }
 
     /// Attempts to allocate cost for the expected maximum tokens from the budget.
@@ -983,6 +1392,66 @@ impl Budget {
     /// }
     /// ```
     pub fn allocate(&self, max_tokens: u32) -> Option<BudgetAllocation<'_>> {
+        let max_cost = self.reserve(max_tokens)?;
+        let remaining_micro_cents = Arc::clone(&self.remaining_micro_cents);
+        Some(BudgetAllocation {
+            remaining_micro_cents,
+            allocated_micro_cents: max_cost,
+            budget: self,
+        })
+    }
+
+    /// Attempts to allocate cost for the expected maximum tokens, returning an allocation
+    /// that owns a reference to the budget.
+    ///
+    /// Unlike [`Budget::allocate`], the returned allocation does not borrow the budget, so it
+    /// can be moved into a spawned task.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use claudius::Budget;
+    /// let budget = Arc::new(Budget::from_dollars_flat_rate(1.0, 100));
+    /// let allocation = budget.allocate_owned(50).unwrap();
+    /// assert_eq!(allocation.remaining_micro_cents(), 5_000);
+    /// ```
+    pub fn allocate_owned(self: &Arc<Self>, max_tokens: u32) -> Option<OwnedBudgetAllocation> {
+        let max_cost = self.reserve(max_tokens)?;
+        Some(OwnedBudgetAllocation {
+            allocated_micro_cents: max_cost,
+            budget: Arc::clone(self),
+        })
+    }
+
+    /// Like [`Budget::allocate`], but waits up to `timeout` for other allocations to return
+    /// enough budget.
+    ///
+    /// The waiter retries each time an allocation is dropped, so a shared budget throttles
+    /// queued work instead of failing it.  Returns `None` once `timeout` elapses, or
+    /// immediately if `max_tokens` could not fit even in the budget's total.
+    pub async fn allocate_wait(
+        &self,
+        max_tokens: u32,
+        timeout: Duration,
+    ) -> Option<BudgetAllocation<'_>> {
+        if self.calculate_max_cost_for_tokens(max_tokens) > self.total_micro_cents {
+            return None;
+        }
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Register for the next release before checking, so a drop in between is not missed.
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            if let Some(allocation) = self.allocate(max_tokens) {
+                return Some(allocation);
+            }
+            tokio::time::timeout_at(deadline, released).await.ok()?;
+        }
+    }
+
+    /// Reserves the maximum cost of `max_tokens` and returns the reserved micro-cents.
+    fn reserve(&self, max_tokens: u32) -> Option<u64> {
         let max_cost = self.calculate_max_cost_for_tokens(max_tokens);
         loop {
             let witness = self.remaining_micro_cents.load(Ordering::Relaxed);
@@ -997,18 +1466,30 @@ impl Budget {
                     )
                     .is_ok()
             {
-                let remaining_micro_cents = Arc::clone(&self.remaining_micro_cents);
-                return Some(BudgetAllocation {
-                    remaining_micro_cents,
-                    allocated_micro_cents: max_cost,
-                    budget: self,
-                });
+                return Some(max_cost);
             } else if witness < max_cost {
                 return None;
             }
         }
     }
 
+    /// Converts micro-cents into a conservative token count using the highest token rate.
+    fn tokens_for_micro_cents(&self, micro_cents: u64) -> u32 {
+        let highest_rate = self
+            .output_token_rate_micro_cents
+            .max(self.input_token_rate_micro_cents)
+            .max(self.cache_creation_token_rate_micro_cents)
+            .max(self.cache_read_token_rate_micro_cents);
+        if highest_rate > 0 {
+            std::cmp::min(
+                micro_cents.checked_div(highest_rate).unwrap_or(0),
+                u32::MAX as u64,
+            ) as u32
+        } else {
+            0
+        }
+    }
+
     /// Calculates the maximum possible cost for the given number of tokens.
     ///
     /// This method uses the highest token rate among all configured rates to
@@ -1149,6 +1630,8 @@ impl Clone for Budget {
             output_token_rate_micro_cents: self.output_token_rate_micro_cents,
             cache_creation_token_rate_micro_cents: self.cache_creation_token_rate_micro_cents,
             cache_read_token_rate_micro_cents: self.cache_read_token_rate_micro_cents,
+            web_search_request_rate_micro_cents: self.web_search_request_rate_micro_cents,
+            released: Arc::clone(&self.released),
         }
     }
 }
@@ -1306,22 +1789,8 @@ impl<'a> BudgetAllocation<'a> {
     /// using the highest token rate. The actual number of tokens you can
     /// consume may be higher if you use cheaper token types.
     pub fn remaining_tokens(&self) -> u32 {
-        let highest_rate = self
-            .budget
-            .output_token_rate_micro_cents
-            .max(self.budget.input_token_rate_micro_cents)
-            .max(self.budget.cache_creation_token_rate_micro_cents)
-            .max(self.budget.cache_read_token_rate_micro_cents);
-        if highest_rate > 0 {
-            std::cmp::min(
-                self.allocated_micro_cents
-                    .checked_div(highest_rate)
-                    .unwrap_or(0),
-                u32::MAX as u64,
-            ) as u32
-        } else {
-            0
-        }
+        self.budget
+            .tokens_for_micro_cents(self.allocated_micro_cents)
     }
 
     /// Returns the remaining budget within this allocation in micro-cents.
@@ -1424,6 +1893,58 @@ impl Drop for BudgetAllocation<'_> {
     fn drop(&mut self) {
         self.remaining_micro_cents
             .fetch_add(self.allocated_micro_cents, Ordering::Relaxed);
+        self.budget.released.notify_waiters();
+    }
+}
+
+/// A budget allocation that holds an `Arc<Budget>` rather than borrowing it.
+///
+/// Created by [`Budget::allocate_owned`].  It behaves like [`BudgetAllocation`], including
+/// returning unused budget on drop, but is `'static` and can be moved across tasks.
+#[derive(Debug)]
+pub struct OwnedBudgetAllocation {
+    allocated_micro_cents: u64,
+    budget: Arc<Budget>,
+}
+
+impl OwnedBudgetAllocation {
+    /// Consumes budget from this allocation based on actual API token usage.
+    ///
+    /// Returns `false`, consuming nothing, if the cost exceeds the remaining allocation.
+    #[must_use]
+    pub fn consume_usage(&mut self, usage: &crate::Usage) -> bool {
+        let actual_cost = self.budget.calculate_cost(usage);
+        if actual_cost <= self.allocated_micro_cents {
+            self.allocated_micro_cents -= actual_cost;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns a conservative estimate of the tokens remaining in this allocation.
+    pub fn remaining_tokens(&self) -> u32 {
+        self.budget
+            .tokens_for_micro_cents(self.allocated_micro_cents)
+    }
+
+    /// Returns the remaining budget within this allocation in micro-cents.
+    pub fn remaining_micro_cents(&self) -> u64 {
+        self.allocated_micro_cents
+    }
+
+    /// Returns the budget this allocation was drawn from.
+    pub fn budget(&self) -> &Arc<Budget> {
+        &self.budget
+    }
+}
+
+impl Drop for OwnedBudgetAllocation {
+    fn drop(&mut self) {
+        self.budget
+            .remaining_micro_cents
+            .fetch_add(self.allocated_micro_cents, Ordering::Relaxed);
+        self.budget.released.notify_waiters();
     }
 }
 
@@ -1470,6 +1991,21 @@ pub trait FileSystem: Send + Sync {
         view_range: Option<(u32, u32)>,
     ) -> Result<String, std::io::Error>;
 
+    /// Views a file like [`FileSystem::view`], truncating output longer than `max_bytes`.
+    ///
+    /// Truncated output keeps the first `max_bytes` bytes (backing up to a character boundary)
+    /// followed by a `...[truncated N bytes]...` marker, so the model knows to narrow its
+    /// `view_range`.
+    async fn view_limited(
+        &self,
+        path: &str,
+        view_range: Option<(u32, u32)>,
+        max_bytes: usize,
+    ) -> Result<String, std::io::Error> {
+        let content = self.view(path, view_range).await?;
+        Ok(truncate_output(content, max_bytes))
+    }
+
     /// Replaces occurrences of a string in a file.
     async fn str_replace(
         &self,
@@ -1517,28 +2053,222 @@ pub trait FileSystem: Send + Sync {
     async fn create(&self, path: &str, file_text: &str) -> Result<String, std::io::Error>;
 }
 
-/////////////////////////////////////////////// Agent //////////////////////////////////////////////
+/////////////////////////////////////////// BashSession ///////////////////////////////////////////
 
-/// Aggregated results from an agent turn.
-#[derive(Debug, Clone)]
-pub struct TurnOutcome {
-    /// The reason the turn finished.
-    pub stop_reason: StopReason,
-    /// Usage accumulated across all requests in the turn.
-    pub usage: Usage,
-    /// Number of API requests made in the turn.
-    pub request_count: u64,
+/// A persistent `bash` process for the bash tool.
+///
+/// Commands run in the same shell, so working directory, variables, and functions carry over
+/// from one command to the next.  Return one from [`Agent::bash_session`] to have the default
+/// [`Agent::bash`] use it; the shell is started on first use and restarted when the model asks
+/// for it.
+#[derive(Debug)]
+pub struct BashSession {
+    shell: tokio::sync::Mutex<Option<BashProcess>>,
+    commands: AtomicU64,
+    timeout: Duration,
 }
 
-/// Usage and request counts accumulated for a single step in a turn.
-#[derive(Debug, Clone)]
-pub struct TurnStep {
-    /// Usage accumulated for the step.
+/// How long a [`BashSession`] command may run before the shell is killed.
+const DEFAULT_BASH_TIMEOUT: Duration = Duration::from_secs(120);
+
+impl Default for BashSession {
+    fn default() -> Self {
+        Self {
+            shell: tokio::sync::Mutex::default(),
+            commands: AtomicU64::default(),
+            timeout: DEFAULT_BASH_TIMEOUT,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BashProcess {
+    _child: tokio::process::Child,
+    stdin: tokio::process::ChildStdin,
+    stdout: tokio::io::BufReader<tokio::process::ChildStdout>,
+}
+
+impl BashSession {
+    /// Creates a session; the shell is not started until the first command.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long each command may run; the default is two minutes.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs `command` in the shell and returns its combined stdout and stderr.
+    ///
+    /// When `restart` is set the current shell is discarded first, and an empty `command`
+    /// only restarts the shell.  A non-zero exit status is appended to the output.  The
+    /// command's stdin is `/dev/null`.  If the shell exits (for example, because the command
+    /// ran `exit`) or the command outlives the session's timeout, an error is returned and the
+    /// next command starts a new shell.
+    pub async fn run(&self, command: &str, restart: bool) -> Result<String, std::io::Error> {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+        let mut shell = self.shell.lock().await;
+        if restart {
+            *shell = None;
+            if command.trim().is_empty() {
+                return Ok("bash tool has been restarted".to_string());
+            }
+        }
+        // The process is only put back once the command completes, so any failure leaves the
+        // session to start a fresh shell on the next command.
+        let mut process = match shell.take() {
+            Some(process) => process,
+            None => BashProcess::spawn()?,
+        };
+
+        let marker = format!(
+            "__claudius_bash_{}_{}__",
+            std::process::id(),
+            self.commands.fetch_add(1, Ordering::Relaxed)
+        );
+        let script =
+            format!("{{\n{command}\n}} </dev/null 2>&1\nprintf '\\n{marker}%d\\n' \"$?\"\n");
+        process.stdin.write_all(script.as_bytes()).await?;
+
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut output = String::new();
+        loop {
+            let mut line = String::new();
+            let Ok(read) =
+                tokio::time::timeout_at(deadline, process.stdout.read_line(&mut line)).await
+            else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!(
+                        "command timed out after {:?} and the shell was restarted; output so far:\n{output}",
+                        self.timeout
+                    ),
+                ));
+            };
+            if read? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("bash exited before finishing; output so far:\n{output}"),
+                ));
+            }
+            if let Some(status) = line.trim_end().strip_prefix(&marker) {
+                // Drop the newline printed ahead of the marker, then the command's own
+                // trailing newline.
+                for _ in 0..2 {
+                    if output.ends_with('\n') {
+                        output.pop();
+                    }
+                }
+                if status != "0" {
+                    output.push_str(&format!("\n[exit status {status}]"));
+                }
+                *shell = Some(process);
+                return Ok(output);
+            }
+            output.push_str(&line);
+        }
+    }
+}
+
+impl BashProcess {
+    fn spawn() -> Result<Self, std::io::Error> {
+        let mut child = tokio::process::Command::new("bash")
+            .arg("--noprofile")
+            .arg("--norc")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(std::io::Error::other("bash started without piped stdio"));
+        };
+        Ok(Self {
+            _child: child,
+            stdin,
+            stdout: tokio::io::BufReader::new(stdout),
+        })
+    }
+}
+
+/////////////////////////////////////////////// Agent //////////////////////////////////////////////
+
+/// Aggregated results from an agent turn.
+#[derive(Debug, Clone)]
+pub struct TurnOutcome {
+    /// The reason the turn finished.
+    pub stop_reason: StopReason,
+    /// Usage accumulated across all requests in the turn.
+    pub usage: Usage,
+    /// Number of API requests made in the turn.
+    pub request_count: u64,
+}
+
+/// Usage and request counts accumulated for a single step in a turn.
+#[derive(Debug, Clone)]
+pub struct TurnStep {
+    /// Usage accumulated for the step.
     pub usage: Usage,
     /// Number of API requests made in the step.
     pub request_count: u64,
 }
 
+/// Agent settings read at the start of a turn and used for every request in it.
+///
+/// The thinking configuration is read once per turn.  The tools are brought up to date before
+/// each step; see [`Agent::tools_changed`].
+#[derive(Debug, Clone, Default)]
+pub struct TurnConfig {
+    /// The thinking configuration sent with each request.
+    pub thinking: Option<ThinkingConfig>,
+    /// The agent's tools in their API form.
+    pub tools: Vec<ToolUnionParam>,
+    /// A hash of the tool names `tools` was built from.
+    tool_set: Option<u64>,
+    /// Whether `tools` was read after the last step, so the next step can use it as is.
+    tools_fresh: bool,
+}
+
+impl TurnConfig {
+    /// Reads the thinking configuration and tools of `agent`.
+    pub async fn for_agent<A: Agent>(agent: &A) -> Self {
+        let mut turn = Self {
+            thinking: agent.thinking().await,
+            ..Self::default()
+        };
+        turn.refresh_tools(agent).await;
+        turn.tools_fresh = true;
+        turn
+    }
+
+    /// Re-reads the tools of `agent`.
+    ///
+    /// The definitions are rebuilt unless [`Agent::tools_changed`] returns `false` and the
+    /// tool names hash the same as when they were last built.
+    pub async fn refresh_tools<A: Agent>(&mut self, agent: &A) {
+        let tools = agent.tools().await;
+        let mut hasher = DefaultHasher::new();
+        for tool in &tools {
+            tool.name().hash(&mut hasher);
+        }
+        let tool_set = hasher.finish();
+        if agent.tools_changed() || self.tool_set != Some(tool_set) {
+            self.tools = tools.iter().map(|tool| tool.to_param()).collect();
+            self.tool_set = Some(tool_set);
+        }
+    }
+
+    /// Brings the tools up to date for the next step, unless they were just read.
+    async fn begin_step<A: Agent>(&mut self, agent: &A) {
+        if !std::mem::take(&mut self.tools_fresh) {
+            self.refresh_tools(agent).await;
+        }
+    }
+}
+
 /// Trait for implementing agents that interact with the Anthropic API.
 ///
 /// Agents encapsulate conversation logic, tool use, and configuration for
@@ -1565,6 +2295,10 @@ pub trait Agent: Send + Sync + Sized {
     }
 
     /// Returns optional metadata for requests.
+    ///
+    /// This is attached to every request the agent makes, so an agent that serves a single
+    /// user can set [`Metadata::user_id`] here once.  Requests fail validation if the ID is
+    /// longer than [`Metadata::MAX_USER_ID_LENGTH`].
     async fn metadata(&self) -> Option<Metadata> {
         None
     }
@@ -1599,6 +2333,16 @@ pub trait Agent: Send + Sync + Sized {
         vec![]
     }
 
+    /// Returns whether the tool set may change between steps of a turn.
+    ///
+    /// When this returns `true`, the default turn rebuilds the tool definitions from
+    /// [`Agent::tools`] before every step.  Agents whose tools are fixed can return `false`:
+    /// the definitions built at the start of the turn are then reused for as long as the tool
+    /// names stay the same.
+    fn tools_changed(&self) -> bool {
+        true
+    }
+
     /// Returns the top-k sampling parameter.
     async fn top_k(&self) -> Option<u32> {
         None
@@ -1614,16 +2358,60 @@ pub trait Agent: Send + Sync + Sized {
         None
     }
 
+    /// Returns the most output the default [`Agent::view`] returns for a single call.
+    ///
+    /// Defaults to `None`, which returns files in full.
+    async fn max_view_bytes(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the persistent shell used by the default [`Agent::bash`].
+    ///
+    /// Defaults to `None`, in which case the bash tool is unsupported.
+    async fn bash_session(&self) -> Option<&BashSession> {
+        None
+    }
+
+    /// Returns how many times the default turn resends a model request that failed with a
+    /// retryable error, such as a dropped connection or an overloaded server.
+    ///
+    /// This is on top of the client's own retries and also covers streams that fail after
+    /// they start.  The identical request is resent after an exponential backoff, and the
+    /// messages and hooks see only the attempt that succeeds.  Text a failed streaming attempt
+    /// already rendered stays on screen.  Defaults to 0.
+    async fn request_retries(&self) -> u32 {
+        0
+    }
+
     /// Handles the case when max tokens is reached.
     async fn handle_max_tokens(&self) -> Result<StopReason, Error> {
         Ok(StopReason::MaxTokens)
     }
 
+    /// Handles the case when the budget cannot cover another request.
+    ///
+    /// Called when the turn's allocation cannot be made, or when the tokens remaining in it
+    /// fall to the thinking reserve.  Defaults to [`Agent::handle_max_tokens`].
+    async fn handle_budget_exhausted(&self) -> Result<StopReason, Error> {
+        self.handle_max_tokens().await
+    }
+
     /// Handles the end of a conversation turn.
     async fn handle_end_turn(&self) -> Result<StopReason, Error> {
         Ok(StopReason::EndTurn)
     }
 
+    /// Handles a response that stopped with [`StopReason::PauseTurn`].
+    ///
+    /// The server pauses long-running turns, and the default turn resends the conversation so
+    /// the model can pick up where it left off.  `pauses` counts the pauses so far in this step,
+    /// including this one.  Return `ControlFlow::Break` with a stop reason to end the turn
+    /// instead, e.g. after too many pauses in a row.  Defaults to continuing.
+    async fn handle_pause_turn(&self, pauses: u32) -> Result<ControlFlow<StopReason>, Error> {
+        _ = pauses;
+        Ok(ControlFlow::Continue(()))
+    }
+
     /// Handles when a stop sequence is encountered.
     async fn handle_stop_sequence(&self, sequence: Option<String>) -> Result<StopReason, Error> {
         _ = sequence;
@@ -1631,11 +2419,29 @@ pub trait Agent: Send + Sync + Sized {
     }
 
     /// Handles when the model refuses to respond.
-    async fn handle_refusal(&self, resp: Message) -> Result<StopReason, Error> {
+    ///
+    /// `refusal_text` is [`Message::refusal_text`] of `resp`, which agents can log or surface
+    /// to explain the refusal.
+    async fn handle_refusal(
+        &self,
+        resp: Message,
+        refusal_text: Option<String>,
+    ) -> Result<StopReason, Error> {
         _ = resp;
+        _ = refusal_text;
         Ok(StopReason::Refusal)
     }
 
+    /// Hook called at the start of each step, before the request is built.
+    ///
+    /// Agents can push synthetic messages here, such as a "continue" prompt or an
+    /// observation.  Add them with [`push_or_merge_message`] so that a message with the same
+    /// role as the last one is merged into it rather than clobbering it.
+    async fn before_step(&mut self, messages: &mut Vec<MessageParam>) -> Result<(), Error> {
+        _ = messages;
+        Ok(())
+    }
+
     /// Hook called before sending a message create request.
     async fn hook_message_create_params(&self, req: &MessageCreateParams) -> Result<(), Error> {
         _ = req;
@@ -1648,6 +2454,61 @@ pub trait Agent: Send + Sync + Sized {
         Ok(())
     }
 
+    /// Decides whether the default turn runs the tools `resp` requested.
+    ///
+    /// Consulted after each response that stopped for [`StopReason::ToolUse`], before any tool
+    /// runs.  Returning `false` ends the turn with that stop reason and answers each tool use
+    /// with an error result, so `messages` can still be sent as-is.  Override this to break out
+    /// of loops, such as the same tool being called over and over;
+    /// [`Message::tool_use_count`], [`Message::content_block_count`], and
+    /// [`Message::has_thinking`] are cheap signals to build on.  Pauses are governed by
+    /// [`Agent::handle_pause_turn`] instead.  Defaults to `true`.
+    async fn should_continue(&self, resp: &Message) -> bool {
+        _ = resp;
+        true
+    }
+
+    /// Returns the prompt size, in tokens, past which the default turn compacts history.
+    ///
+    /// After each assistant response it records, whether or not the turn goes on, the default
+    /// turn calls [`Agent::compact_history`] if that request used more prompt tokens than this,
+    /// counting cache reads and writes as well as uncached input.  Defaults to `None`, which
+    /// never compacts.
+    async fn compaction_threshold(&self) -> Option<u32> {
+        None
+    }
+
+    /// Shrinks `messages` once the prompt has grown past [`Agent::compaction_threshold`].
+    ///
+    /// A typical implementation asks `client` to summarize the older messages and replaces
+    /// them with a single user message holding the summary.  Keep each tool use together with
+    /// its tool result, and keep the first message from the user.  Defaults to doing nothing.
+    async fn compact_history(
+        &self,
+        client: &Anthropic,
+        messages: &mut Vec<MessageParam>,
+    ) -> Result<(), Error> {
+        _ = client;
+        _ = messages;
+        Ok(())
+    }
+
+    /// Hook called just before a tool requested by the model starts running.
+    ///
+    /// Use this together with [`Agent::hook_tool_end`] to surface tool progress, such as
+    /// "running get_weather...", while a turn is in flight.
+    async fn hook_tool_start(&self, tool_use: &ToolUseBlock) {
+        _ = tool_use;
+    }
+
+    /// Hook called once a tool has finished, with the time it took to compute and apply.
+    ///
+    /// Called whether the tool succeeded, returned an error result, or stopped the turn.
+    async fn hook_tool_end(&self, tool_use: &ToolUseBlock, elapsed: Duration) {
+        _ = tool_use;
+        _ = elapsed;
+    }
+
     /// Takes a conversation turn, potentially making multiple API calls.
     async fn take_turn(
         &mut self,
@@ -1658,6 +2519,50 @@ pub trait Agent: Send + Sync + Sized {
         self.take_default_turn(client, messages, budget).await
     }
 
+    /// Resumes a conversation whose last assistant message may have unanswered tool calls.
+    ///
+    /// This is for picking up a saved session.  If the last message is from the assistant and
+    /// contains `tool_use` blocks, those tools are run through [`Agent::handle_tool_use`] and
+    /// their results appended before the turn continues with [`Agent::take_turn`]; the
+    /// assistant message itself is not sent again.  Otherwise this is just
+    /// [`Agent::take_turn`].
+    async fn resume(
+        &mut self,
+        client: &Anthropic,
+        messages: &mut Vec<MessageParam>,
+        budget: &Arc<Budget>,
+    ) -> Result<TurnOutcome, Error> {
+        let pending = match messages.last() {
+            Some(MessageParam {
+                role: MessageRole::Assistant,
+                content: MessageParamContent::Array(blocks),
+            }) if blocks.iter().any(ContentBlock::is_tool_use) => blocks.clone(),
+            _ => return self.take_turn(client, messages, budget).await,
+        };
+        let resp = Message::new(
+            "resumed".to_string(),
+            pending,
+            self.model().await,
+            Usage::new(0, 0),
+        );
+        match self.handle_tool_use(client, &resp).await {
+            ControlFlow::Continue(tool_results) => {
+                push_or_merge_message(
+                    messages,
+                    MessageParam::new(MessageParamContent::Array(tool_results), MessageRole::User),
+                );
+            }
+            ControlFlow::Break(res) => {
+                return res.map(|stop_reason| TurnOutcome {
+                    stop_reason,
+                    usage: Usage::new(0, 0),
+                    request_count: 0,
+                });
+            }
+        }
+        self.take_turn(client, messages, budget).await
+    }
+
     /// Takes a conversation turn, streaming output to the renderer.
     async fn take_turn_streaming(
         &mut self,
@@ -1694,7 +2599,7 @@ pub trait Agent: Send + Sync + Sized {
         let turn_start = Instant::now();
         let Some(mut tokens_rem) = budget.allocate(self.max_tokens().await) else {
             AGENT_TURN_DURATION.add(turn_start.elapsed().as_secs_f64());
-            let stop_reason = self.handle_max_tokens().await?;
+            let stop_reason = self.handle_budget_exhausted().await?;
             return Ok(TurnOutcome {
                 stop_reason,
                 usage: Usage::new(0, 0),
@@ -1702,13 +2607,16 @@ pub trait Agent: Send + Sync + Sized {
             });
         };
 
+        let mut turn = TurnConfig::for_agent(self).await;
+        let thinking_reserve = turn.thinking.map(|t| t.num_tokens()).unwrap_or(0);
         let mut usage_total = Usage::new(0, 0);
         let mut request_count: u64 = 0;
 
-        while tokens_rem.remaining_tokens()
-            > self.thinking().await.map(|t| t.num_tokens()).unwrap_or(0)
-        {
-            match self.step_turn(client, messages, &mut tokens_rem).await {
+        while tokens_rem.remaining_tokens() > thinking_reserve {
+            match self
+                .step_turn_with_turn(client, messages, &mut tokens_rem, &mut turn)
+                .await
+            {
                 ControlFlow::Continue(step) => {
                     usage_total = usage_total + step.usage;
                     request_count = request_count.saturating_add(step.request_count);
@@ -1723,7 +2631,7 @@ pub trait Agent: Send + Sync + Sized {
             }
         }
         AGENT_TURN_DURATION.add(turn_start.elapsed().as_secs_f64());
-        let stop_reason = self.handle_max_tokens().await?;
+        let stop_reason = self.handle_budget_exhausted().await?;
         Ok(TurnOutcome {
             stop_reason,
             usage: usage_total,
@@ -1744,7 +2652,7 @@ pub trait Agent: Send + Sync + Sized {
         renderer.start_agent(&context);
         let Some(mut tokens_rem) = budget.allocate(self.max_tokens().await) else {
             AGENT_TURN_DURATION.add(turn_start.elapsed().as_secs_f64());
-            let stop_reason = self.handle_max_tokens().await?;
+            let stop_reason = self.handle_budget_exhausted().await?;
             renderer.finish_agent(&context, Some(&stop_reason));
             return Ok(TurnOutcome {
                 stop_reason,
@@ -1753,14 +2661,21 @@ pub trait Agent: Send + Sync + Sized {
             });
         };
 
+        let mut turn = TurnConfig::for_agent(self).await;
+        let thinking_reserve = turn.thinking.map(|t| t.num_tokens()).unwrap_or(0);
         let mut usage_total = Usage::new(0, 0);
         let mut request_count: u64 = 0;
 
-        while tokens_rem.remaining_tokens()
-            > self.thinking().await.map(|t| t.num_tokens()).unwrap_or(0)
-        {
+        while tokens_rem.remaining_tokens() > thinking_reserve {
             match self
-                .step_turn_streaming(client, messages, &mut tokens_rem, renderer, &context)
+                .step_turn_streaming_with_turn(
+                    client,
+                    messages,
+                    &mut tokens_rem,
+                    &mut turn,
+                    renderer,
+                    &context,
+                )
                 .await
             {
                 ControlFlow::Continue(step) => {
@@ -1784,7 +2699,7 @@ pub trait Agent: Send + Sync + Sized {
             }
         }
         AGENT_TURN_DURATION.add(turn_start.elapsed().as_secs_f64());
-        let stop_reason = self.handle_max_tokens().await?;
+        let stop_reason = self.handle_budget_exhausted().await?;
         renderer.finish_agent(&context, Some(&stop_reason));
         Ok(TurnOutcome {
             stop_reason,
@@ -1794,16 +2709,25 @@ pub trait Agent: Send + Sync + Sized {
     }
 
     /// Executes a single step in a conversation turn.
+    ///
+    /// This reads a fresh [`TurnConfig`] for the one step.  The default turn loop calls
+    /// [`Agent::step_turn_with_turn`] instead, which shares one config across the turn.
     async fn step_turn(
         &mut self,
         client: &Anthropic,
         messages: &mut Vec<MessageParam>,
         tokens_rem: &mut BudgetAllocation,
     ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
-        self.step_default_turn(client, messages, tokens_rem).await
+        let mut turn = TurnConfig::for_agent(self).await;
+        self.step_turn_with_turn(client, messages, tokens_rem, &mut turn)
+            .await
     }
 
     /// Executes a single step in a conversation turn with streaming output.
+    ///
+    /// This reads a fresh [`TurnConfig`] for the one step.  The default turn loop calls
+    /// [`Agent::step_turn_streaming_with_turn`] instead, which shares one config across the
+    /// turn.
     async fn step_turn_streaming(
         &mut self,
         client: &Anthropic,
@@ -1812,10 +2736,45 @@ pub trait Agent: Send + Sync + Sized {
         renderer: &mut dyn Renderer,
         context: &AgentStreamContext,
     ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
-        self.step_default_turn_streaming(client, messages, tokens_rem, renderer, context)
+        let mut turn = TurnConfig::for_agent(self).await;
+        self.step_turn_streaming_with_turn(
+            client, messages, tokens_rem, &mut turn, renderer, context,
+        )
+        .await
+    }
+
+    /// Executes a single step in a conversation turn, using the config read for the turn.
+    ///
+    /// The default turn loop calls this once per step with the same `turn`.
+    async fn step_turn_with_turn(
+        &mut self,
+        client: &Anthropic,
+        messages: &mut Vec<MessageParam>,
+        tokens_rem: &mut BudgetAllocation,
+        turn: &mut TurnConfig,
+    ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
+        self.step_default_turn_with_turn(client, messages, tokens_rem, turn)
             .await
     }
 
+    /// Executes a single step with streaming output, using the config read for the turn.
+    ///
+    /// The default streaming turn loop calls this once per step with the same `turn`.
+    async fn step_turn_streaming_with_turn(
+        &mut self,
+        client: &Anthropic,
+        messages: &mut Vec<MessageParam>,
+        tokens_rem: &mut BudgetAllocation,
+        turn: &mut TurnConfig,
+        renderer: &mut dyn Renderer,
+        context: &AgentStreamContext,
+    ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
+        self.step_default_turn_streaming_with_turn(
+            client, messages, tokens_rem, turn, renderer, context,
+        )
+        .await
+    }
+
     /// Default implementation for executing a single step in a conversation turn.
     async fn step_default_turn(
         &mut self,
@@ -1823,7 +2782,9 @@ pub trait Agent: Send + Sync + Sized {
         messages: &mut Vec<MessageParam>,
         tokens_rem: &mut BudgetAllocation,
     ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
-        step_default_turn_impl(self, client, messages, tokens_rem, None).await
+        let mut turn = TurnConfig::for_agent(self).await;
+        self.step_default_turn_with_turn(client, messages, tokens_rem, &mut turn)
+            .await
     }
 
     /// Default implementation for executing a single step with streaming output.
@@ -1835,13 +2796,41 @@ pub trait Agent: Send + Sync + Sized {
         renderer: &mut dyn Renderer,
         context: &AgentStreamContext,
     ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
-        let show_thinking = self.thinking().await.is_some();
+        let mut turn = TurnConfig::for_agent(self).await;
+        self.step_default_turn_streaming_with_turn(
+            client, messages, tokens_rem, &mut turn, renderer, context,
+        )
+        .await
+    }
+
+    /// Default implementation for executing a single step with the config read for the turn.
+    async fn step_default_turn_with_turn(
+        &mut self,
+        client: &Anthropic,
+        messages: &mut Vec<MessageParam>,
+        tokens_rem: &mut BudgetAllocation,
+        turn: &mut TurnConfig,
+    ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
+        step_default_turn_impl(self, client, messages, tokens_rem, turn, None).await
+    }
+
+    /// Default implementation for executing a single streaming step with the config read for
+    /// the turn.
+    async fn step_default_turn_streaming_with_turn(
+        &mut self,
+        client: &Anthropic,
+        messages: &mut Vec<MessageParam>,
+        tokens_rem: &mut BudgetAllocation,
+        turn: &mut TurnConfig,
+        renderer: &mut dyn Renderer,
+        context: &AgentStreamContext,
+    ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
         let streaming = StreamingContext {
             renderer,
             context,
-            show_thinking,
+            show_thinking: turn.thinking.is_some(),
         };
-        step_default_turn_impl(self, client, messages, tokens_rem, Some(streaming)).await
+        step_default_turn_impl(self, client, messages, tokens_rem, turn, Some(streaming)).await
     }
 
     /// Handles tool use requests from the model.
@@ -1877,32 +2866,43 @@ pub trait Agent: Send + Sync + Sized {
             AGENT_TOOL_CALLS.click();
             let callback = tool.callback();
             let tool_use = tool_use.clone();
+            self.hook_tool_start(&tool_use).await;
+            let trace = ToolCallTrace::start(&tool_use);
             let this = &*self;
             let compute_start = Instant::now();
-            let intermediate = callback.compute_tool_result(client, this, &tool_use).await;
+            let intermediate = trace
+                .compute(callback.compute_tool_result(client, this, &tool_use))
+                .await;
             let compute_duration = compute_start.elapsed();
             let apply_start = Instant::now();
-            match callback
-                .apply_tool_result(client, self, &tool_use, intermediate)
-                .await
-            {
+            let result = trace
+                .apply(callback.apply_tool_result(client, self, &tool_use, intermediate))
+                .await;
+            let elapsed = compute_duration + apply_start.elapsed();
+            trace.finish(&result, elapsed);
+            AGENT_TOOL_DURATION.add(elapsed.as_secs_f64());
+            self.hook_tool_end(&tool_use, elapsed).await;
+            match result {
                 ControlFlow::Continue(result) => {
-                    AGENT_TOOL_DURATION
-                        .add((compute_duration + apply_start.elapsed()).as_secs_f64());
                     if result.is_err() {
                         AGENT_TOOL_ERRORS.click();
                     }
                     push_tool_result(&mut tool_results, None, result);
                 }
                 ControlFlow::Break(err) => {
-                    AGENT_TOOL_DURATION
-                        .add((compute_duration + apply_start.elapsed()).as_secs_f64());
                     AGENT_TOOL_ERRORS.click();
                     return ControlFlow::Break(Err(err));
                 }
             }
         }
-        ControlFlow::Continue(tool_results)
+        let tool_uses: Vec<ToolUseBlock> = tools_and_blocks
+            .into_iter()
+            .map(|(tool_use, _)| tool_use)
+            .collect();
+        match align_tool_result_blocks(&tool_uses, tool_results) {
+            Ok(tool_results) => ControlFlow::Continue(tool_results),
+            Err(err) => ControlFlow::Break(Err(err)),
+        }
     }
 
     /// Default implementation for handling tool use requests with streaming output.
@@ -1919,30 +2919,47 @@ pub trait Agent: Send + Sync + Sized {
             AGENT_TOOL_CALLS.click();
             let tool_context = context.child(format!("tool:{}", tool_use.name));
             let callback = tool.callback();
+            self.hook_tool_start(tool_use).await;
+            let trace = ToolCallTrace::start(tool_use);
             let this = &*self;
             let start = Instant::now();
-            let intermediate = callback
-                .compute_tool_result_streaming(client, this, tool_use, renderer, &tool_context)
+            let intermediate = trace
+                .compute(callback.compute_tool_result_streaming(
+                    client,
+                    this,
+                    tool_use,
+                    renderer,
+                    &tool_context,
+                ))
                 .await;
-            match callback
-                .apply_tool_result(client, self, tool_use, intermediate)
-                .await
-            {
+            let result = trace
+                .apply(callback.apply_tool_result(client, self, tool_use, intermediate))
+                .await;
+            let elapsed = start.elapsed();
+            trace.finish(&result, elapsed);
+            AGENT_TOOL_DURATION.add(elapsed.as_secs_f64());
+            self.hook_tool_end(tool_use, elapsed).await;
+            match result {
                 ControlFlow::Continue(result) => {
-                    AGENT_TOOL_DURATION.add(start.elapsed().as_secs_f64());
                     if result.is_err() {
                         AGENT_TOOL_ERRORS.click();
                     }
                     push_tool_result(&mut tool_results, Some((renderer, &tool_context)), result);
                 }
                 ControlFlow::Break(err) => {
-                    AGENT_TOOL_DURATION.add(start.elapsed().as_secs_f64());
                     AGENT_TOOL_ERRORS.click();
                     return ControlFlow::Break(Err(err));
                 }
             }
         }
-        ControlFlow::Continue(tool_results)
+        let tool_uses: Vec<ToolUseBlock> = tools_and_blocks
+            .into_iter()
+            .map(|(tool_use, _)| tool_use)
+            .collect();
+        match align_tool_result_blocks(&tool_uses, tool_results) {
+            Ok(tool_results) => ControlFlow::Continue(tool_results),
+            Err(err) => ControlFlow::Break(Err(err)),
+        }
     }
 
     /// Collect all ToolUseBlock blocks from the message.
@@ -1964,11 +2981,29 @@ pub trait Agent: Send + Sync + Sized {
     }
 
     /// Creates a message request with the agent's configuration.
+    ///
+    /// This reads a fresh [`TurnConfig`]; the default turn calls
+    /// [`Agent::create_request_with_turn`] with the config it keeps across steps.
     async fn create_request(
         &self,
         max_tokens: u32,
         messages: Vec<MessageParam>,
         stream: bool,
+    ) -> MessageCreateParams {
+        let turn = TurnConfig::for_agent(self).await;
+        self.create_request_with_turn(max_tokens, messages, stream, &turn)
+            .await
+    }
+
+    /// Creates a message request with the agent's configuration.
+    ///
+    /// The thinking configuration and tools come from `turn` rather than from the agent.
+    async fn create_request_with_turn(
+        &self,
+        max_tokens: u32,
+        messages: Vec<MessageParam>,
+        stream: bool,
+        turn: &TurnConfig,
     ) -> MessageCreateParams {
         let system = self.system().await;
         let mut messages = messages;
@@ -1976,87 +3011,102 @@ pub trait Agent: Send + Sync + Sized {
         let keep_latest = MAX_CACHE_BREAKPOINTS.saturating_sub(system_cache_controls);
         prune_cache_controls_in_messages(&mut messages, keep_latest);
 
-        let tools = self
-            .tools()
-            .await
-            .iter()
-            .map(|tool| tool.to_param())
-            .collect::<Vec<_>>();
-        let tools = if tools.is_empty() { None } else { Some(tools) };
+        // `ToolChoice::None` forbids tools for this request, so neither the tools nor the
+        // choice are sent, unless the history holds tool use or tool result blocks: the API
+        // rejects those without the matching definitions, so they are sent with the choice.
+        let tool_choice = self.tool_choice().await;
+        let tools_forbidden = matches!(tool_choice, Some(ToolChoice::None))
+            && !messages.iter().any(|message| match &message.content {
+                MessageParamContent::Array(blocks) => blocks
+                    .iter()
+                    .any(|block| block.is_tool_use() || block.is_tool_result()),
+                MessageParamContent::String(_) => false,
+            });
+        let (tools, tool_choice) = if tools_forbidden {
+            (None, None)
+        } else {
+            (
+                (!turn.tools.is_empty()).then(|| turn.tools.clone()),
+                tool_choice,
+            )
+        };
         MessageCreateParams {
             max_tokens,
             model: self.model().await,
             messages,
             metadata: self.metadata().await,
             output_format: None,
+            service_tier: None,
             stop_sequences: self.stop_sequences().await,
             system,
-            thinking: self.thinking().await,
+            thinking: turn.thinking,
             temperature: self.temperature().await,
             top_k: self.top_k().await,
             top_p: self.top_p().await,
             stream,
-            tool_choice: self.tool_choice().await,
+            tool_choice,
             tools,
+            betas: None,
+            extra: Default::default(),
         }
     }
 
     /// Handles text editor tool use.
     async fn text_editor(&self, tool_use: ToolUseBlock) -> Result<String, std::io::Error> {
         #[derive(serde::Deserialize)]
-        struct Command {
-            command: String,
+        struct Command<'a> {
+            command: &'a str,
         }
-        let cmd: Command = serde_json::from_value(tool_use.input.clone())?;
-        match cmd.command.as_str() {
+        let cmd: Command = tool_use.parse_input()?;
+        match cmd.command {
             "view" => {
                 #[derive(serde::Deserialize)]
-                struct ViewTool {
-                    path: String,
+                struct ViewTool<'a> {
+                    path: &'a str,
                     view_range: Option<(u32, u32)>,
                 }
-                let args: ViewTool = serde_json::from_value(tool_use.input)?;
-                self.view(&args.path, args.view_range).await
+                let args: ViewTool = tool_use.parse_input()?;
+                self.view(args.path, args.view_range).await
             }
             "str_replace" => {
                 #[derive(serde::Deserialize)]
-                struct StrReplaceTool {
-                    path: String,
-                    old_str: String,
-                    new_str: Option<String>,
+                struct StrReplaceTool<'a> {
+                    path: &'a str,
+                    old_str: &'a str,
+                    new_str: Option<&'a str>,
                 }
-                let args: StrReplaceTool = serde_json::from_value(tool_use.input)?;
-                let new_str = args.new_str.as_deref().unwrap_or("");
-                self.str_replace(&args.path, &args.old_str, new_str).await
+                let args: StrReplaceTool = tool_use.parse_input()?;
+                let new_str = args.new_str.unwrap_or("");
+                self.str_replace(args.path, args.old_str, new_str).await
             }
             "insert" => {
                 #[derive(serde::Deserialize)]
-                struct InsertTool {
-                    path: String,
+                struct InsertTool<'a> {
+                    path: &'a str,
                     insert_line: u32,
-                    insert_text: Option<String>,
-                    new_str: Option<String>,
+                    insert_text: Option<&'a str>,
+                    new_str: Option<&'a str>,
                 }
-                let args: InsertTool = serde_json::from_value(tool_use.input)?;
+                let args: InsertTool = tool_use.parse_input()?;
                 let text = args.insert_text.or(args.new_str).ok_or_else(|| {
                     std::io::Error::new(
                         std::io::ErrorKind::InvalidInput,
                         "missing insert_text field",
                     )
                 })?;
-                self.insert(&args.path, args.insert_line, &text).await
+                self.insert(args.path, args.insert_line, text).await
             }
             "create" => {
                 /// Tool parameters for file creation.
                 #[derive(serde::Deserialize)]
-                struct CreateTool {
+                struct CreateTool<'a> {
                     /// Path where the new file should be created.
-                    path: String,
+                    path: &'a str,
                     /// Content to write to the new file.
-                    file_text: String,
+                    file_text: &'a str,
                 }
-                let args: CreateTool = serde_json::from_value(tool_use.input)?;
-                self.create(&args.path, &args.file_text).await
+                let args: CreateTool = tool_use.parse_input()?;
+                self.create(args.path, args.file_text).await
             }
             _ => Err(std::io::Error::new(
                 std::io::ErrorKind::Unsupported,
@@ -2066,13 +3116,17 @@ pub trait Agent: Send + Sync + Sized {
     }
 
     /// Executes a bash command.
+    ///
+    /// Runs in [`Agent::bash_session`] when the agent provides one.
     async fn bash(&self, command: &str, restart: bool) -> Result<String, std::io::Error> {
-        let _ = command;
-        let _ = restart;
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Unsupported,
-            "bash is not supported",
-        ))
+        if let Some(session) = self.bash_session().await {
+            session.run(command, restart).await
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "bash is not supported",
+            ))
+        }
     }
 
     /// Searches the filesystem for files matching the query.
@@ -2094,7 +3148,10 @@ pub trait Agent: Send + Sync + Sized {
         view_range: Option<(u32, u32)>,
     ) -> Result<String, std::io::Error> {
         if let Some(fs) = self.filesystem().await {
-            fs.view(path, view_range).await
+            match self.max_view_bytes().await {
+                Some(max_bytes) => fs.view_limited(path, view_range, max_bytes).await,
+                None => fs.view(path, view_range).await,
+            }
         } else {
             Err(std::io::Error::new(
                 std::io::ErrorKind::Unsupported,
@@ -2185,12 +3242,55 @@ pub trait Agent: Send + Sync + Sized {
 #[async_trait::async_trait]
 impl Agent for () {}
 
+////////////////////////////////////////// PathFileSystem //////////////////////////////////////////
+
+/// A [`FileSystem`] rooted at a directory on local disk.
+///
+/// Paths are resolved under the base directory, rejecting `..` components and `//` paths.  By
+/// default symlinks are not followed out of the base: a path that resolves to something outside
+/// it fails with [`std::io::ErrorKind::PermissionDenied`].  A bare [`Path`] is a filesystem with
+/// these defaults.
+#[derive(Clone, Debug)]
+pub struct PathFileSystem {
+    base: Path<'static>,
+    follow_symlinks: bool,
+}
+
+impl PathFileSystem {
+    /// Creates a filesystem rooted at `base` that does not follow symlinks out of it.
+    pub fn new(base: Path<'_>) -> Self {
+        Self {
+            base: base.into_owned(),
+            follow_symlinks: false,
+        }
+    }
+
+    /// Sets whether symlinks may resolve to paths outside the base directory.
+    ///
+    /// Only enable this when every symlink under the base directory is trusted.
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Resolves `path` under the base directory.
+    fn resolve(&self, path: &str) -> Result<Path<'static>, std::io::Error> {
+        let path = sanitize_path(self.base.clone(), path)?;
+        if !self.follow_symlinks {
+            ensure_within_base(&self.base, &path)?;
+        }
+        Ok(path)
+    }
+}
+
 #[async_trait::async_trait]
-impl FileSystem for Path<'_> {
+impl FileSystem for PathFileSystem {
     async fn search(&self, search: &str) -> Result<String, std::io::Error> {
+        // -R dereferences every symlink it meets, -r only those named on the command line.
+        let recursive = if self.follow_symlinks { "-nRI" } else { "-nrI" };
         let output = std::process::Command::new("grep")
-            .args(["-nRI", "--", search])
-            .current_dir(self)
+            .args([recursive, "--", search])
+            .current_dir(&self.base)
             .output()?;
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -2214,7 +3314,7 @@ impl FileSystem for Path<'_> {
                 "view_range values must be >= 1",
             ));
         }
-        let path = sanitize_path(self.clone(), path)?;
+        let path = self.resolve(path)?;
         if path.is_file() {
             let content = std::fs::read_to_string(path)?;
             let lines = content
@@ -2255,7 +3355,7 @@ impl FileSystem for Path<'_> {
         old_str: &str,
         new_str: &str,
     ) -> Result<String, std::io::Error> {
-        let path = sanitize_path(self.clone(), path)?;
+        let path = self.resolve(path)?;
         if path.is_file() {
             let content = std::fs::read_to_string(&path)?;
             let count = content.matches(old_str).count();
@@ -2288,7 +3388,7 @@ impl FileSystem for Path<'_> {
         insert_line: u32,
         insert_text: &str,
     ) -> Result<String, std::io::Error> {
-        let path = sanitize_path(self.clone(), path)?;
+        let path = self.resolve(path)?;
         if path.is_file() {
             let content = std::fs::read_to_string(&path)?;
             let mut lines = content
@@ -2315,7 +3415,7 @@ impl FileSystem for Path<'_> {
         }
     }
 
-    /// Create a file within the filesystem path, ensuring it doesn't already exist.
+    /// Create a file within the base directory, ensuring it doesn't already exist.
     ///
     /// This implementation uses atomic file creation semantics - the file is only
     /// created if it doesn't already exist, preventing accidental overwrites.
@@ -2325,7 +3425,7 @@ impl FileSystem for Path<'_> {
     /// Returns [`std::io::ErrorKind::AlreadyExists`] if the file already exists.
     /// Returns other I/O errors if file creation fails for other reasons.
     async fn create(&self, path: &str, file_text: &str) -> Result<String, std::io::Error> {
-        let path = sanitize_path(self.clone(), path)?;
+        let path = self.resolve(path)?;
         if !path.exists() {
             std::fs::create_dir_all(path.dirname())?;
             std::fs::write(&path, file_text)?;
@@ -2339,24 +3439,69 @@ impl FileSystem for Path<'_> {
     }
 }
 
-/////////////////////////////////////////////// Mount //////////////////////////////////////////////
-
-/// A filesystem mount point with associated permissions.
-///
-/// Wraps a filesystem implementation with a path prefix and access permissions,
-/// enabling controlled access to specific parts of the filesystem.
-pub struct Mount {
-    path: Path<'static>,
-    perm: Permissions,
-    fs: Box<dyn FileSystem>,
-}
-
 #[async_trait::async_trait]
-impl FileSystem for Mount {
+impl FileSystem for Path<'_> {
     async fn search(&self, search: &str) -> Result<String, std::io::Error> {
-        match self.perm {
-            Permissions::WriteOnly => Err(std::io::Error::new(
-                std::io::ErrorKind::PermissionDenied,
+        PathFileSystem::new(self.clone()).search(search).await
+    }
+
+    async fn view(
+        &self,
+        path: &str,
+        view_range: Option<(u32, u32)>,
+    ) -> Result<String, std::io::Error> {
+        PathFileSystem::new(self.clone())
+            .view(path, view_range)
+            .await
+    }
+
+    async fn str_replace(
+        &self,
+        path: &str,
+        old_str: &str,
+        new_str: &str,
+    ) -> Result<String, std::io::Error> {
+        PathFileSystem::new(self.clone())
+            .str_replace(path, old_str, new_str)
+            .await
+    }
+
+    async fn insert(
+        &self,
+        path: &str,
+        insert_line: u32,
+        insert_text: &str,
+    ) -> Result<String, std::io::Error> {
+        PathFileSystem::new(self.clone())
+            .insert(path, insert_line, insert_text)
+            .await
+    }
+
+    async fn create(&self, path: &str, file_text: &str) -> Result<String, std::io::Error> {
+        PathFileSystem::new(self.clone())
+            .create(path, file_text)
+            .await
+    }
+}
+
+/////////////////////////////////////////////// Mount //////////////////////////////////////////////
+
+/// A filesystem mount point with associated permissions.
+///
+/// Wraps a filesystem implementation with a path prefix and access permissions,
+/// enabling controlled access to specific parts of the filesystem.
+pub struct Mount {
+    path: Path<'static>,
+    perm: Permissions,
+    fs: Box<dyn FileSystem>,
+}
+
+#[async_trait::async_trait]
+impl FileSystem for Mount {
+    async fn search(&self, search: &str) -> Result<String, std::io::Error> {
+        match self.perm {
+            Permissions::WriteOnly => Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
                 "search not allowed with WriteOnly permissions",
             )),
             Permissions::ReadOnly | Permissions::ReadWrite => self.fs.search(search).await,
@@ -2377,6 +3522,23 @@ impl FileSystem for Mount {
         }
     }
 
+    async fn view_limited(
+        &self,
+        path: &str,
+        view_range: Option<(u32, u32)>,
+        max_bytes: usize,
+    ) -> Result<String, std::io::Error> {
+        match self.perm {
+            Permissions::WriteOnly => Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "view not allowed with WriteOnly permissions",
+            )),
+            Permissions::ReadOnly | Permissions::ReadWrite => {
+                self.fs.view_limited(path, view_range, max_bytes).await
+            }
+        }
+    }
+
     async fn str_replace(
         &self,
         path: &str,
@@ -2491,15 +3653,34 @@ impl MountHierarchy {
 
 #[async_trait::async_trait]
 impl FileSystem for MountHierarchy {
+    /// Search every mount, concatenating their results in mount order.
+    ///
+    /// A mount that fails, such as a write-only mount, contributes a `[/path: error]` line in
+    /// place of its results so the others are still searched.  Only when every mount fails is
+    /// the first error returned.
     async fn search(&self, search: &str) -> Result<String, std::io::Error> {
         let mut output = String::new();
+        let mut first_error = None;
+        let mut any_ok = false;
         for mount in self.mounts.iter() {
-            output += &mount.search(search).await?;
+            match mount.search(search).await {
+                Ok(result) => {
+                    any_ok = true;
+                    output += &result;
+                }
+                Err(err) => {
+                    output += &format!("[{}: {err}]", mount.path);
+                    first_error.get_or_insert(err);
+                }
+            }
             if !output.ends_with('\n') {
                 output.push('\n');
             }
         }
-        Ok(output)
+        match first_error {
+            Some(err) if !any_ok => Err(err),
+            _ => Ok(output),
+        }
     }
 
     async fn view(
@@ -2511,6 +3692,16 @@ impl FileSystem for MountHierarchy {
         fs.view(path.as_str(), view_range).await
     }
 
+    async fn view_limited(
+        &self,
+        path: &str,
+        view_range: Option<(u32, u32)>,
+        max_bytes: usize,
+    ) -> Result<String, std::io::Error> {
+        let (fs, path) = self.fs_for_path(path)?;
+        fs.view_limited(path.as_str(), view_range, max_bytes).await
+    }
+
     async fn str_replace(
         &self,
         path: &str,
@@ -2539,6 +3730,71 @@ impl FileSystem for MountHierarchy {
 
 /////////////////////////////////////////////// Misc ///////////////////////////////////////////////
 
+/// Orders `results` to match `tool_uses`, one result per tool use.
+///
+/// The API rejects a turn whose tool results don't answer every tool use, so this returns a
+/// validation error naming each `tool_use_id` that has no result.  When several results share
+/// an id the first is kept; results for ids not in `tool_uses` are dropped.
+pub fn align_tool_results(
+    tool_uses: &[ToolUseBlock],
+    results: Vec<ToolResultBlock>,
+) -> Result<Vec<ToolResultBlock>, Error> {
+    let mut by_id: HashMap<String, ToolResultBlock> = HashMap::new();
+    for result in results {
+        by_id.entry(result.tool_use_id.clone()).or_insert(result);
+    }
+    let mut aligned = Vec::with_capacity(tool_uses.len());
+    let mut missing = vec![];
+    for tool_use in tool_uses {
+        match by_id.remove(&tool_use.id) {
+            Some(result) => aligned.push(result),
+            None => missing.push(tool_use.id.as_str()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(Error::validation(
+            format!("no tool result for tool_use ids: {}", missing.join(", ")),
+            Some("tool_result".to_string()),
+        ));
+    }
+    Ok(aligned)
+}
+
+/// Aligns the tool result blocks built for a turn with the turn's tool uses.
+fn align_tool_result_blocks(
+    tool_uses: &[ToolUseBlock],
+    blocks: Vec<ContentBlock>,
+) -> Result<Vec<ContentBlock>, Error> {
+    let results = blocks
+        .into_iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolResult(result) => Some(result),
+            _ => None,
+        })
+        .collect();
+    let mut blocks: Vec<ContentBlock> = align_tool_results(tool_uses, results)?
+        .into_iter()
+        .map(ContentBlock::from)
+        .collect();
+    prune_tool_result_cache_controls(&mut blocks, MAX_CACHE_BREAKPOINTS);
+    Ok(blocks)
+}
+
+/// Cuts `content` to at most `max_bytes` bytes, noting how much was dropped.
+fn truncate_output(mut content: String, max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return content;
+    }
+    let mut cut = max_bytes;
+    while !content.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let truncated = content.len() - cut;
+    content.truncate(cut);
+    content.push_str(&format!("\n...[truncated {truncated} bytes]...\n"));
+    content
+}
+
 fn sanitize_path(base: Path, path: &str) -> Result<Path<'static>, std::io::Error> {
     let path = Path::from(path);
     if path
@@ -2563,6 +3819,37 @@ fn sanitize_path(base: Path, path: &str) -> Result<Path<'static>, std::io::Error
     }
 }
 
+/// Fails with `PermissionDenied` if `path` resolves, through symlinks, to outside of `base`.
+///
+/// A path that does not exist yet is checked through its nearest existing ancestor, so `create`
+/// cannot write through a symlinked directory either.  A dangling symlink is always rejected,
+/// as writing to it would create its target wherever that points.
+fn ensure_within_base(base: &Path, path: &Path) -> Result<(), std::io::Error> {
+    let base = match std::fs::canonicalize(base.as_str()) {
+        Ok(base) => base,
+        // Nothing under a base that does not exist yet can be a symlink.
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let mut candidate = std::path::PathBuf::from(path.as_str());
+    loop {
+        match std::fs::canonicalize(&candidate) {
+            Ok(resolved) if resolved.starts_with(&base) => return Ok(()),
+            Ok(_) => break,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                if std::fs::symlink_metadata(&candidate).is_ok() || !candidate.pop() {
+                    break;
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        format!("{path} resolves outside of the filesystem base"),
+    ))
+}
+
 //////////////////////////////////////// Streaming Helpers /////////////////////////////////////////
 
 /// Renders a complete tool result block to the renderer.
@@ -2635,7 +3922,7 @@ fn push_tool_result(
             tool_results.push(block.with_error(true).into());
         }
     }
-    prune_tool_result_cache_controls(tool_results, 4);
+    prune_tool_result_cache_controls(tool_results, MAX_CACHE_BREAKPOINTS);
 }
 
 fn prune_tool_result_cache_controls(tool_results: &mut [ContentBlock], keep_latest: usize) {
@@ -2669,45 +3956,69 @@ fn prune_tool_result_cache_controls(tool_results: &mut [ContentBlock], keep_late
     }
 }
 
+/// The wait before the first retry of [`Agent::request_retries`], doubled for each retry after.
+const REQUEST_RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
 async fn step_default_turn_impl<A: Agent>(
     agent: &mut A,
     client: &Anthropic,
     messages: &mut Vec<MessageParam>,
     tokens_rem: &mut BudgetAllocation<'_>,
+    turn: &mut TurnConfig,
     mut streaming: Option<StreamingContext<'_>>,
 ) -> ControlFlow<Result<TurnOutcome, Error>, TurnStep> {
+    if let Err(err) = agent.before_step(messages).await {
+        return ControlFlow::Break(Err(err));
+    }
+    // After `before_step`, so that tools it adds are sent with this step's request.
+    turn.begin_step(agent).await;
     let stream = streaming.is_some();
     let mut usage_total = Usage::new(0, 0);
     let mut request_count: u64 = 0;
+    let mut pauses: u32 = 0;
     loop {
         let req = agent
-            .create_request(tokens_rem.remaining_tokens(), messages.clone(), stream)
+            .create_request_with_turn(
+                tokens_rem.remaining_tokens(),
+                messages.clone(),
+                stream,
+                turn,
+            )
             .await;
         if let Err(err) = agent.hook_message_create_params(&req).await {
             return ControlFlow::Break(Err(err));
         }
 
-        AGENT_TURN_REQUESTS.click();
-        let resp = if let Some(streaming) = streaming.as_mut() {
-            match stream_message_with_renderer(
-                client,
-                req,
-                streaming.renderer,
-                streaming.context,
-                streaming.show_thinking,
-            )
-            .await
-            {
-                Ok(resp) => resp,
-                Err(err) => return ControlFlow::Break(Err(err)),
-            }
-        } else {
-            match client.send(req).await {
-                Ok(resp) => resp,
+        let retries = agent.request_retries().await;
+        let mut attempt: u32 = 0;
+        let resp = loop {
+            let attempt_req = req.clone();
+            AGENT_TURN_REQUESTS.click();
+            let result = if let Some(streaming) = streaming.as_mut() {
+                stream_message_with_renderer(
+                    client,
+                    attempt_req,
+                    streaming.renderer,
+                    streaming.context,
+                    streaming.show_thinking,
+                )
+                .await
+            } else {
+                client.send(attempt_req).await
+            };
+            match result {
+                Ok(resp) => break resp,
+                Err(err) if attempt < retries && err.is_retryable() => {
+                    tokio::time::sleep(REQUEST_RETRY_BACKOFF * 2u32.pow(attempt.min(8))).await;
+                    attempt += 1;
+                }
                 Err(err) => return ControlFlow::Break(Err(err)),
             }
         };
 
+        // The request has been paid for, so charge it before any hook can fail the turn.
+        usage_total = usage_total + resp.usage;
+        let within_budget = tokens_rem.consume_usage(&resp.usage);
         if let Err(err) = agent.hook_message(&resp).await {
             return ControlFlow::Break(Err(err));
         }
@@ -2716,8 +4027,7 @@ async fn step_default_turn_impl<A: Agent>(
             role: MessageRole::Assistant,
             content: MessageParamContent::Array(resp.content.clone()),
         };
-        usage_total = usage_total + resp.usage;
-        if !tokens_rem.consume_usage(&resp.usage) {
+        if !within_budget {
             return ControlFlow::Break(Ok(TurnOutcome {
                 stop_reason: StopReason::MaxTokens,
                 usage: usage_total,
@@ -2727,99 +4037,155 @@ async fn step_default_turn_impl<A: Agent>(
         request_count = request_count.saturating_add(1);
         push_or_merge_message(messages, assistant_message);
 
-        let tool_results = match resp.stop_reason {
-            None | Some(StopReason::EndTurn) => {
-                let stop_reason = match agent.handle_end_turn().await {
-                    Ok(stop_reason) => stop_reason,
-                    Err(err) => return ControlFlow::Break(Err(err)),
-                };
-                return ControlFlow::Break(Ok(TurnOutcome {
-                    stop_reason,
-                    usage: usage_total,
-                    request_count,
-                }));
+        let prompt = prompt_tokens(&resp.usage);
+        let stop_reason = 'step: {
+            if resp.stop_reason == Some(StopReason::ToolUse) && !agent.should_continue(&resp).await
+            {
+                let abandoned: Vec<ContentBlock> = resp
+                    .content
+                    .iter()
+                    .filter_map(|block| match block {
+                        ContentBlock::ToolUse(tool_use) => Some(
+                            ToolResultBlock::error(tool_use.id.clone(), "the agent ended the turn")
+                                .into(),
+                        ),
+                        _ => None,
+                    })
+                    .collect();
+                if !abandoned.is_empty() {
+                    push_or_merge_message(
+                        messages,
+                        MessageParam::new(MessageParamContent::Array(abandoned), MessageRole::User),
+                    );
+                }
+                break 'step Some(StopReason::ToolUse);
             }
-            Some(StopReason::MaxTokens) => {
-                let stop_reason = match agent.handle_max_tokens().await {
-                    Ok(stop_reason) => stop_reason,
+
+            let tool_results = match resp.stop_reason {
+                None | Some(StopReason::EndTurn) => match agent.handle_end_turn().await {
+                    Ok(stop_reason) => break 'step Some(stop_reason),
                     Err(err) => return ControlFlow::Break(Err(err)),
-                };
-                return ControlFlow::Break(Ok(TurnOutcome {
-                    stop_reason,
-                    usage: usage_total,
-                    request_count,
-                }));
-            }
-            Some(StopReason::StopSequence) => {
-                let stop_reason = match agent.handle_stop_sequence(resp.stop_sequence).await {
-                    Ok(stop_reason) => stop_reason,
+                },
+                Some(StopReason::MaxTokens) => match agent.handle_max_tokens().await {
+                    Ok(stop_reason) => break 'step Some(stop_reason),
                     Err(err) => return ControlFlow::Break(Err(err)),
-                };
-                return ControlFlow::Break(Ok(TurnOutcome {
-                    stop_reason,
-                    usage: usage_total,
-                    request_count,
-                }));
-            }
-            Some(StopReason::Refusal) => {
-                let stop_reason = match agent.handle_refusal(resp).await {
-                    Ok(stop_reason) => stop_reason,
+                },
+                Some(StopReason::StopSequence) => match agent
+                    .handle_stop_sequence(resp.stop_sequence().map(str::to_string))
+                    .await
+                {
+                    Ok(stop_reason) => break 'step Some(stop_reason),
                     Err(err) => return ControlFlow::Break(Err(err)),
-                };
-                return ControlFlow::Break(Ok(TurnOutcome {
-                    stop_reason,
-                    usage: usage_total,
-                    request_count,
-                }));
-            }
-            Some(StopReason::PauseTurn) => {
-                continue;
-            }
-            Some(StopReason::ToolUse) => {
-                if let Some(streaming) = streaming.as_mut() {
-                    match agent
-                        .handle_tool_use_streaming(
-                            client,
-                            &resp,
-                            streaming.renderer,
-                            streaming.context,
-                        )
-                        .await
-                    {
-                        ControlFlow::Continue(results) => results,
-                        ControlFlow::Break(err) => {
-                            let outcome = err.map(|stop_reason| TurnOutcome {
-                                stop_reason,
-                                usage: usage_total,
-                                request_count,
-                            });
-                            return ControlFlow::Break(outcome);
+                },
+                Some(StopReason::Refusal) => {
+                    let refusal_text = resp.refusal_text();
+                    match agent.handle_refusal(resp, refusal_text).await {
+                        Ok(stop_reason) => break 'step Some(stop_reason),
+                        Err(err) => return ControlFlow::Break(Err(err)),
+                    }
+                }
+                Some(StopReason::PauseTurn) => {
+                    pauses = pauses.saturating_add(1);
+                    match agent.handle_pause_turn(pauses).await {
+                        Ok(ControlFlow::Continue(())) => {
+                            if let Err(err) =
+                                compact_past_threshold(agent, client, messages, prompt).await
+                            {
+                                return ControlFlow::Break(Err(err));
+                            }
+                            continue;
                         }
+                        Ok(ControlFlow::Break(stop_reason)) => break 'step Some(stop_reason),
+                        Err(err) => return ControlFlow::Break(Err(err)),
                     }
-                } else {
-                    match agent.handle_tool_use(client, &resp).await {
+                }
+                Some(StopReason::ToolUse) => {
+                    let handled = if let Some(streaming) = streaming.as_mut() {
+                        agent
+                            .handle_tool_use_streaming(
+                                client,
+                                &resp,
+                                streaming.renderer,
+                                streaming.context,
+                            )
+                            .await
+                    } else {
+                        agent.handle_tool_use(client, &resp).await
+                    };
+                    match handled {
                         ControlFlow::Continue(results) => results,
-                        ControlFlow::Break(err) => {
-                            let outcome = err.map(|stop_reason| TurnOutcome {
-                                stop_reason,
-                                usage: usage_total,
-                                request_count,
-                            });
-                            return ControlFlow::Break(outcome);
-                        }
+                        ControlFlow::Break(Ok(stop_reason)) => break 'step Some(stop_reason),
+                        ControlFlow::Break(Err(err)) => return ControlFlow::Break(Err(err)),
                     }
                 }
+            };
+
+            let ends_turn = agent
+                .collect_tool_uses(&resp)
+                .await
+                .iter()
+                .filter(|(_, tool)| tool.ends_turn())
+                .any(|(tool_use, _)| {
+                    tool_results.iter().any(|block| {
+                        matches!(block, ContentBlock::ToolResult(result)
+                            if result.tool_use_id == tool_use.id && result.is_error != Some(true))
+                    })
+                });
+            let user_message =
+                MessageParam::new(MessageParamContent::Array(tool_results), MessageRole::User);
+            push_or_merge_message(messages, user_message);
+            if ends_turn {
+                match agent.handle_end_turn().await {
+                    Ok(stop_reason) => break 'step Some(stop_reason),
+                    Err(err) => return ControlFlow::Break(Err(err)),
+                }
             }
+            None
         };
 
-        let user_message =
-            MessageParam::new(MessageParamContent::Array(tool_results), MessageRole::User);
-        push_or_merge_message(messages, user_message);
-        return ControlFlow::Continue(TurnStep {
-            usage: usage_total,
-            request_count,
-        });
+        if let Err(err) = compact_past_threshold(agent, client, messages, prompt).await {
+            return ControlFlow::Break(Err(err));
+        }
+        return match stop_reason {
+            Some(stop_reason) => ControlFlow::Break(Ok(TurnOutcome {
+                stop_reason,
+                usage: usage_total,
+                request_count,
+            })),
+            None => ControlFlow::Continue(TurnStep {
+                usage: usage_total,
+                request_count,
+            }),
+        };
+    }
+}
+
+/// Compacts `messages` if a request's `prompt` tokens crossed [`Agent::compaction_threshold`].
+async fn compact_past_threshold<A: Agent>(
+    agent: &A,
+    client: &Anthropic,
+    messages: &mut Vec<MessageParam>,
+    prompt: u64,
+) -> Result<(), Error> {
+    if let Some(threshold) = agent.compaction_threshold().await
+        && prompt > u64::from(threshold)
+    {
+        agent.compact_history(client, messages).await?;
     }
+    Ok(())
+}
+
+/// Returns the number of prompt tokens a request used, cached or not.
+fn prompt_tokens(usage: &Usage) -> u64 {
+    [
+        Some(usage.input_tokens),
+        usage.cache_creation_input_tokens,
+        usage.cache_read_input_tokens,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|tokens| tokens.max(0) as u64)
+    .sum()
 }
 
 async fn stream_message_with_renderer(
@@ -2847,13 +4213,16 @@ async fn stream_message_with_renderer(
             if partial.stop_reason.is_none() {
                 partial.stop_reason = Some(StopReason::EndTurn);
             }
+            client.apply_response_middleware(&mut partial);
             return Ok(partial);
         }
         match event {
             Ok(event) => match &event {
-                MessageStreamEvent::Ping => {}
-                MessageStreamEvent::MessageStart(_) => {}
-                MessageStreamEvent::MessageDelta(_) => {}
+                MessageStreamEvent::Ping | MessageStreamEvent::Unknown { .. } => {}
+                MessageStreamEvent::MessageStart(_) => renderer.start_message(context),
+                MessageStreamEvent::MessageDelta(delta_event) => {
+                    renderer.update_usage(context, &delta_event.usage);
+                }
                 MessageStreamEvent::ContentBlockStart(start_event) => {
                     match &start_event.content_block {
                         ContentBlock::ToolUse(tool_use) => {
@@ -2904,6 +4273,7 @@ async fn stream_message_with_renderer(
                     }
                     ContentBlockDelta::SignatureDelta(_) => {}
                     ContentBlockDelta::CitationsDelta(_) => {}
+                    ContentBlockDelta::CompactionDelta(_) => {}
                 },
                 MessageStreamEvent::ContentBlockStop(stop_event) => {
                     if active_tool_uses.remove(&stop_event.index) {
@@ -2924,7 +4294,10 @@ async fn stream_message_with_renderer(
 
     renderer.finish_response(context);
     match rx.await {
-        Ok(Ok(resp)) => Ok(resp),
+        Ok(Ok(mut resp)) => {
+            client.apply_response_middleware(&mut resp);
+            Ok(resp)
+        }
         Ok(Err(err)) => {
             renderer.print_error(context, &err.to_string());
             Err(err)
@@ -2943,6 +4316,7 @@ async fn stream_message_with_renderer(
 mod tests {
     use super::*;
     use crate::Usage;
+    use crate::test_support::{MockServer, Reply};
     use std::sync::atomic::Ordering;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -2973,6 +4347,101 @@ mod tests {
         assert_eq!(budget.remaining_micro_cents(), 100_000_000);
     }
 
+    #[test]
+    fn budget_charges_web_search_requests() {
+        use crate::{ServerToolUsage, Usage};
+        let budget =
+            Budget::new_with_rates(10_000_000, 300, 1500, 375, 30).with_web_search_rate(1_000_000);
+        let usage = Usage::new(1000, 200).with_server_tool_use(ServerToolUsage::new(3));
+        assert_eq!(
+            budget.calculate_cost(&usage),
+            1000 * 300 + 200 * 1500 + 3 * 1_000_000
+        );
+        assert!(budget.consume_usage(&usage));
+        assert_eq!(budget.remaining_micro_cents(), 10_000_000 - 3_600_000);
+        // Without a rate, searches are free.
+        let free = Budget::new_with_rates(10_000_000, 300, 1500, 375, 30);
+        assert_eq!(free.calculate_cost(&usage), 600_000);
+    }
+
+    #[test]
+    fn budget_for_model_uses_model_pricing() {
+        use crate::Usage;
+        let budget = Budget::for_model(1.0, KnownModel::ClaudeHaiku45);
+        assert_eq!(budget.remaining_micro_cents(), 100_000_000);
+
+        let usage = Usage::new(1000, 100)
+            .with_cache_creation_input_tokens(10)
+            .with_cache_read_input_tokens(20);
+        let cost = budget.calculate_cost(&usage);
+        assert_eq!(cost, 1000 * 100 + 100 * 500 + 10 * 125 + 20 * 10);
+    }
+
+    #[tokio::test]
+    async fn owned_allocations_cross_task_boundaries() {
+        let budget = Arc::new(Budget::new_flat_rate(1_000, 1));
+        let mut first = budget.allocate_owned(300).unwrap();
+        let second = budget.allocate_owned(300).unwrap();
+        assert_eq!(budget.remaining_micro_cents(), 400);
+        assert!(budget.allocate_owned(500).is_none());
+
+        let first = tokio::spawn(async move {
+            assert!(first.consume_usage(&Usage::new(100, 50)));
+            first.remaining_micro_cents()
+        });
+        let second = tokio::spawn(async move {
+            tokio::task::yield_now().await;
+            second.remaining_tokens()
+        });
+        assert_eq!(first.await.unwrap(), 150);
+        assert_eq!(second.await.unwrap(), 300);
+
+        // Both allocations were dropped; only the consumed 150 stays spent.
+        assert_eq!(budget.remaining_micro_cents(), 850);
+    }
+
+    #[tokio::test]
+    async fn allocate_wait_wakes_when_allocation_drops() {
+        let budget = Arc::new(Budget::new_flat_rate(1_000, 1));
+        let held = budget.allocate_owned(600).unwrap();
+
+        let waiting = Arc::clone(&budget);
+        let waiter = tokio::spawn(async move {
+            waiting
+                .allocate_wait(600, Duration::from_secs(30))
+                .await
+                .map(|allocation| allocation.remaining_micro_cents())
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(held);
+        assert_eq!(waiter.await.unwrap(), Some(600));
+        assert_eq!(budget.remaining_micro_cents(), 1_000);
+    }
+
+    #[tokio::test]
+    async fn allocate_wait_gives_up() {
+        let budget = Budget::new_flat_rate(1_000, 1);
+        let _held = budget.allocate(600).unwrap();
+        assert!(
+            budget
+                .allocate_wait(600, Duration::from_millis(10))
+                .await
+                .is_none()
+        );
+
+        // More than the whole budget never fits, so there is no point waiting.
+        let start = Instant::now();
+        assert!(
+            budget
+                .allocate_wait(2_000, Duration::from_secs(30))
+                .await
+                .is_none()
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
     #[test]
     fn budget_calculate_cost_basic_usage() {
         use crate::Usage;
@@ -2984,6 +4453,20 @@ mod tests {
         assert_eq!(cost, expected_cost);
     }
 
+    #[test]
+    fn budget_cost_dollars_matches_calculate_cost() {
+        use crate::Usage;
+        let budget = Budget::new_with_rates(10000, 10, 20, 5, 15);
+        assert_eq!(budget.rates(), TokenRates::new(10, 20, 5, 15));
+
+        let usage = Usage::new(50, 100)
+            .with_cache_creation_input_tokens(20)
+            .with_cache_read_input_tokens(30);
+        assert_eq!(budget.calculate_cost(&usage), usage.cost(&budget.rates()));
+        let expected = budget.calculate_cost(&usage) as f64 / 100_000_000.0;
+        assert_eq!(budget.cost_dollars(&usage), expected);
+    }
+
     #[test]
     fn budget_calculate_cost_with_cache() {
         use crate::Usage;
@@ -3439,7 +4922,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn mount_hierarchy_search_error_propagates() {
+    async fn mount_hierarchy_search_notes_failed_mounts() {
         let mut hierarchy = MountHierarchy { mounts: vec![] };
 
         hierarchy
@@ -3457,71 +4940,121 @@ mod tests {
             )
             .unwrap();
 
-        let result = hierarchy.search("test").await;
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
-        assert!(err.to_string().contains("search error from home"));
+        let result = hierarchy.search("test").await.unwrap();
+        assert_eq!(
+            result,
+            "search from root\n[/home: search error from home]\n"
+        );
     }
 
     #[tokio::test]
-    async fn mount_hierarchy_search_adds_newlines() {
+    async fn mount_hierarchy_search_skips_write_only_mounts() {
         let mut hierarchy = MountHierarchy { mounts: vec![] };
 
-        // Mock that doesn't end with newline
-        let mut mock = MockFileSystem::new_ok("no_newline");
-        mock.search_result = MockResult::Ok("result without newline".to_string());
-
         hierarchy
-            .mount("/".into(), Permissions::ReadWrite, mock)
+            .mount(
+                "/".into(),
+                Permissions::ReadOnly,
+                MockFileSystem::new_ok("root"),
+            )
             .unwrap();
         hierarchy
             .mount(
-                "/home".into(),
-                Permissions::ReadWrite,
-                MockFileSystem::new_ok("home"),
+                "/drop".into(),
+                Permissions::WriteOnly,
+                MockFileSystem::new_ok("drop"),
             )
             .unwrap();
 
         let result = hierarchy.search("test").await.unwrap();
-        assert_eq!(result, "result without newline\nsearch from home\n");
+        assert_eq!(
+            result,
+            "search from root\n[/drop: search not allowed with WriteOnly permissions]\n"
+        );
     }
 
     #[tokio::test]
-    async fn mount_hierarchy_view_uses_correct_filesystem() {
+    async fn mount_hierarchy_search_errors_when_every_mount_fails() {
         let mut hierarchy = MountHierarchy { mounts: vec![] };
 
         hierarchy
             .mount(
                 "/".into(),
                 Permissions::ReadWrite,
-                MockFileSystem::new_ok("root"),
+                MockFileSystem::new_err("root", std::io::ErrorKind::NotFound),
             )
             .unwrap();
         hierarchy
             .mount(
                 "/home".into(),
                 Permissions::ReadWrite,
-                MockFileSystem::new_ok("home"),
+                MockFileSystem::new_err("home", std::io::ErrorKind::PermissionDenied),
             )
             .unwrap();
 
-        let result = hierarchy.view("/file.txt", None).await.unwrap();
-        assert_eq!(result, "view from root");
-
-        let result = hierarchy
-            .view("/home/file.txt", Some((1, 10)))
-            .await
-            .unwrap();
-        assert_eq!(result, "view from home");
+        let err = hierarchy.search("test").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("search error from root"));
     }
 
     #[tokio::test]
-    async fn mount_hierarchy_view_error_no_filesystem() {
-        let hierarchy = MountHierarchy { mounts: vec![] };
+    async fn mount_hierarchy_search_adds_newlines() {
+        let mut hierarchy = MountHierarchy { mounts: vec![] };
 
-        let result = hierarchy.view("/file.txt", None).await;
-        assert!(result.is_err());
+        // Mock that doesn't end with newline
+        let mut mock = MockFileSystem::new_ok("no_newline");
+        mock.search_result = MockResult::Ok("result without newline".to_string());
+
+        hierarchy
+            .mount("/".into(), Permissions::ReadWrite, mock)
+            .unwrap();
+        hierarchy
+            .mount(
+                "/home".into(),
+                Permissions::ReadWrite,
+                MockFileSystem::new_ok("home"),
+            )
+            .unwrap();
+
+        let result = hierarchy.search("test").await.unwrap();
+        assert_eq!(result, "result without newline\nsearch from home\n");
+    }
+
+    #[tokio::test]
+    async fn mount_hierarchy_view_uses_correct_filesystem() {
+        let mut hierarchy = MountHierarchy { mounts: vec![] };
+
+        hierarchy
+            .mount(
+                "/".into(),
+                Permissions::ReadWrite,
+                MockFileSystem::new_ok("root"),
+            )
+            .unwrap();
+        hierarchy
+            .mount(
+                "/home".into(),
+                Permissions::ReadWrite,
+                MockFileSystem::new_ok("home"),
+            )
+            .unwrap();
+
+        let result = hierarchy.view("/file.txt", None).await.unwrap();
+        assert_eq!(result, "view from root");
+
+        let result = hierarchy
+            .view("/home/file.txt", Some((1, 10)))
+            .await
+            .unwrap();
+        assert_eq!(result, "view from home");
+    }
+
+    #[tokio::test]
+    async fn mount_hierarchy_view_error_no_filesystem() {
+        let hierarchy = MountHierarchy { mounts: vec![] };
+
+        let result = hierarchy.view("/file.txt", None).await;
+        assert!(result.is_err());
         let err = result.unwrap_err();
         assert_eq!(err.kind(), std::io::ErrorKind::Other);
         assert_eq!(err.to_string(), "filesystem not initialized");
@@ -3873,6 +5406,73 @@ mod tests {
         std::fs::remove_dir_all(dir).ok();
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn filesystem_rejects_symlinks_escaping_base() {
+        let dir = make_temp_dir("symlink_base");
+        let outside = make_temp_dir("symlink_outside");
+        std::fs::write(outside.join("secret.txt"), "secret\n").unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), dir.join("link.txt")).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("linkdir")).unwrap();
+        std::fs::write(dir.join("inside.txt"), "inside\n").unwrap();
+        std::os::unix::fs::symlink(dir.join("inside.txt"), dir.join("alias.txt")).unwrap();
+        let base = Path::try_from(dir.as_path()).unwrap();
+
+        let err = base.view("link.txt", None).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+        let err = base.create("linkdir/new.txt", "x").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(!outside.join("new.txt").exists());
+        assert_eq!(base.view("alias.txt", None).await.unwrap(), "inside\n\n");
+
+        let following = PathFileSystem::new(base).with_follow_symlinks(true);
+        assert_eq!(
+            following.view("link.txt", None).await.unwrap(),
+            "secret\n\n"
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+        std::fs::remove_dir_all(outside).ok();
+    }
+
+    #[tokio::test]
+    async fn view_limited_truncates_large_files() {
+        let dir = make_temp_dir("view_limited");
+        std::fs::write(dir.join("big.txt"), "0123456789".repeat(10)).unwrap();
+        std::fs::write(dir.join("small.txt"), "tiny").unwrap();
+        let mut hierarchy = MountHierarchy { mounts: vec![] };
+        hierarchy
+            .mount(
+                "/".into(),
+                Permissions::ReadOnly,
+                Path::try_from(dir.as_path()).unwrap().into_owned(),
+            )
+            .unwrap();
+
+        let result = hierarchy.view_limited("/big.txt", None, 25).await.unwrap();
+        assert_eq!(
+            result,
+            "0123456789012345678901234\n...[truncated 76 bytes]...\n"
+        );
+
+        let result = hierarchy
+            .view_limited("/small.txt", None, 25)
+            .await
+            .unwrap();
+        assert_eq!(result, "tiny\n");
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn truncate_output_respects_char_boundaries() {
+        assert_eq!(
+            truncate_output("héllo".to_string(), 2),
+            "h\n...[truncated 5 bytes]...\n"
+        );
+        assert_eq!(truncate_output("hello".to_string(), 5), "hello");
+    }
+
     // Permission tests
     #[tokio::test]
     async fn mount_permissions_readonly_allows_search_and_view() {
@@ -4778,4 +6378,1431 @@ mod tests {
         assert_eq!(content, "hello\n");
         std::fs::remove_dir_all(temp_dir).unwrap();
     }
+
+    /// Serves one canned JSON response per request and returns the base URL to use.
+    fn serve_json_responses(responses: Vec<serde_json::Value>) -> String {
+        MockServer::start(
+            responses
+                .iter()
+                .map(|response| Reply::json(response.to_string())),
+        )
+        .url()
+    }
+
+    fn tool_use_response(id: &str, name: &str, input_tokens: u32) -> serde_json::Value {
+        serde_json::json!({
+            "id": "msg_tool",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [{"type": "tool_use", "id": id, "name": name, "input": {}}],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": input_tokens, "output_tokens": 5}
+        })
+    }
+
+    fn text_response(text: &str, stop_reason: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": "msg_test",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [{"type": "text", "text": text}],
+            "stop_reason": stop_reason,
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        })
+    }
+
+    fn test_client(base_url: String) -> Anthropic {
+        Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(base_url)
+    }
+
+    #[derive(Default)]
+    struct ContinuingAgent {
+        requests: std::sync::Mutex<Vec<MessageCreateParams>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for ContinuingAgent {
+        async fn before_step(&mut self, messages: &mut Vec<MessageParam>) -> Result<(), Error> {
+            push_or_merge_message(messages, MessageParam::user("continue"));
+            Ok(())
+        }
+
+        async fn hook_message_create_params(&self, req: &MessageCreateParams) -> Result<(), Error> {
+            self.requests.lock().unwrap().push(req.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn before_step_injects_message_into_request() {
+        let base_url = serve_json_responses(vec![text_response("done", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = ContinuingAgent::default();
+        let mut messages = vec![MessageParam::user("hi"), MessageParam::assistant("partial")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+
+        let requests = agent.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].messages,
+            vec![
+                MessageParam::user("hi"),
+                MessageParam::assistant("partial"),
+                MessageParam::user("continue"),
+            ]
+        );
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[3].role, MessageRole::Assistant);
+    }
+
+    struct CountingTool {
+        conversions: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    struct CountingCallback;
+
+    #[async_trait::async_trait]
+    impl<A: Agent> ToolCallback<A> for CountingCallback {
+        async fn compute_tool_result(
+            &self,
+            _client: &Anthropic,
+            _agent: &A,
+            _tool_use: &ToolUseBlock,
+        ) -> Box<dyn IntermediateToolResult> {
+            Box::new(())
+        }
+
+        async fn apply_tool_result(
+            &self,
+            _client: &Anthropic,
+            _agent: &mut A,
+            tool_use: &ToolUseBlock,
+            _intermediate: Box<dyn IntermediateToolResult>,
+        ) -> ToolResult {
+            ControlFlow::Continue(Ok(ToolResultBlock::new(tool_use.id.clone())
+                .with_string_content("counted".to_string())))
+        }
+    }
+
+    impl<A: Agent> Tool<A> for CountingTool {
+        fn name(&self) -> String {
+            "counting".to_string()
+        }
+
+        fn callback(&self) -> Box<dyn ToolCallback<A> + '_> {
+            Box::new(CountingCallback)
+        }
+
+        fn to_param(&self) -> ToolUnionParam {
+            self.conversions.fetch_add(1, Ordering::Relaxed);
+            ToolUnionParam::CustomTool(ToolParam::new(
+                "counting".to_string(),
+                serde_json::json!({"type": "object"}),
+            ))
+        }
+    }
+
+    struct CountingToolAgent {
+        tool: Arc<CountingTool>,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for CountingToolAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![self.tool.clone()]
+        }
+
+        fn tools_changed(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn tools_are_converted_once_per_turn() {
+        let base_url = serve_json_responses(vec![
+            tool_use_response("toolu_1", "counting", 10),
+            tool_use_response("toolu_2", "counting", 10),
+            text_response("done", "end_turn"),
+        ]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let conversions = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut agent = CountingToolAgent {
+            tool: Arc::new(CountingTool {
+                conversions: Arc::clone(&conversions),
+            }),
+        };
+        let mut messages = vec![MessageParam::user("count")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        assert_eq!(outcome.request_count, 3);
+        assert_eq!(conversions.load(Ordering::Relaxed), 1);
+    }
+
+    struct LateTool;
+
+    impl<A: Agent> Tool<A> for LateTool {
+        fn name(&self) -> String {
+            "late".to_string()
+        }
+
+        fn callback(&self) -> Box<dyn ToolCallback<A> + '_> {
+            Box::new(CountingCallback)
+        }
+
+        fn to_param(&self) -> ToolUnionParam {
+            ToolUnionParam::CustomTool(ToolParam::new(
+                "late".to_string(),
+                serde_json::json!({"type": "object"}),
+            ))
+        }
+    }
+
+    /// Gains [`LateTool`] in `before_step` once the conversation has a tool result.
+    struct GrowingToolAgent {
+        tools_changed: bool,
+        late: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for GrowingToolAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            let mut tools: Vec<Arc<dyn Tool<Self>>> = vec![Arc::new(CountingTool {
+                conversions: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            })];
+            if self.late {
+                tools.push(Arc::new(LateTool));
+            }
+            tools
+        }
+
+        fn tools_changed(&self) -> bool {
+            self.tools_changed
+        }
+
+        async fn before_step(&mut self, messages: &mut Vec<MessageParam>) -> Result<(), Error> {
+            self.late = messages.len() > 1;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_added_mid_turn_is_sent_with_the_next_request() {
+        for tools_changed in [true, false] {
+            let server = MockServer::start([
+                Reply::json(tool_use_response("toolu_1", "counting", 10).to_string()),
+                Reply::json(text_response("done", "end_turn").to_string()),
+            ]);
+            let client = test_client(server.url());
+            let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+            let mut agent = GrowingToolAgent {
+                tools_changed,
+                late: false,
+            };
+            let mut messages = vec![MessageParam::user("count")];
+
+            agent
+                .take_turn(&client, &mut messages, &budget)
+                .await
+                .unwrap();
+            let tool_names = |request: &crate::test_support::Request| {
+                request.json()["tools"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|tool| tool["name"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            };
+            let requests = server.requests().await;
+            assert_eq!(tool_names(&requests[0]), ["counting"]);
+            assert_eq!(tool_names(&requests[1]), ["counting", "late"]);
+        }
+    }
+
+    struct AddingAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for AddingAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![Arc::from(fn_tool(
+                "add",
+                "Add two integers.",
+                serde_json::json!({"type": "object"}),
+                |input| match (input["a"].as_i64(), input["b"].as_i64()) {
+                    (Some(a), Some(b)) => Ok((a + b).to_string()),
+                    _ => Err("a and b must be integers".to_string()),
+                },
+            ))]
+        }
+    }
+
+    struct LoopGuardAgent {
+        tool: Arc<CountingTool>,
+        tool_calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for LoopGuardAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![self.tool.clone()]
+        }
+
+        async fn should_continue(&self, resp: &Message) -> bool {
+            let calls = self
+                .tool_calls
+                .fetch_add(resp.tool_use_count(), Ordering::Relaxed)
+                + resp.tool_use_count();
+            calls < 2
+        }
+    }
+
+    #[tokio::test]
+    async fn should_continue_ends_repeated_tool_use() {
+        let base_url = serve_json_responses(vec![
+            tool_use_response("toolu_1", "counting", 10),
+            tool_use_response("toolu_2", "counting", 10),
+        ]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = LoopGuardAgent {
+            tool: Arc::new(CountingTool {
+                conversions: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            }),
+            tool_calls: std::sync::atomic::AtomicUsize::new(0),
+        };
+        let mut messages = vec![MessageParam::user("count")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::ToolUse);
+        assert_eq!(outcome.request_count, 2);
+        // The second tool use is answered with an error instead of being run.
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[3].role, MessageRole::Assistant);
+        let MessageParamContent::Array(blocks) = &messages[4].content else {
+            panic!("expected tool results, got {:?}", messages[4].content);
+        };
+        assert!(matches!(
+            &blocks[..],
+            [ContentBlock::ToolResult(result)]
+                if result.tool_use_id == "toolu_2" && result.is_error == Some(true)
+        ));
+    }
+
+    struct CompactingAgent {
+        tool: Arc<CountingTool>,
+        compactions: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for CompactingAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![self.tool.clone()]
+        }
+
+        async fn compaction_threshold(&self) -> Option<u32> {
+            Some(100)
+        }
+
+        async fn compact_history(
+            &self,
+            _client: &Anthropic,
+            messages: &mut Vec<MessageParam>,
+        ) -> Result<(), Error> {
+            self.compactions.fetch_add(1, Ordering::Relaxed);
+            let recent = messages.split_off(messages.len() - 2);
+            *messages = vec![MessageParam::user("Summary: counting so far.")];
+            messages.extend(recent);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn compact_history_runs_past_threshold() {
+        let tool_use = |id: &str, input_tokens: u32| {
+            let mut response = tool_use_response(id, "counting", input_tokens);
+            response["usage"]["cache_read_input_tokens"] = serde_json::json!(60);
+            response
+        };
+        let base_url = serve_json_responses(vec![
+            tool_use("toolu_1", 30),
+            tool_use("toolu_2", 50),
+            text_response("done", "end_turn"),
+        ]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = CompactingAgent {
+            tool: Arc::new(CountingTool {
+                conversions: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            }),
+            compactions: std::sync::atomic::AtomicUsize::new(0),
+        };
+        let mut messages = vec![MessageParam::user("count")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        // 30 + 60 prompt tokens stays under the threshold; 50 + 60 crosses it.
+        assert_eq!(agent.compactions.load(Ordering::Relaxed), 1);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(
+            messages[0].content,
+            MessageParamContent::String("Summary: counting so far.".to_string())
+        );
+        assert_eq!(messages[3].role, MessageRole::Assistant);
+    }
+
+    #[tokio::test]
+    async fn compact_history_runs_after_end_turn() {
+        let mut response = text_response("done", "end_turn");
+        response["usage"]["input_tokens"] = serde_json::json!(200);
+        let client = test_client(serve_json_responses(vec![response]));
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = CompactingAgent {
+            tool: Arc::new(CountingTool {
+                conversions: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            }),
+            compactions: std::sync::atomic::AtomicUsize::new(0),
+        };
+        let mut messages = vec![MessageParam::user("count")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        assert_eq!(agent.compactions.load(Ordering::Relaxed), 1);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2].role, MessageRole::Assistant);
+    }
+
+    #[tokio::test]
+    async fn fn_tool_runs_closure_handler() {
+        let tool_use = serde_json::json!({
+            "id": "msg_tool",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [
+                {"type": "tool_use", "id": "toolu_1", "name": "add", "input": {"a": 2, "b": 3}},
+                {"type": "tool_use", "id": "toolu_2", "name": "add", "input": {"a": "two"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        });
+        let base_url = serve_json_responses(vec![tool_use, text_response("5", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = AddingAgent;
+        let mut messages = vec![MessageParam::user("add 2 and 3")];
+
+        let turn = TurnConfig::for_agent(&agent).await;
+        let params = agent
+            .create_request_with_turn(1024, messages.clone(), false, &turn)
+            .await;
+        let Some(ToolUnionParam::CustomTool(param)) =
+            params.tools.as_ref().and_then(|tools| tools.first())
+        else {
+            panic!("expected a custom tool: {:?}", params.tools);
+        };
+        assert_eq!(param.name, "add");
+        assert_eq!(param.description.as_deref(), Some("Add two integers."));
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        let MessageParamContent::Array(blocks) = &messages[2].content else {
+            panic!("expected tool results: {:?}", messages[2]);
+        };
+        let results: Vec<_> = blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolResult(result) => Some((
+                    result.tool_use_id.as_str(),
+                    result.content.clone(),
+                    result.is_error,
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                (
+                    "toolu_1",
+                    Some(ToolResultBlockContent::String("5".to_string())),
+                    None
+                ),
+                (
+                    "toolu_2",
+                    Some(ToolResultBlockContent::String(
+                        "a and b must be integers".to_string()
+                    )),
+                    Some(true)
+                ),
+            ]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn tool_calls_emit_tracing_spans() {
+        let mut tool_use = tool_use_response("toolu_traced", "add", 10);
+        tool_use["content"][0]["input"] = serde_json::json!({"a": 2, "b": 3});
+        let base_url = serve_json_responses(vec![tool_use, text_response("5", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = AddingAgent;
+        let mut messages = vec![MessageParam::user("add 2 and 3")];
+
+        agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert!(logs_contain("tool_call{"));
+        assert!(logs_contain("tool=add"));
+        assert!(logs_contain("tool_use_id=toolu_traced"));
+        assert!(logs_contain("input_bytes=13"));
+        assert!(logs_contain("success=true"));
+        assert!(logs_contain("tool call finished"));
+    }
+
+    struct NamedAgent {
+        name: String,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for NamedAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![Arc::from(fn_tool_stateful(
+                "whoami",
+                "Report the assistant's name.",
+                serde_json::json!({"type": "object"}),
+                |agent: &NamedAgent, input| {
+                    let name = agent.name.clone();
+                    async move {
+                        match input["greeting"].as_str() {
+                            Some(greeting) => Ok(format!("{greeting}, {name}")),
+                            None => Err("greeting is required".to_string()),
+                        }
+                    }
+                },
+            ))]
+        }
+    }
+
+    #[tokio::test]
+    async fn fn_tool_stateful_reads_agent() {
+        let mut tool_use = tool_use_response("toolu_1", "whoami", 10);
+        tool_use["content"][0]["input"] = serde_json::json!({"greeting": "ahoy"});
+        let base_url = serve_json_responses(vec![tool_use, text_response("done", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = NamedAgent {
+            name: "Claudius".to_string(),
+        };
+        let mut messages = vec![MessageParam::user("who are you?")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        let MessageParamContent::Array(blocks) = &messages[2].content else {
+            panic!("expected tool results: {:?}", messages[2]);
+        };
+        let Some(ContentBlock::ToolResult(result)) = blocks.first() else {
+            panic!("expected a tool result: {blocks:?}");
+        };
+        assert_eq!(result.tool_use_id, "toolu_1");
+        assert_eq!(
+            result.content,
+            Some(ToolResultBlockContent::String("ahoy, Claudius".to_string()))
+        );
+        assert_eq!(result.is_error, None);
+    }
+
+    #[test]
+    fn search_filesystem_schema_json() {
+        let tool = ToolSearchFileSystem;
+        let param = <ToolSearchFileSystem as Tool<AddingAgent>>::to_param(&tool);
+        assert_eq!(
+            param.input_schema_json(),
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The search query to find on the filesystem."
+                    }
+                },
+                "required": ["query"]
+            })
+        );
+        let described = <ToolSearchFileSystem as Tool<AddingAgent>>::describe(&tool);
+        assert!(described.starts_with(
+            "name: search_filesystem\ndescription: Search the local filesystem.\nschema: {\n"
+        ));
+        assert!(described.contains(r#""required": ["#), "{described}");
+        assert_eq!(
+            ToolUnionParam::new_bash_tool().input_schema_json(),
+            serde_json::Value::Null
+        );
+    }
+
+    struct ShoutingAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for ShoutingAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            let shout = ToolUnionParam::CustomTool(
+                ToolParam::new(
+                    "shout".to_string(),
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": {"text": {"type": "string"}},
+                        "required": ["text"]
+                    }),
+                )
+                .with_description("Shout the text.".to_string()),
+            );
+            let handler = |input: serde_json::Value| async move {
+                input["text"]
+                    .as_str()
+                    .map(str::to_uppercase)
+                    .ok_or_else(|| "text is required".to_string())
+            };
+            vec![Arc::new(
+                CustomToolWithHandler::from_union(shout, handler).unwrap(),
+            )]
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_tool_with_handler_runs_on_agent() {
+        let mut tool_use = tool_use_response("toolu_1", "shout", 10);
+        tool_use["content"][0]["input"] = serde_json::json!({"text": "hello"});
+        let base_url = serve_json_responses(vec![tool_use, text_response("done", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = ShoutingAgent;
+        let mut messages = vec![MessageParam::user("shout hello")];
+
+        let turn = TurnConfig::for_agent(&agent).await;
+        let params = agent
+            .create_request_with_turn(1024, messages.clone(), false, &turn)
+            .await;
+        let Some(ToolUnionParam::CustomTool(param)) =
+            params.tools.as_ref().and_then(|tools| tools.first())
+        else {
+            panic!("expected a custom tool: {:?}", params.tools);
+        };
+        assert_eq!(param.name, "shout");
+        assert_eq!(param.description.as_deref(), Some("Shout the text."));
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        let MessageParamContent::Array(blocks) = &messages[2].content else {
+            panic!("expected tool results: {:?}", messages[2]);
+        };
+        let Some(ContentBlock::ToolResult(result)) = blocks.first() else {
+            panic!("expected a tool result: {blocks:?}");
+        };
+        assert_eq!(
+            result.content,
+            Some(ToolResultBlockContent::String("HELLO".to_string()))
+        );
+        assert_eq!(result.is_error, None);
+    }
+
+    #[test]
+    fn custom_tool_with_handler_rejects_builtin_tools() {
+        let handler = |_input: serde_json::Value| async { Ok(String::new()) };
+        let err = CustomToolWithHandler::from_union(ToolUnionParam::new_bash_tool(), handler)
+            .err()
+            .expect("bash is not a custom tool");
+        assert!(err.to_string().contains("custom tools"), "{err}");
+    }
+
+    #[derive(Default)]
+    struct ThinkingCountingAgent {
+        thinking_calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for ThinkingCountingAgent {
+        async fn thinking(&self) -> Option<ThinkingConfig> {
+            self.thinking_calls.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![Arc::from(fn_tool(
+                "noop",
+                "Do nothing.",
+                serde_json::json!({"type": "object"}),
+                |_| Ok("done".to_string()),
+            ))]
+        }
+    }
+
+    #[tokio::test]
+    async fn thinking_is_read_once_per_turn() {
+        let base_url = serve_json_responses(vec![
+            tool_use_response("toolu_1", "noop", 10),
+            tool_use_response("toolu_2", "noop", 10),
+            text_response("done", "end_turn"),
+        ]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = ThinkingCountingAgent::default();
+        let mut messages = vec![MessageParam::user("go")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.request_count, 3);
+        assert_eq!(agent.thinking_calls.load(Ordering::Relaxed), 1);
+    }
+
+    struct FinishTool;
+
+    struct FinishCallback;
+
+    #[async_trait::async_trait]
+    impl<A: Agent> ToolCallback<A> for FinishCallback {
+        async fn compute_tool_result(
+            &self,
+            _client: &Anthropic,
+            _agent: &A,
+            _tool_use: &ToolUseBlock,
+        ) -> Box<dyn IntermediateToolResult> {
+            Box::new(())
+        }
+
+        async fn apply_tool_result(
+            &self,
+            _client: &Anthropic,
+            _agent: &mut A,
+            tool_use: &ToolUseBlock,
+            _intermediate: Box<dyn IntermediateToolResult>,
+        ) -> ToolResult {
+            let summary = tool_use.input["summary"].as_str().unwrap_or_default();
+            ControlFlow::Continue(Ok(
+                ToolResultBlock::new(tool_use.id.clone()).with_string_content(summary.to_string())
+            ))
+        }
+    }
+
+    impl<A: Agent> Tool<A> for FinishTool {
+        fn name(&self) -> String {
+            "finish".to_string()
+        }
+
+        fn callback(&self) -> Box<dyn ToolCallback<A> + '_> {
+            Box::new(FinishCallback)
+        }
+
+        fn to_param(&self) -> ToolUnionParam {
+            ToolUnionParam::CustomTool(ToolParam::new(
+                "finish".to_string(),
+                serde_json::json!({"type": "object"}),
+            ))
+        }
+
+        fn ends_turn(&self) -> bool {
+            true
+        }
+    }
+
+    struct FinishingAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for FinishingAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![Arc::new(FinishTool)]
+        }
+    }
+
+    #[tokio::test]
+    async fn finish_tool_ends_turn_without_error() {
+        // Only one response is served, so continuing the loop would fail the turn.
+        let mut tool_use = tool_use_response("toolu_finish", "finish", 10);
+        tool_use["content"][0]["input"] = serde_json::json!({"summary": "all done"});
+        let base_url = serve_json_responses(vec![tool_use]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = FinishingAgent;
+        let mut messages = vec![MessageParam::user("wrap it up")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        assert_eq!(outcome.request_count, 1);
+
+        let last = messages.last().unwrap();
+        assert_eq!(last.role, MessageRole::User);
+        let MessageParamContent::Array(blocks) = &last.content else {
+            panic!("expected tool results, got {:?}", last.content);
+        };
+        let ContentBlock::ToolResult(result) = &blocks[0] else {
+            panic!("expected a tool result, got {:?}", blocks[0]);
+        };
+        assert_eq!(result.tool_use_id, "toolu_finish");
+        assert_eq!(
+            result.content,
+            Some(ToolResultBlockContent::String("all done".to_string()))
+        );
+    }
+
+    #[test]
+    fn align_tool_results_reorders_and_dedupes() {
+        let tool_uses = vec![
+            ToolUseBlock::new("a", "counting", serde_json::json!({})),
+            ToolUseBlock::new("b", "counting", serde_json::json!({})),
+            ToolUseBlock::new("c", "counting", serde_json::json!({})),
+        ];
+        let results = vec![
+            ToolResultBlock::ok("c", "third"),
+            ToolResultBlock::ok("a", "first"),
+            ToolResultBlock::ok("stray", "ignored"),
+            ToolResultBlock::ok("b", "second"),
+            ToolResultBlock::ok("a", "duplicate"),
+        ];
+        let aligned = align_tool_results(&tool_uses, results).unwrap();
+        assert_eq!(
+            aligned,
+            vec![
+                ToolResultBlock::ok("a", "first"),
+                ToolResultBlock::ok("b", "second"),
+                ToolResultBlock::ok("c", "third"),
+            ]
+        );
+    }
+
+    #[test]
+    fn align_tool_results_reports_missing_ids() {
+        let tool_uses = vec![
+            ToolUseBlock::new("a", "counting", serde_json::json!({})),
+            ToolUseBlock::new("b", "counting", serde_json::json!({})),
+            ToolUseBlock::new("c", "counting", serde_json::json!({})),
+        ];
+        let err =
+            align_tool_results(&tool_uses, vec![ToolResultBlock::ok("b", "only")]).unwrap_err();
+        assert!(err.is_validation());
+        assert!(
+            err.to_string()
+                .contains("no tool result for tool_use ids: a, c"),
+            "{err}"
+        );
+    }
+
+    #[derive(Default)]
+    struct PauseLimitingAgent {
+        pauses: std::sync::Mutex<Vec<u32>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for PauseLimitingAgent {
+        async fn handle_pause_turn(&self, pauses: u32) -> Result<ControlFlow<StopReason>, Error> {
+            self.pauses.lock().unwrap().push(pauses);
+            if pauses >= 3 {
+                Ok(ControlFlow::Break(StopReason::PauseTurn))
+            } else {
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn handle_pause_turn_bails_after_repeated_pauses() {
+        let base_url = serve_json_responses(vec![
+            text_response("searching", "pause_turn"),
+            text_response("still searching", "pause_turn"),
+            text_response("searching more", "pause_turn"),
+            text_response("never sent", "end_turn"),
+        ]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = PauseLimitingAgent::default();
+        let mut messages = vec![MessageParam::user("research this")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::PauseTurn);
+        assert_eq!(outcome.request_count, 3);
+        assert_eq!(*agent.pauses.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[derive(Default)]
+    struct RetryingAgent {
+        messages_seen: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for RetryingAgent {
+        async fn request_retries(&self) -> u32 {
+            1
+        }
+
+        async fn hook_message(&self, _resp: &Message) -> Result<(), Error> {
+            self.messages_seen.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn request_retries_resend_after_transient_failure() {
+        let overloaded = serde_json::json!({
+            "type": "error",
+            "error": {"type": "overloaded_error", "message": "Overloaded"}
+        });
+        let server = MockServer::start([
+            Reply::json(overloaded.to_string()).with_status("503 Service Unavailable"),
+            Reply::json(text_response("recovered", "end_turn").to_string()),
+        ]);
+        let client = test_client(server.url()).with_max_retries(0);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = RetryingAgent::default();
+        let mut messages = vec![MessageParam::user("hi")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        assert_eq!(outcome.request_count, 1);
+        assert_eq!(agent.messages_seen.load(Ordering::Relaxed), 1);
+        assert_eq!(messages.len(), 2);
+
+        let requests = server.requests().await;
+        assert_eq!(requests[0].body(), requests[1].body());
+    }
+
+    struct RejectingAgent;
+
+    #[async_trait::async_trait]
+    impl Agent for RejectingAgent {
+        async fn hook_message(&self, _resp: &Message) -> Result<(), Error> {
+            Err(Error::validation("response rejected", None))
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_hook_message_still_charges_budget() {
+        let base_url = serve_json_responses(vec![text_response("hello", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = RejectingAgent;
+        let mut messages = vec![MessageParam::user("hi")];
+
+        let err = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("response rejected"), "{err}");
+        // 10 input and 5 output tokens at one micro-cent each.
+        assert_eq!(budget.remaining_micro_cents(), 1_000_000 - 15);
+    }
+
+    #[derive(Default)]
+    struct RefusalLoggingAgent {
+        refusals: std::sync::Mutex<Vec<Option<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for RefusalLoggingAgent {
+        async fn handle_refusal(
+            &self,
+            _resp: Message,
+            refusal_text: Option<String>,
+        ) -> Result<StopReason, Error> {
+            self.refusals.lock().unwrap().push(refusal_text);
+            Ok(StopReason::Refusal)
+        }
+    }
+
+    #[tokio::test]
+    async fn handle_refusal_receives_refusal_text() {
+        let base_url =
+            serve_json_responses(vec![text_response("I can't help with that.", "refusal")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = RefusalLoggingAgent::default();
+        let mut messages = vec![MessageParam::user("do something bad")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::Refusal);
+        assert_eq!(
+            *agent.refusals.lock().unwrap(),
+            vec![Some("I can't help with that.".to_string())]
+        );
+    }
+
+    #[derive(Default)]
+    struct BudgetWatchingAgent {
+        budget_exhausted: AtomicU64,
+        max_tokens_hit: AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for BudgetWatchingAgent {
+        async fn handle_max_tokens(&self) -> Result<StopReason, Error> {
+            self.max_tokens_hit.fetch_add(1, Ordering::Relaxed);
+            Ok(StopReason::MaxTokens)
+        }
+
+        async fn handle_budget_exhausted(&self) -> Result<StopReason, Error> {
+            self.budget_exhausted.fetch_add(1, Ordering::Relaxed);
+            Ok(StopReason::Refusal)
+        }
+    }
+
+    #[tokio::test]
+    async fn tiny_budget_calls_handle_budget_exhausted() {
+        let client = test_client("http://127.0.0.1:1".to_string());
+        let budget = Arc::new(Budget::new_flat_rate(10, 1));
+        let mut agent = BudgetWatchingAgent::default();
+        let mut messages = vec![MessageParam::user("hi")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::Refusal);
+        assert_eq!(outcome.request_count, 0);
+        assert_eq!(agent.budget_exhausted.load(Ordering::Relaxed), 1);
+        assert_eq!(agent.max_tokens_hit.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn model_max_tokens_calls_handle_max_tokens() {
+        let base_url = serve_json_responses(vec![text_response("cut", "max_tokens")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = BudgetWatchingAgent::default();
+        let mut messages = vec![MessageParam::user("hi")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::MaxTokens);
+        assert_eq!(agent.budget_exhausted.load(Ordering::Relaxed), 0);
+        assert_eq!(agent.max_tokens_hit.load(Ordering::Relaxed), 1);
+    }
+
+    #[derive(Default)]
+    struct StopSequenceAgent {
+        matched: std::sync::Mutex<Option<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for StopSequenceAgent {
+        async fn stop_sequences(&self) -> Option<Vec<String>> {
+            Some(vec!["###".to_string()])
+        }
+
+        async fn handle_stop_sequence(
+            &self,
+            sequence: Option<String>,
+        ) -> Result<StopReason, Error> {
+            *self.matched.lock().unwrap() = sequence;
+            Ok(StopReason::StopSequence)
+        }
+    }
+
+    #[tokio::test]
+    async fn handle_stop_sequence_receives_matched_sequence() {
+        let mut response = text_response("before", "stop_sequence");
+        response["stop_sequence"] = serde_json::json!("###");
+        let base_url = serve_json_responses(vec![response]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = StopSequenceAgent::default();
+        let mut messages = vec![MessageParam::user("hi")];
+
+        let outcome = agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::StopSequence);
+        assert_eq!(agent.matched.lock().unwrap().as_deref(), Some("###"));
+    }
+
+    #[derive(Default)]
+    struct BashAgent {
+        session: BashSession,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for BashAgent {
+        async fn bash_session(&self) -> Option<&BashSession> {
+            Some(&self.session)
+        }
+    }
+
+    async fn run_bash_tool(agent: &BashAgent, input: serde_json::Value) -> ToolResult {
+        let client = test_client("http://127.0.0.1:1".to_string());
+        let tool_use = ToolUseBlock::new("toolu_bash", "bash", input);
+        let intermediate = <BashCallback as ToolCallback<BashAgent>>::compute_tool_result(
+            &BashCallback,
+            &client,
+            agent,
+            &tool_use,
+        )
+        .await;
+        intermediate
+            .as_any()
+            .downcast_ref::<ToolResult>()
+            .unwrap()
+            .clone()
+    }
+
+    fn tool_result_text(result: ToolResult) -> String {
+        match result {
+            ControlFlow::Continue(Ok(block)) => match block.content {
+                Some(ToolResultBlockContent::String(text)) => text,
+                other => panic!("unexpected content: {other:?}"),
+            },
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn bash_input_without_restart_deserializes() {
+        let agent = BashAgent::default();
+        let result = run_bash_tool(&agent, serde_json::json!({"command": "echo hi"})).await;
+        assert_eq!(tool_result_text(result), "hi");
+    }
+
+    #[tokio::test]
+    async fn bash_session_persists_until_restart() {
+        let agent = BashAgent::default();
+        run_bash_tool(
+            &agent,
+            serde_json::json!({"command": "export GREETING=hello"}),
+        )
+        .await;
+        let result = run_bash_tool(&agent, serde_json::json!({"command": "echo $GREETING"})).await;
+        assert_eq!(tool_result_text(result), "hello");
+
+        run_bash_tool(&agent, serde_json::json!({"command": "", "restart": true})).await;
+        let result = run_bash_tool(
+            &agent,
+            serde_json::json!({"command": "echo \"[$GREETING]\"", "restart": false}),
+        )
+        .await;
+        assert_eq!(tool_result_text(result), "[]");
+    }
+
+    #[tokio::test]
+    async fn bash_session_reports_exit_status() {
+        let session = BashSession::new();
+        let output = session.run("echo oops >&2; false", false).await.unwrap();
+        assert_eq!(output, "oops\n[exit status 1]");
+    }
+
+    #[tokio::test]
+    async fn bash_session_keeps_blank_lines_and_ignores_stdin() {
+        let session = BashSession::new();
+        let output = session.run("printf 'a\\n\\n'; cat; echo b", false).await;
+        assert_eq!(output.unwrap(), "a\n\nb");
+    }
+
+    #[tokio::test]
+    async fn bash_session_times_out() {
+        let session = BashSession::new().with_timeout(Duration::from_millis(100));
+        let err = session.run("sleep 5", false).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert_eq!(session.run("echo again", false).await.unwrap(), "again");
+    }
+
+    #[tokio::test]
+    async fn bash_input_requires_command() {
+        let agent = BashAgent::default();
+        let result = run_bash_tool(&agent, serde_json::json!({"restart": false})).await;
+        assert!(
+            matches!(result, ControlFlow::Continue(Err(_))),
+            "{result:?}"
+        );
+    }
+
+    #[derive(Default)]
+    struct ToolObservingAgent {
+        events: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for ToolObservingAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![Arc::new(CountingTool {
+                conversions: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            })]
+        }
+
+        async fn hook_tool_start(&self, tool_use: &ToolUseBlock) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("start {}", tool_use.id));
+        }
+
+        async fn hook_tool_end(&self, tool_use: &ToolUseBlock, elapsed: Duration) {
+            assert!(elapsed < Duration::from_secs(60));
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("end {}", tool_use.id));
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_hooks_fire_in_order() {
+        let tool_uses = serde_json::json!({
+            "id": "msg_tools",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [
+                {"type": "tool_use", "id": "toolu_a", "name": "counting", "input": {}},
+                {"type": "tool_use", "id": "toolu_b", "name": "missing", "input": {}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 10, "output_tokens": 5}
+        });
+        let base_url = serve_json_responses(vec![tool_uses, text_response("done", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let mut agent = ToolObservingAgent::default();
+        let mut messages = vec![MessageParam::user("go")];
+
+        agent
+            .take_turn(&client, &mut messages, &budget)
+            .await
+            .unwrap();
+        assert_eq!(
+            *agent.events.lock().unwrap(),
+            vec![
+                "start toolu_a",
+                "end toolu_a",
+                "start toolu_b",
+                "end toolu_b"
+            ]
+        );
+    }
+
+    struct ToolChoiceAgent {
+        tool_choice: ToolChoice,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for ToolChoiceAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![Arc::new(CountingTool {
+                conversions: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            })]
+        }
+
+        async fn tool_choice(&self) -> Option<ToolChoice> {
+            Some(self.tool_choice.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_choice_none_omits_tools() {
+        let agent = ToolChoiceAgent {
+            tool_choice: ToolChoice::none(),
+        };
+        let turn = TurnConfig::for_agent(&agent).await;
+        let req = agent
+            .create_request_with_turn(1024, vec![MessageParam::user("hi")], false, &turn)
+            .await;
+        assert!(req.tools.is_none());
+        assert!(req.tool_choice.is_none());
+    }
+
+    #[tokio::test]
+    async fn tool_choice_none_sends_tools_the_history_needs() {
+        let agent = ToolChoiceAgent {
+            tool_choice: ToolChoice::none(),
+        };
+        let messages = vec![
+            MessageParam::user("count"),
+            MessageParam::new_with_blocks(
+                vec![ContentBlock::ToolUse(ToolUseBlock::new(
+                    "toolu_1",
+                    "counting",
+                    serde_json::json!({}),
+                ))],
+                MessageRole::Assistant,
+            ),
+            MessageParam::new_with_blocks(
+                vec![ContentBlock::ToolResult(
+                    ToolResultBlock::new("toolu_1".to_string())
+                        .with_string_content("counted".to_string()),
+                )],
+                MessageRole::User,
+            ),
+        ];
+        let turn = TurnConfig::for_agent(&agent).await;
+        let req = agent
+            .create_request_with_turn(1024, messages, false, &turn)
+            .await;
+        assert_eq!(req.tools.map(|tools| tools.len()), Some(1));
+        assert_eq!(
+            serde_json::to_value(req.tool_choice).unwrap(),
+            serde_json::json!({"type": "none"})
+        );
+    }
+
+    #[tokio::test]
+    async fn tool_choice_any_sends_tools_and_flag() {
+        let agent = ToolChoiceAgent {
+            tool_choice: ToolChoice::any_with_disable_parallel(true),
+        };
+        let turn = TurnConfig::for_agent(&agent).await;
+        let req = agent
+            .create_request_with_turn(1024, vec![MessageParam::user("hi")], false, &turn)
+            .await;
+        assert_eq!(req.tools.map(|tools| tools.len()), Some(1));
+        assert_eq!(
+            serde_json::to_value(req.tool_choice).unwrap(),
+            serde_json::json!({"type": "any", "disable_parallel_tool_use": true})
+        );
+    }
+
+    struct RunCountingTool {
+        runs: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    struct RunCountingCallback<'a>(&'a RunCountingTool);
+
+    #[async_trait::async_trait]
+    impl<A: Agent> ToolCallback<A> for RunCountingCallback<'_> {
+        async fn compute_tool_result(
+            &self,
+            _client: &Anthropic,
+            _agent: &A,
+            _tool_use: &ToolUseBlock,
+        ) -> Box<dyn IntermediateToolResult> {
+            self.0.runs.fetch_add(1, Ordering::Relaxed);
+            Box::new(())
+        }
+
+        async fn apply_tool_result(
+            &self,
+            _client: &Anthropic,
+            _agent: &mut A,
+            tool_use: &ToolUseBlock,
+            _intermediate: Box<dyn IntermediateToolResult>,
+        ) -> ToolResult {
+            ControlFlow::Continue(Ok(
+                ToolResultBlock::new(tool_use.id.clone()).with_string_content("sunny".to_string())
+            ))
+        }
+    }
+
+    impl<A: Agent> Tool<A> for RunCountingTool {
+        fn name(&self) -> String {
+            "get_weather".to_string()
+        }
+
+        fn callback(&self) -> Box<dyn ToolCallback<A> + '_> {
+            Box::new(RunCountingCallback(self))
+        }
+
+        fn to_param(&self) -> ToolUnionParam {
+            ToolUnionParam::CustomTool(ToolParam::new(
+                "get_weather".to_string(),
+                serde_json::json!({"type": "object"}),
+            ))
+        }
+    }
+
+    struct ResumingAgent {
+        tool: Arc<RunCountingTool>,
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for ResumingAgent {
+        async fn tools(&self) -> Vec<Arc<dyn Tool<Self>>> {
+            vec![self.tool.clone()]
+        }
+    }
+
+    #[tokio::test]
+    async fn resume_runs_pending_tool_use_once() {
+        let base_url = serve_json_responses(vec![text_response("It is sunny.", "end_turn")]);
+        let client = test_client(base_url);
+        let budget = Arc::new(Budget::new_flat_rate(1_000_000, 1));
+        let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut agent = ResumingAgent {
+            tool: Arc::new(RunCountingTool {
+                runs: Arc::clone(&runs),
+            }),
+        };
+        let tool_use = ToolUseBlock::new("toolu_1", "get_weather", serde_json::json!({}));
+        let mut messages = vec![
+            MessageParam::user("What's the weather?"),
+            MessageParam::new_with_blocks(
+                vec![ContentBlock::ToolUse(tool_use)],
+                MessageRole::Assistant,
+            ),
+        ];
+
+        let outcome = agent.resume(&client, &mut messages, &budget).await.unwrap();
+        assert_eq!(outcome.stop_reason, StopReason::EndTurn);
+        assert_eq!(outcome.request_count, 1);
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+
+        let roles: Vec<MessageRole> = messages.iter().map(|message| message.role).collect();
+        assert_eq!(
+            roles,
+            vec![
+                MessageRole::User,
+                MessageRole::Assistant,
+                MessageRole::User,
+                MessageRole::Assistant,
+            ]
+        );
+        let MessageParamContent::Array(results) = &messages[2].content else {
+            panic!("expected tool results");
+        };
+        assert!(
+            matches!(&results[0], ContentBlock::ToolResult(result) if result.tool_use_id == "toolu_1")
+        );
+    }
 }
@@ -27,6 +27,7 @@
 //! - `/model <name>` - Change the model
 //! - `/system [prompt]` - Set or clear system prompt
 //! - `/stats` - Show session statistics
+//! - `/tools` - List registered tools and their schemas
 //! - `/quit` - Exit the application
 
 use std::path::PathBuf;
@@ -39,10 +40,10 @@ use rustyline::error::ReadlineError;
 
 use claudius::Renderer;
 use claudius::chat::{
-    ChatAgent, ChatArgs, ChatCommand, ChatConfig, ChatSession, PlainTextRenderer, help_text,
-    parse_command,
+    ChatAgent, ChatArgs, ChatCommand, ChatConfig, ChatSession, PlainTextRenderer, SessionStats,
+    help_text, parse_command,
 };
-use claudius::{Anthropic, Model, SystemPrompt, ThinkingConfig};
+use claudius::{Anthropic, MessageCreateParams, Model, ThinkingConfig};
 
 /// Main entry point for the claudius-chat application.
 #[tokio::main]
@@ -100,38 +101,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 println!("    {}", line);
                             }
                         }
-                        ChatCommand::Model(model_name) => {
-                            let model = model_name
-                                .parse()
-                                .unwrap_or_else(|_| Model::Custom(model_name.clone()));
+                        ChatCommand::Model(model) => {
+                            let message = match &model {
+                                Model::Known(_) => format!("Model changed to: {}", model),
+                                Model::Custom(_) => format!(
+                                    "Model changed to: {} (not a known model; sent as-is)",
+                                    model
+                                ),
+                            };
                             session.template_mut().model = Some(model);
+                            renderer.print_info(&context, &message);
+                        }
+                        ChatCommand::SetSystem(prompt) => {
                             renderer
-                                .print_info(&context, &format!("Model changed to: {}", model_name));
+                                .print_info(&context, &format!("System prompt set to: {}", prompt));
+                            session.config_mut().set_system_prompt(Some(prompt));
                         }
-                        ChatCommand::System(prompt) => {
-                            session.template_mut().system = prompt.clone().map(SystemPrompt::from);
-                            match prompt {
-                                Some(p) => renderer
-                                    .print_info(&context, &format!("System prompt set to: {}", p)),
-                                None => renderer.print_info(&context, "System prompt cleared."),
-                            }
+                        ChatCommand::ClearSystem => {
+                            session.config_mut().set_system_prompt(None);
+                            renderer.print_info(&context, "System prompt cleared.");
                         }
                         ChatCommand::MaxTokens(value) => {
                             session.template_mut().max_tokens = Some(value);
                             renderer.print_info(&context, &format!("max_tokens set to {value}"));
                         }
                         ChatCommand::Temperature(value) => {
-                            session.template_mut().temperature = Some(value);
-                            renderer
-                                .print_info(&context, &format!("temperature set to {:.2}", value));
+                            let top_p = session.template().top_p;
+                            match MessageCreateParams::validate_sampling(Some(value), top_p) {
+                                Ok(()) => {
+                                    session.template_mut().temperature = Some(value);
+                                    renderer.print_info(
+                                        &context,
+                                        &format!("temperature set to {:.2}", value),
+                                    );
+                                }
+                                Err(err) => renderer.print_error(&context, &err.to_string()),
+                            }
                         }
                         ChatCommand::ClearTemperature => {
                             session.template_mut().temperature = None;
                             renderer.print_info(&context, "temperature reset to model default");
                         }
                         ChatCommand::TopP(value) => {
-                            session.template_mut().top_p = Some(value);
-                            renderer.print_info(&context, &format!("top_p set to {:.2}", value));
+                            let temperature = session.template().temperature;
+                            match MessageCreateParams::validate_sampling(temperature, Some(value)) {
+                                Ok(()) => {
+                                    session.template_mut().top_p = Some(value);
+                                    renderer.print_info(
+                                        &context,
+                                        &format!("top_p set to {:.2}", value),
+                                    );
+                                }
+                                Err(err) => renderer.print_error(&context, &err.to_string()),
+                            }
                         }
                         ChatCommand::ClearTopP => {
                             session.template_mut().top_p = None;
@@ -236,6 +258,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         ChatCommand::ShowConfig => {
                             print_config(&session);
                         }
+                        ChatCommand::Tools => {
+                            let tools = session.describe_tools().await;
+                            if tools.is_empty() {
+                                println!("    No tools registered.");
+                            }
+                            for tool in tools {
+                                println!("{tool}\n");
+                            }
+                        }
                         ChatCommand::Invalid(message) => {
                             renderer.print_error(&context, &message);
                         }
@@ -246,8 +277,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 // Regular message - send to API
                 println!("Claude:");
                 let message = claudius::MessageParam::user(line);
-                if let Err(e) = session.send_message(message, &mut renderer).await {
-                    renderer.print_error(&context, &e.to_string());
+                let before = session.stats();
+                match session.send_message(message, &mut renderer).await {
+                    Ok(()) => print_turn_footer(&mut renderer, &before, &session.stats()),
+                    Err(e) => renderer.print_error(&context, &e.to_string()),
                 }
             }
             Err(ReadlineError::Interrupted) => {
@@ -296,6 +329,14 @@ fn print_stats<A: ChatAgent>(session: &ChatSession<A>) {
         "      Total tokens: {} in / {} out ({} requests)",
         stats.total_input_tokens, stats.total_output_tokens, stats.total_requests
     );
+    match stats.tokens_per_second() {
+        Some(rate) => println!(
+            "      Throughput: {:.1} output tokens/s over {:.1}s",
+            rate,
+            stats.elapsed.as_secs_f64()
+        ),
+        None => println!("      Throughput: (no responses yet)"),
+    }
     if stats.caching_enabled {
         println!(
             "      Cache tokens: {} created / {} read",
@@ -321,6 +362,22 @@ fn print_stats<A: ChatAgent>(session: &ChatSession<A>) {
     }
 }
 
+fn print_turn_footer(renderer: &mut dyn Renderer, before: &SessionStats, after: &SessionStats) {
+    let output = after
+        .streamed_output_tokens
+        .saturating_sub(before.streamed_output_tokens);
+    let secs = after.elapsed.saturating_sub(before.elapsed).as_secs_f64();
+    if secs > 0.0 {
+        renderer.print_info(
+            &(),
+            &format!(
+                "[{output} output tokens in {secs:.1}s, {:.1} tokens/s]",
+                output as f64 / secs
+            ),
+        );
+    }
+}
+
 fn print_config<A: ChatAgent>(session: &ChatSession<A>) {
     let stats = session.stats();
     println!("    Current Configuration:");
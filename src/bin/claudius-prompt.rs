@@ -274,6 +274,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     println!("Block {}: Web Search Tool Result", idx);
                                     println!("  Result: {:?}", web_search_result);
                                 }
+                                claudius::ContentBlock::Compaction(compaction_block) => {
+                                    println!("Block {}: Compaction", idx);
+                                    println!("  Content: {:?}", compaction_block.content);
+                                }
                             }
                         }
                     } else if !result.api_success {
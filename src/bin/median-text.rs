@@ -36,6 +36,7 @@ Output the corrected/unified document and only the corrected/unified document.
 "#.into()),
         metadata: None,
         output_format: None,
+        service_tier: None,
         stop_sequences: None,
         thinking: Some(ThinkingConfig::enabled(1024)),
         tools: None,
@@ -43,6 +44,8 @@ Output the corrected/unified document and only the corrected/unified document.
         tool_choice: None,
         top_k: None,
         top_p: None,
+        betas: None,
+        extra: Default::default(),
     };
     let client = Anthropic::new(None).expect("could not create anthropic client");
     let resp = client.send(create).await.expect("claude failed");
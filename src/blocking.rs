@@ -0,0 +1,138 @@
+//! A synchronous wrapper around [`Anthropic`] for callers outside an async context.
+//!
+//! [`BlockingAnthropic`] owns a current-thread Tokio runtime and drives each request to
+//! completion on it.  Because the runtime is owned by the client, its methods must not be
+//! called from within another Tokio runtime; use [`Anthropic`] directly there.
+
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    Anthropic, Error, Message, MessageCountTokensParams, MessageCreateParams, MessageStreamEvent,
+    MessageTokensCount, Result,
+};
+
+///////////////////////////////////////// BlockingAnthropic ////////////////////////////////////////
+
+/// A blocking client for the Anthropic API.
+#[derive(Debug)]
+pub struct BlockingAnthropic {
+    client: Anthropic,
+    runtime: Runtime,
+}
+
+impl BlockingAnthropic {
+    /// Creates a blocking client, reading the API key from the environment when `api_key` is
+    /// `None`, exactly as [`Anthropic::new`] does.
+    pub fn new(api_key: Option<String>) -> Result<Self> {
+        Self::from_client(Anthropic::new(api_key)?)
+    }
+
+    /// Wraps an already configured async client in a new current-thread runtime.
+    pub fn from_client(client: Anthropic) -> Result<Self> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| Error::io("failed to create a tokio runtime", err))?;
+        Ok(Self { client, runtime })
+    }
+
+    /// Returns the wrapped async client.
+    pub fn client(&self) -> &Anthropic {
+        &self.client
+    }
+
+    /// Sends a message and blocks until the response arrives.
+    pub fn send(&self, params: MessageCreateParams) -> Result<Message> {
+        self.runtime.block_on(self.client.send(params))
+    }
+
+    /// Counts the tokens `params` would use, blocking until the count arrives.
+    pub fn count_tokens(&self, params: MessageCountTokensParams) -> Result<MessageTokensCount> {
+        self.runtime.block_on(self.client.count_tokens(params))
+    }
+
+    /// Starts a streaming request and returns an iterator over its events.
+    ///
+    /// Each call to [`Iterator::next`] blocks on the runtime until the next event arrives.
+    pub fn stream(&self, params: &MessageCreateParams) -> Result<BlockingStream<'_>> {
+        let stream = self.runtime.block_on(self.client.stream(params))?;
+        Ok(BlockingStream {
+            runtime: &self.runtime,
+            stream: Box::pin(stream),
+        })
+    }
+}
+
+////////////////////////////////////////// BlockingStream //////////////////////////////////////////
+
+/// An iterator over the events of a streaming response, returned by
+/// [`BlockingAnthropic::stream`].
+pub struct BlockingStream<'a> {
+    runtime: &'a Runtime,
+    stream: Pin<Box<dyn Stream<Item = Result<MessageStreamEvent>> + 'a>>,
+}
+
+impl Iterator for BlockingStream<'_> {
+    type Item = Result<MessageStreamEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+impl std::fmt::Debug for BlockingStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingStream").finish_non_exhaustive()
+    }
+}
+
+/////////////////////////////////////////////// tests //////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockServer, Reply};
+    use crate::{ContentBlock, KnownModel, MessageParam, Model};
+
+    #[test]
+    fn send_blocks_until_response() {
+        let body = serde_json::json!({
+            "id": "msg_blocking",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [{"type": "text", "text": "Hello from a script"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 3, "output_tokens": 4}
+        })
+        .to_string();
+        let server = MockServer::start([Reply::json(body)]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let client = BlockingAnthropic::from_client(client).unwrap();
+
+        let message = client
+            .send(MessageCreateParams::new(
+                16,
+                vec![MessageParam::user("hi")],
+                Model::Known(KnownModel::ClaudeHaiku45),
+            ))
+            .unwrap();
+        assert_eq!(message.id, "msg_blocking");
+        assert!(matches!(
+            &message.content[..],
+            [ContentBlock::Text(text)] if text.text == "Hello from a script"
+        ));
+
+        let request = server.blocking_requests().remove(0);
+        assert!(
+            request.request_line().starts_with("POST /v1/messages"),
+            "{request:?}"
+        );
+    }
+}
@@ -79,6 +79,59 @@ pub fn apply_cache_control_to_messages(messages: &mut [MessageParam]) {
     }
 }
 
+/// Places a cache breakpoint after every `cache_every` blocks of a long context.
+///
+/// This is meant for large documents that have been split into many blocks: marking chunk
+/// boundaries lets later requests that share a prefix of the context read it from the cache.
+/// Breakpoints already present on `blocks` count against [`MAX_CACHE_BREAKPOINTS`], and an
+/// error is returned rather than exceeding it.  Breakpoints used elsewhere in the request, such
+/// as on the system prompt, are the caller's responsibility.
+pub fn cacheable_context(
+    mut blocks: Vec<ContentBlock>,
+    cache_every: usize,
+) -> Result<Vec<ContentBlock>, crate::Error> {
+    if cache_every == 0 {
+        return Err(crate::Error::validation(
+            "cache_every must be greater than 0",
+            Some("cache_every".to_string()),
+        ));
+    }
+    let existing = blocks.iter().filter(|b| block_has_cache_control(b)).count();
+    let boundaries: Vec<usize> = (cache_every - 1..blocks.len())
+        .step_by(cache_every)
+        .filter(|&idx| !block_has_cache_control(&blocks[idx]))
+        .collect();
+    if existing + boundaries.len() > MAX_CACHE_BREAKPOINTS {
+        return Err(crate::Error::validation(
+            format!(
+                "caching every {cache_every} of {} blocks needs {} cache breakpoints, exceeding the limit of {MAX_CACHE_BREAKPOINTS}",
+                blocks.len(),
+                existing + boundaries.len(),
+            ),
+            Some("cache_every".to_string()),
+        ));
+    }
+    for idx in boundaries {
+        let block = &mut blocks[idx];
+        match block {
+            ContentBlock::Image(image_block) => {
+                image_block.cache_control = Some(CacheControlEphemeral::new());
+            }
+            ContentBlock::Document(document_block) => {
+                document_block.cache_control = Some(CacheControlEphemeral::new());
+            }
+            _ => set_cache_control_on_block(block),
+        }
+        if !block_has_cache_control(block) {
+            return Err(crate::Error::validation(
+                format!("block {idx} does not support cache_control"),
+                Some(format!("blocks[{idx}]")),
+            ));
+        }
+    }
+    Ok(blocks)
+}
+
 /// Clears cache_control from all content blocks in a message.
 fn clear_cache_control_from_message(message: &mut MessageParam) {
     if let MessageParamContent::Array(blocks) = &mut message.content {
@@ -112,6 +165,9 @@ fn clear_cache_control_on_block(block: &mut ContentBlock) {
         ContentBlock::WebSearchToolResult(web_search_result) => {
             web_search_result.cache_control = None;
         }
+        ContentBlock::Compaction(compaction) => {
+            compaction.cache_control = None;
+        }
         // Thinking blocks don't support cache_control.
         ContentBlock::Thinking(_) | ContentBlock::RedactedThinking(_) => {}
     }
@@ -154,7 +210,8 @@ fn set_cache_control_on_block(block: &mut ContentBlock) {
         | ContentBlock::ServerToolUse(_)
         | ContentBlock::WebSearchToolResult(_)
         | ContentBlock::Thinking(_)
-        | ContentBlock::RedactedThinking(_) => {}
+        | ContentBlock::RedactedThinking(_)
+        | ContentBlock::Compaction(_) => {}
     }
 }
 
@@ -169,6 +226,64 @@ fn block_has_cache_control(block: &ContentBlock) -> bool {
         ContentBlock::WebSearchToolResult(web_search_result) => {
             web_search_result.cache_control.is_some()
         }
+        ContentBlock::Compaction(compaction) => compaction.cache_control.is_some(),
         ContentBlock::Thinking(_) | ContentBlock::RedactedThinking(_) => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_blocks(n: usize) -> Vec<ContentBlock> {
+        (0..n)
+            .map(|i| ContentBlock::Text(TextBlock::new(format!("chunk {i}"))))
+            .collect()
+    }
+
+    fn cached_indices(blocks: &[ContentBlock]) -> Vec<usize> {
+        blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block_has_cache_control(block))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    #[test]
+    fn cacheable_context_marks_chunk_boundaries() {
+        let blocks = cacheable_context(text_blocks(10), 3).unwrap();
+        assert_eq!(cached_indices(&blocks), vec![2, 5, 8]);
+    }
+
+    #[test]
+    fn cacheable_context_allows_exactly_max_breakpoints() {
+        let blocks = cacheable_context(text_blocks(8), 2).unwrap();
+        assert_eq!(cached_indices(&blocks).len(), MAX_CACHE_BREAKPOINTS);
+    }
+
+    #[test]
+    fn cacheable_context_rejects_too_many_breakpoints() {
+        let err = cacheable_context(text_blocks(10), 2).unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("5 cache breakpoints"), "{err}");
+    }
+
+    #[test]
+    fn cacheable_context_counts_existing_breakpoints() {
+        let mut blocks = text_blocks(6);
+        blocks[0] = ContentBlock::Text(
+            TextBlock::new("pinned").with_cache_control(CacheControlEphemeral::new()),
+        );
+        blocks[1] = ContentBlock::Text(
+            TextBlock::new("pinned").with_cache_control(CacheControlEphemeral::new()),
+        );
+        assert!(cacheable_context(blocks.clone(), 2).is_ok());
+        assert!(cacheable_context(blocks, 1).is_err());
+    }
+
+    #[test]
+    fn cacheable_context_rejects_zero_interval() {
+        assert!(cacheable_context(text_blocks(3), 0).is_err());
+    }
+}
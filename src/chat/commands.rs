@@ -4,6 +4,8 @@
 //! allowing users to control the chat session without sending messages
 //! to the API.
 
+use crate::types::{MessageCreateParams, Model};
+
 /// A parsed chat command.
 ///
 /// These commands control the chat session and are not sent to the API.
@@ -12,12 +14,17 @@ pub enum ChatCommand {
     /// Clear the conversation history.
     Clear,
 
-    /// Change the model.
-    Model(String),
+    /// Change the model used for subsequent turns.
+    ///
+    /// Names that match a [`KnownModel`](crate::KnownModel) parse to [`Model::Known`]; anything
+    /// else is kept as [`Model::Custom`] and sent to the API as-is.
+    Model(Model),
+
+    /// Set the system prompt used for subsequent turns.
+    SetSystem(String),
 
-    /// Set or clear the system prompt.
-    /// `None` clears the current system prompt.
-    System(Option<String>),
+    /// Clear the system prompt.
+    ClearSystem,
 
     /// Set the maximum tokens per response.
     MaxTokens(u32),
@@ -86,6 +93,9 @@ pub enum ChatCommand {
     /// Show the current configuration.
     ShowConfig,
 
+    /// List the registered tools and the schemas they send.
+    Tools,
+
     /// Report a parsing error back to the caller.
     Invalid(String),
 }
@@ -117,18 +127,31 @@ pub fn parse_command(input: &str) -> Option<ChatCommand> {
     let result = match command.as_str() {
         "clear" => ChatCommand::Clear,
         "model" => match argument {
-            Some(model) => ChatCommand::Model(model.to_string()),
+            Some(model) if model.contains(char::is_whitespace) => {
+                ChatCommand::Invalid("/model expects a single model name".to_string())
+            }
+            Some(model) => match model.parse::<Model>() {
+                Ok(model) => ChatCommand::Model(model),
+                Err(()) => ChatCommand::Model(Model::Custom(model.to_string())),
+            },
             None => ChatCommand::Invalid("/model requires a model name".to_string()),
         },
-        "system" => ChatCommand::System(argument.map(|s| s.to_string())),
+        "system" => match argument {
+            Some(prompt) => ChatCommand::SetSystem(prompt.to_string()),
+            None => ChatCommand::ClearSystem,
+        },
+        "system-clear" => ChatCommand::ClearSystem,
         "help" | "?" => ChatCommand::Help,
         "quit" | "exit" | "q" => ChatCommand::Quit,
         "stats" | "status" => ChatCommand::Stats,
         "config" => ChatCommand::ShowConfig,
+        "tools" => ChatCommand::Tools,
         "max_tokens" => parse_u32_command(argument, ChatCommand::MaxTokens, "/max_tokens"),
         "temperature" => match argument {
             Some(arg) if arg.eq_ignore_ascii_case("clear") => ChatCommand::ClearTemperature,
-            Some(arg) => match parse_f32_in_range(arg, 0.0, 1.0) {
+            Some(arg) => match parse_sampling_value(arg, |value| {
+                MessageCreateParams::validate_sampling(Some(value), None)
+            }) {
                 Ok(value) => ChatCommand::Temperature(value),
                 Err(err) => ChatCommand::Invalid(format!("/temperature {err}")),
             },
@@ -136,7 +159,9 @@ pub fn parse_command(input: &str) -> Option<ChatCommand> {
         },
         "top_p" => match argument {
             Some(arg) if arg.eq_ignore_ascii_case("clear") => ChatCommand::ClearTopP,
-            Some(arg) => match parse_f32_in_range(arg, 0.0, 1.0) {
+            Some(arg) => match parse_sampling_value(arg, |value| {
+                MessageCreateParams::validate_sampling(None, Some(value))
+            }) {
                 Ok(value) => ChatCommand::TopP(value),
                 Err(err) => ChatCommand::Invalid(format!("/top_p {err}")),
             },
@@ -219,15 +244,15 @@ where
     }
 }
 
-fn parse_f32_in_range(value: &str, min: f32, max: f32) -> Result<f32, String> {
+fn parse_sampling_value(
+    value: &str,
+    validate: impl FnOnce(f32) -> Result<(), crate::Error>,
+) -> Result<f32, String> {
     let parsed: f32 = value
         .parse()
-        .map_err(|_| format!("expects a value between {min} and {max}"))?;
-    if parsed.is_finite() && parsed >= min && parsed <= max {
-        Ok(parsed)
-    } else {
-        Err(format!("expects a value between {min} and {max}"))
-    }
+        .map_err(|_| "expects a number between 0.0 and 1.0".to_string())?;
+    validate(parsed).map_err(|err| err.to_string())?;
+    Ok(parsed)
 }
 
 /// Default thinking budget when enabled without a specific value.
@@ -272,9 +297,10 @@ pub fn help_text() -> &'static str {
   /clear                 Clear conversation history
   /model <name>          Change the model (e.g., /model claude-sonnet-4-0)
   /system [prompt]       Set system prompt (no argument clears it)
+  /system-clear          Clear the system prompt
   /max_tokens <n>        Set maximum response tokens
   /temperature <v>       Set temperature 0.0-1.0 (use 'clear' to reset)
-  /top_p <v>             Set top-p 0.0-1.0 (use 'clear' to reset)
+  /top_p <v>             Set top-p in (0.0, 1.0] (use 'clear' to reset)
   /top_k <n>             Set top-k (use 'clear' to reset)
   /stop add <seq>        Add a stop sequence
   /stop clear            Clear all stop sequences
@@ -287,6 +313,7 @@ pub fn help_text() -> &'static str {
   /load <file>           Load a transcript from disk
   /stats                 Show session statistics
   /config                Show current configuration
+  /tools                 List registered tools and their schemas
   /help                  Show this help message
   /quit                  Exit the chat"#
 }
@@ -294,6 +321,7 @@ pub fn help_text() -> &'static str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::KnownModel;
 
     #[test]
     fn parse_quit_commands() {
@@ -313,12 +341,22 @@ mod tests {
     fn parse_model() {
         assert_eq!(
             parse_command("/model claude-sonnet-4-0"),
-            Some(ChatCommand::Model("claude-sonnet-4-0".to_string()))
+            Some(ChatCommand::Model(Model::Known(KnownModel::ClaudeSonnet40)))
         );
         assert_eq!(
             parse_command("/model   claude-haiku-4-5  "),
-            Some(ChatCommand::Model("claude-haiku-4-5".to_string()))
+            Some(ChatCommand::Model(Model::Known(KnownModel::ClaudeHaiku45)))
         );
+        assert_eq!(
+            parse_command("/model claude-3-5-haiku-latest"),
+            Some(ChatCommand::Model(Model::Custom(
+                "claude-3-5-haiku-latest".to_string()
+            )))
+        );
+        assert!(matches!(
+            parse_command("/model claude haiku"),
+            Some(ChatCommand::Invalid(msg)) if msg.contains("single model name")
+        ));
         assert_eq!(
             parse_command("/model"),
             Some(ChatCommand::Invalid(
@@ -331,11 +369,15 @@ mod tests {
     fn parse_system() {
         assert_eq!(
             parse_command("/system You are a helpful assistant"),
-            Some(ChatCommand::System(Some(
+            Some(ChatCommand::SetSystem(
                 "You are a helpful assistant".to_string()
-            )))
+            ))
+        );
+        assert_eq!(parse_command("/system"), Some(ChatCommand::ClearSystem));
+        assert_eq!(
+            parse_command("/system-clear"),
+            Some(ChatCommand::ClearSystem)
         );
-        assert_eq!(parse_command("/system"), Some(ChatCommand::System(None)));
     }
 
     #[test]
@@ -352,6 +394,19 @@ mod tests {
             parse_command("/temperature"),
             Some(ChatCommand::Invalid(msg)) if msg.contains("requires")
         ));
+        assert!(matches!(
+            parse_command("/temperature 1.5"),
+            Some(ChatCommand::Invalid(msg)) if msg.contains("temperature")
+        ));
+    }
+
+    #[test]
+    fn parse_top_p() {
+        assert_eq!(parse_command("/top_p 0.9"), Some(ChatCommand::TopP(0.9)));
+        assert!(matches!(
+            parse_command("/top_p 0"),
+            Some(ChatCommand::Invalid(msg)) if msg.contains("greater than 0.0")
+        ));
     }
 
     #[test]
@@ -426,6 +481,7 @@ mod tests {
     fn parse_stats_and_config() {
         assert_eq!(parse_command("/stats"), Some(ChatCommand::Stats));
         assert_eq!(parse_command("/config"), Some(ChatCommand::ShowConfig));
+        assert_eq!(parse_command("/tools"), Some(ChatCommand::Tools));
     }
 
     #[test]
@@ -8,7 +8,9 @@ use std::path::PathBuf;
 use arrrg_derive::CommandLine;
 
 use crate::Budget;
-use crate::types::{KnownModel, MessageCreateTemplate, Model, SystemPrompt, ThinkingConfig};
+use crate::types::{
+    KnownModel, MessageCreateParams, MessageCreateTemplate, Model, SystemPrompt, ThinkingConfig,
+};
 
 /// Default maximum tokens per response.
 const DEFAULT_MAX_TOKENS: u32 = 4096;
@@ -103,6 +105,12 @@ impl TryFrom<ChatArgs> for MessageCreateTemplate {
             template.top_p = Some(parse_f32_arg(top_p, "top-p")?);
         }
 
+        MessageCreateParams::validate_sampling(template.temperature, template.top_p).map_err(
+            |err| ChatArgsError {
+                message: format!("invalid sampling options: {err}"),
+            },
+        )?;
+
         template.top_k = args.top_k;
 
         if let Some(thinking) = args.thinking {
@@ -370,7 +378,7 @@ mod tests {
             model: Some("claude-sonnet-4-0".to_string()),
             system: Some("You are helpful.".to_string()),
             max_tokens: Some(8192),
-            temperature: Some("0.7".to_string()),
+            temperature: None,
             top_p: Some("0.9".to_string()),
             top_k: Some(40),
             thinking: Some(2048),
@@ -380,7 +388,7 @@ mod tests {
         assert_eq!(config.model(), Model::Known(KnownModel::ClaudeSonnet40));
         assert_eq!(config.system_prompt_text(), Some("You are helpful."));
         assert_eq!(config.max_tokens(), 8192);
-        assert_eq!(config.template.temperature, Some(0.7));
+        assert_eq!(config.template.temperature, None);
         assert_eq!(config.template.top_p, Some(0.9));
         assert_eq!(config.template.top_k, Some(40));
         assert_eq!(config.thinking_budget(), Some(2048));
@@ -412,6 +420,23 @@ mod tests {
         assert!(err.message.contains("--top-p"));
     }
 
+    #[test]
+    fn config_from_args_rejects_invalid_sampling() {
+        for (temperature, top_p) in [
+            (Some("0.7"), Some("0.9")),
+            (None, Some("0")),
+            (Some("1.5"), None),
+        ] {
+            let args = ChatArgs {
+                temperature: temperature.map(str::to_string),
+                top_p: top_p.map(str::to_string),
+                ..Default::default()
+            };
+            let err = ChatConfig::try_from(args).unwrap_err();
+            assert!(err.message.contains("sampling"), "{}", err.message);
+        }
+    }
+
     #[test]
     fn config_builder_pattern() {
         let config = ChatConfig::new()
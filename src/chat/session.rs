@@ -7,6 +7,7 @@ use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use serde_json::{from_reader, to_writer_pretty};
@@ -16,10 +17,12 @@ use crate::cache_control::apply_cache_control_to_messages;
 use crate::chat::config::ChatConfig;
 use crate::error::Result;
 use crate::types::{
-    CacheControlEphemeral, MessageCreateTemplate, MessageParam, Model, SystemPrompt, TextBlock,
-    Usage,
+    CacheControlEphemeral, MessageCreateTemplate, MessageDeltaUsage, MessageParam, Model,
+    SystemPrompt, TextBlock, Usage,
+};
+use crate::{
+    Agent, Anthropic, Budget, Renderer, StopReason, StreamContext, ThinkingConfig, TurnOutcome,
 };
-use crate::{Agent, Anthropic, Budget, Renderer, ThinkingConfig, TurnOutcome};
 
 const BUDGET_BUFFER_MICRO_CENTS: u64 = 1;
 
@@ -120,6 +123,7 @@ pub struct ChatSession<A: ChatAgent> {
     usage_totals: Usage,
     last_turn_usage: Option<Usage>,
     request_count: u64,
+    throughput: Throughput,
     budget: Arc<Budget>,
 }
 
@@ -166,6 +170,26 @@ pub struct SessionStats {
     pub total_cache_creation_tokens: u64,
     /// Total cache read tokens across all requests.
     pub total_cache_read_tokens: u64,
+    /// Total input and output tokens across all requests.
+    pub total_tokens: u64,
+    /// Output tokens reported by `message_delta` events as responses streamed.
+    pub streamed_output_tokens: u64,
+    /// Time from each response's `message_start` to its latest `message_delta`.
+    pub elapsed: Duration,
+}
+
+impl SessionStats {
+    /// Returns the streamed output tokens generated per second of response time.
+    ///
+    /// Returns `None` before any `message_delta` event has arrived.
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            Some(self.streamed_output_tokens as f64 / secs)
+        } else {
+            None
+        }
+    }
 }
 
 impl ChatSession<ConfigAgent> {
@@ -186,6 +210,7 @@ impl<A: ChatAgent> ChatSession<A> {
             usage_totals: Usage::new(0, 0),
             last_turn_usage: None,
             request_count: 0,
+            throughput: Throughput::default(),
             budget,
         }
     }
@@ -227,10 +252,20 @@ impl<A: ChatAgent> ChatSession<A> {
             apply_cache_control_to_messages(&mut self.messages);
         }
 
+        let mut renderer = MeteredRenderer {
+            inner: renderer,
+            throughput: &mut self.throughput,
+        };
         let outcome = self
             .agent
-            .take_turn_streaming_root(&self.client, &mut self.messages, &self.budget, renderer)
+            .take_turn_streaming_root(
+                &self.client,
+                &mut self.messages,
+                &self.budget,
+                &mut renderer,
+            )
             .await;
+        renderer.throughput.finish_message();
 
         match outcome {
             Ok(outcome) => {
@@ -275,6 +310,17 @@ impl<A: ChatAgent> ChatSession<A> {
         &mut self.agent.config_mut().template
     }
 
+    /// Returns a [`Tool::describe`](crate::Tool::describe) summary of each tool the agent
+    /// registers.
+    pub async fn describe_tools(&self) -> Vec<String> {
+        self.agent
+            .tools()
+            .await
+            .iter()
+            .map(|tool| tool.describe())
+            .collect()
+    }
+
     /// Saves the transcript to the specified path.
     pub fn save_transcript_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let transcript = TranscriptFile::new(&self.messages);
@@ -309,6 +355,8 @@ impl<A: ChatAgent> ChatSession<A> {
             }
             None => (None, 0),
         };
+        let total_input_tokens = tokens_to_u64(self.usage_totals.input_tokens);
+        let total_output_tokens = tokens_to_u64(self.usage_totals.output_tokens);
         SessionStats {
             model: config.model(),
             message_count: self.message_count(),
@@ -322,8 +370,8 @@ impl<A: ChatAgent> ChatSession<A> {
             session_budget_tokens,
             budget_spent_tokens,
             transcript_path: config.transcript_path.clone(),
-            total_input_tokens: tokens_to_u64(self.usage_totals.input_tokens),
-            total_output_tokens: tokens_to_u64(self.usage_totals.output_tokens),
+            total_input_tokens,
+            total_output_tokens,
             total_requests: self.request_count,
             last_turn_input_tokens: self
                 .last_turn_usage
@@ -342,6 +390,9 @@ impl<A: ChatAgent> ChatSession<A> {
                 .cache_read_input_tokens
                 .map(|t| t.max(0) as u64)
                 .unwrap_or(0),
+            total_tokens: total_input_tokens.saturating_add(total_output_tokens),
+            streamed_output_tokens: self.throughput.output_tokens(),
+            elapsed: self.throughput.elapsed(),
         }
     }
 
@@ -378,6 +429,140 @@ impl TranscriptFile {
     }
 }
 
+/// Output throughput accumulated from the `message_delta` events of streamed responses.
+#[derive(Debug, Default)]
+struct Throughput {
+    output_tokens: u64,
+    elapsed: Duration,
+    current: Option<StreamedMessage>,
+}
+
+/// The response currently streaming; its usage is cumulative, so later deltas replace earlier.
+#[derive(Debug)]
+struct StreamedMessage {
+    started: Instant,
+    output_tokens: u64,
+    elapsed: Duration,
+}
+
+impl Throughput {
+    fn start_message(&mut self, now: Instant) {
+        self.finish_message();
+        self.current = Some(StreamedMessage {
+            started: now,
+            output_tokens: 0,
+            elapsed: Duration::ZERO,
+        });
+    }
+
+    fn update_usage(&mut self, usage: &MessageDeltaUsage, now: Instant) {
+        if let Some(current) = self.current.as_mut() {
+            current.output_tokens = tokens_to_u64(usage.output_tokens);
+            current.elapsed = now.saturating_duration_since(current.started);
+        }
+    }
+
+    fn finish_message(&mut self) {
+        if let Some(current) = self.current.take() {
+            self.output_tokens = self.output_tokens.saturating_add(current.output_tokens);
+            self.elapsed += current.elapsed;
+        }
+    }
+
+    fn output_tokens(&self) -> u64 {
+        let current = self.current.as_ref().map_or(0, |c| c.output_tokens);
+        self.output_tokens.saturating_add(current)
+    }
+
+    fn elapsed(&self) -> Duration {
+        let current = self.current.as_ref().map_or(Duration::ZERO, |c| c.elapsed);
+        self.elapsed + current
+    }
+}
+
+/// Forwards to the caller's renderer while feeding streamed usage into the session throughput.
+struct MeteredRenderer<'a> {
+    inner: &'a mut dyn Renderer,
+    throughput: &'a mut Throughput,
+}
+
+impl Renderer for MeteredRenderer<'_> {
+    fn start_agent(&mut self, context: &dyn StreamContext) {
+        self.inner.start_agent(context);
+    }
+
+    fn finish_agent(&mut self, context: &dyn StreamContext, stop_reason: Option<&StopReason>) {
+        self.inner.finish_agent(context, stop_reason);
+    }
+
+    fn start_message(&mut self, context: &dyn StreamContext) {
+        self.throughput.start_message(Instant::now());
+        self.inner.start_message(context);
+    }
+
+    fn update_usage(&mut self, context: &dyn StreamContext, usage: &MessageDeltaUsage) {
+        self.throughput.update_usage(usage, Instant::now());
+        self.inner.update_usage(context, usage);
+    }
+
+    fn print_text(&mut self, context: &dyn StreamContext, text: &str) {
+        self.inner.print_text(context, text);
+    }
+
+    fn print_thinking(&mut self, context: &dyn StreamContext, text: &str) {
+        self.inner.print_thinking(context, text);
+    }
+
+    fn print_error(&mut self, context: &dyn StreamContext, error: &str) {
+        self.inner.print_error(context, error);
+    }
+
+    fn print_info(&mut self, context: &dyn StreamContext, info: &str) {
+        self.inner.print_info(context, info);
+    }
+
+    fn start_tool_use(&mut self, context: &dyn StreamContext, name: &str, id: &str) {
+        self.inner.start_tool_use(context, name, id);
+    }
+
+    fn print_tool_input(&mut self, context: &dyn StreamContext, partial_json: &str) {
+        self.inner.print_tool_input(context, partial_json);
+    }
+
+    fn finish_tool_use(&mut self, context: &dyn StreamContext) {
+        self.inner.finish_tool_use(context);
+    }
+
+    fn start_tool_result(
+        &mut self,
+        context: &dyn StreamContext,
+        tool_use_id: &str,
+        is_error: bool,
+    ) {
+        self.inner.start_tool_result(context, tool_use_id, is_error);
+    }
+
+    fn print_tool_result_text(&mut self, context: &dyn StreamContext, text: &str) {
+        self.inner.print_tool_result_text(context, text);
+    }
+
+    fn finish_tool_result(&mut self, context: &dyn StreamContext) {
+        self.inner.finish_tool_result(context);
+    }
+
+    fn finish_response(&mut self, context: &dyn StreamContext) {
+        self.inner.finish_response(context);
+    }
+
+    fn print_interrupted(&mut self, context: &dyn StreamContext) {
+        self.inner.print_interrupted(context);
+    }
+
+    fn should_interrupt(&self) -> bool {
+        self.inner.should_interrupt()
+    }
+}
+
 fn tokens_to_u64(value: i32) -> u64 {
     value.max(0) as u64
 }
@@ -398,6 +583,7 @@ fn budget_allows_next_turn(budget: &Budget, last_turn_usage: Option<&Usage>) ->
 mod tests {
     use super::*;
     use crate::cache_control::apply_cache_control_to_message;
+    use crate::test_support::{MockServer, Reply, sse_events};
     use crate::types::{KnownModel, SystemPrompt};
     use crate::{ContentBlock, MessageParamContent, MessageRole};
 
@@ -701,4 +887,125 @@ mod tests {
             }
         }
     }
+
+    /// Returns the events of a streamed turn that replies with `text`.
+    fn streamed_text_turn(id: &str, text: &str) -> Vec<serde_json::Value> {
+        use serde_json::json;
+
+        vec![
+            json!({"type": "message_start", "message": {
+                "id": id, "type": "message", "role": "assistant",
+                "model": "claude-haiku-4-5", "content": [], "stop_reason": null,
+                "usage": {"input_tokens": 10, "output_tokens": 1}
+            }}),
+            json!({"type": "content_block_start", "index": 0,
+                "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "text_delta", "text": text}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "message_delta",
+                "delta": {"stop_reason": "end_turn", "stop_sequence": null},
+                "usage": {"output_tokens": 5}}),
+            json!({"type": "message_stop"}),
+        ]
+    }
+
+    #[tokio::test]
+    async fn system_prompt_change_applies_to_next_turn() {
+        let server = MockServer::start([
+            Reply::sse(sse_events(&streamed_text_turn("msg_1", "ahoy"))),
+            Reply::sse(sse_events(&streamed_text_turn("msg_2", "hello"))),
+        ]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let config = ChatConfig::default()
+            .with_caching(false)
+            .with_system_prompt("Talk like a pirate.".to_string());
+        let mut session = ChatSession::new(client, config);
+        let mut renderer = crate::PlainTextRenderer::with_color(false);
+
+        session
+            .send_message(MessageParam::user("hi"), &mut renderer)
+            .await
+            .unwrap();
+        session
+            .config_mut()
+            .set_system_prompt(Some("Be formal.".to_string()));
+        session
+            .send_message(MessageParam::user("hi again"), &mut renderer)
+            .await
+            .unwrap();
+        session.config_mut().set_system_prompt(None);
+        assert_eq!(session.stats().system_prompt, None);
+
+        let requests: Vec<_> = server
+            .requests()
+            .await
+            .iter()
+            .map(|request| request.json())
+            .collect();
+        assert_eq!(requests[0]["system"], "Talk like a pirate.");
+        assert_eq!(requests[1]["system"], "Be formal.");
+        assert_eq!(requests[1]["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(session.message_count(), 4);
+    }
+
+    #[test]
+    fn throughput_follows_message_delta_events() {
+        use crate::MessageStreamEvent;
+
+        let client = Anthropic::new(None).unwrap();
+        let mut session = ChatSession::new(client, ChatConfig::default());
+        let start = Instant::now();
+        let events = streamed_text_turn("msg_1", "hello")
+            .into_iter()
+            .chain(streamed_text_turn("msg_2", "again"));
+        for (i, event) in events.enumerate() {
+            let now = start + Duration::from_millis(250 * i as u64);
+            match serde_json::from_value(event).unwrap() {
+                MessageStreamEvent::MessageStart(_) => session.throughput.start_message(now),
+                MessageStreamEvent::MessageDelta(delta) => {
+                    session.throughput.update_usage(&delta.usage, now)
+                }
+                _ => {}
+            }
+        }
+
+        // Each response reports 5 output tokens four events (one second) after it starts.
+        let stats = session.stats();
+        assert_eq!(stats.streamed_output_tokens, 10);
+        assert_eq!(stats.elapsed, Duration::from_secs(2));
+        assert_eq!(stats.tokens_per_second(), Some(5.0));
+        session.throughput.finish_message();
+        assert_eq!(session.stats().tokens_per_second(), Some(5.0));
+    }
+
+    #[tokio::test]
+    async fn stats_report_throughput_after_streamed_turn() {
+        let server = MockServer::start([Reply::sse(sse_events(&streamed_text_turn(
+            "msg_1", "hello",
+        )))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let mut session = ChatSession::new(client, ChatConfig::default());
+        assert_eq!(session.stats().tokens_per_second(), None);
+
+        let mut renderer = crate::PlainTextRenderer::with_color(false);
+        session
+            .send_message(MessageParam::user("hi"), &mut renderer)
+            .await
+            .unwrap();
+
+        let stats = session.stats();
+        assert_eq!(stats.total_output_tokens, 5);
+        assert_eq!(stats.streamed_output_tokens, 5);
+        assert_eq!(
+            stats.total_tokens,
+            stats.total_input_tokens + stats.total_output_tokens
+        );
+    }
 }
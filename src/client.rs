@@ -1,30 +1,98 @@
+use std::borrow::Cow;
 use std::env;
 use std::fs;
 use std::pin::Pin;
-use std::sync::Arc;
-use std::task::{Context, Poll};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 use std::time::{Duration, Instant};
 
-use futures::Stream;
-use reqwest::header::{HeaderMap, HeaderValue};
+use futures::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client as ReqwestClient, Response, header};
 use serde::Deserialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::time::sleep;
 
 use crate::AccumulatingStream;
 use crate::backoff::ExponentialBackoff;
 use crate::client_logger::ClientLogger;
 use crate::error::{Error, Result};
+use crate::middleware::{RequestMiddleware, RequestSigner, ResponseMiddleware};
 use crate::observability::{
     CLIENT_REQUEST_DURATION, CLIENT_REQUEST_ERRORS, CLIENT_REQUEST_RETRIES, CLIENT_REQUESTS,
     CLIENT_RETRY_BACKOFF,
 };
-use crate::sse::process_sse;
+use crate::rate_limit::TokenBucket;
+use crate::recording::Recorder;
+use crate::response_cache::ResponseCache;
+use crate::sse::process_sse_limited;
 use crate::types::{
-    Message, MessageCountTokensParams, MessageCreateParams, MessageStreamEvent, MessageTokensCount,
-    ModelInfo, ModelListParams, ModelListResponse,
+    BatchListParams, BatchListResponse, BatchProcessingStatus, BatchProgress, BatchResultEntry,
+    ContentBlock, Message, MessageBatch, MessageCountTokensParams, MessageCreateParams,
+    MessageParam, MessageRole, MessageStreamEvent, MessageTokensCount, ModelInfo, ModelListParams,
+    ModelListResponse, StopReason, ToolResultBlock, ToolUseBlock, parse_batch_results_jsonl,
 };
 
+/// One page of a cursor-paginated list endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// The items on this page.
+    pub data: Vec<T>,
+    /// Whether another page follows this one.
+    pub has_more: bool,
+    /// The ID of the last item on this page, used as the cursor for the next page.
+    pub last_id: Option<String>,
+}
+
+impl From<ModelListResponse> for Page<ModelInfo> {
+    fn from(response: ModelListResponse) -> Self {
+        Self {
+            data: response.data,
+            has_more: response.has_more,
+            last_id: response.last_id,
+        }
+    }
+}
+
+impl From<BatchListResponse> for Page<MessageBatch> {
+    fn from(response: BatchListResponse) -> Self {
+        Self {
+            data: response.data,
+            has_more: response.has_more,
+            last_id: response.last_id,
+        }
+    }
+}
+
+/// Walks a cursor-paginated endpoint, yielding every item of every page in order.
+///
+/// `fetch` is called with `None` for the first page and with the previous page's `last_id`
+/// for each page after it.  Paging stops once a page reports no more results or has no
+/// `last_id`.  An error from `fetch` is yielded and ends the stream.
+pub fn paginate<T, F, Fut>(fetch: F) -> impl Stream<Item = Result<T>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Page<T>>>,
+{
+    futures::stream::unfold(Some((fetch, None)), |state| async move {
+        let (mut fetch, cursor) = state?;
+        match fetch(cursor).await {
+            Ok(page) => {
+                let next = match page.last_id {
+                    Some(last_id) if page.has_more => Some((fetch, Some(last_id))),
+                    _ => None,
+                };
+                Some((Ok(page.data), next))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+    .flat_map(|page| match page {
+        Ok(items) => futures::stream::iter(items.into_iter().map(Ok)).left_stream(),
+        Err(err) => futures::stream::once(async move { Err(err) }).right_stream(),
+    })
+}
+
 /// A stream wrapper that logs events and the final message through a [`ClientLogger`].
 ///
 /// This stream passes through all events from the underlying [`AccumulatingStream`],
@@ -34,6 +102,7 @@ pub struct LoggingStream<'a> {
     inner: AccumulatingStream,
     logger: &'a dyn ClientLogger,
     receiver: Option<tokio::sync::oneshot::Receiver<Result<Message>>>,
+    response_middleware: Middlewares<dyn ResponseMiddleware>,
 }
 
 impl<'a> LoggingStream<'a> {
@@ -42,11 +111,13 @@ impl<'a> LoggingStream<'a> {
         inner: AccumulatingStream,
         receiver: tokio::sync::oneshot::Receiver<Result<Message>>,
         logger: &'a dyn ClientLogger,
+        response_middleware: Middlewares<dyn ResponseMiddleware>,
     ) -> Self {
         Self {
             inner,
             logger,
             receiver: Some(receiver),
+            response_middleware,
         }
     }
 }
@@ -65,9 +136,10 @@ impl Stream for LoggingStream<'_> {
             Poll::Ready(None) => {
                 // Stream ended - try to get the accumulated message
                 if let Some(mut receiver) = self.receiver.take()
-                    && let Ok(Ok(ref message)) = receiver.try_recv()
+                    && let Ok(Ok(mut message)) = receiver.try_recv()
                 {
-                    self.logger.log_stream_message(message);
+                    self.response_middleware.apply(&mut message);
+                    self.logger.log_stream_message(&message);
                 }
                 Poll::Ready(None)
             }
@@ -76,10 +148,95 @@ impl Stream for LoggingStream<'_> {
     }
 }
 
+/// The source of an [`AbortableStream`], shared with its [`StreamHandle`]s.
+#[derive(Default)]
+struct AbortState {
+    stream: Option<Pin<Box<dyn Stream<Item = Result<MessageStreamEvent>> + Send>>>,
+    aborted: bool,
+    waker: Option<Waker>,
+}
+
+/// A stream of events that can be ended early through a [`StreamHandle`].
+///
+/// Returned by [`Anthropic::stream_with_handle`].  It yields the same events as
+/// [`Anthropic::stream`] until the response ends or the request is aborted, after which it
+/// yields `None`.
+pub struct AbortableStream {
+    state: Arc<Mutex<AbortState>>,
+}
+
+impl Stream for AbortableStream {
+    type Item = Result<MessageStreamEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.state.lock().unwrap();
+        let Some(stream) = state.stream.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let poll = stream.as_mut().poll_next(cx);
+        match &poll {
+            Poll::Ready(None) => state.stream = None,
+            Poll::Pending => state.waker = Some(cx.waker().clone()),
+            Poll::Ready(Some(_)) => {}
+        }
+        poll
+    }
+}
+
+impl std::fmt::Debug for AbortableStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AbortableStream").finish_non_exhaustive()
+    }
+}
+
+/// Ends the request behind an [`AbortableStream`].
+///
+/// Handles are cheap to clone and may be used from any task, so a UI can stop a response
+/// that nothing is reading anymore.
+#[derive(Clone)]
+pub struct StreamHandle {
+    state: Arc<Mutex<AbortState>>,
+}
+
+impl StreamHandle {
+    /// Aborts the request, closing its connection now rather than when the stream is dropped.
+    ///
+    /// The stream yields `None` on its next poll.  Aborting a finished or already aborted
+    /// stream does nothing.
+    pub fn abort(&self) {
+        let (stream, waker) = {
+            let mut state = self.state.lock().unwrap();
+            state.aborted = true;
+            (state.stream.take(), state.waker.take())
+        };
+        drop(stream);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Returns true if [`abort`](Self::abort) has been called.
+    pub fn is_aborted(&self) -> bool {
+        self.state.lock().unwrap().aborted
+    }
+}
+
+impl std::fmt::Debug for StreamHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamHandle")
+            .field("aborted", &self.is_aborted())
+            .finish()
+    }
+}
+
 const DEFAULT_API_URL: &str = "https://api.anthropic.com";
 const ANTHROPIC_API_VERSION: &str = "2023-06-01";
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
-const STRUCTURED_OUTPUTS_BETA: &str = "structured-outputs-2025-11-13";
+const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 10;
+const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+/// Headers required by the API that custom headers may not override.
+const REQUIRED_HEADERS: &[&str] = &["content-type", "x-api-key", "anthropic-version"];
 
 /// Client for the Anthropic API with performance optimizations.
 #[derive(Debug, Clone)]
@@ -88,11 +245,82 @@ pub struct Anthropic {
     client: ReqwestClient,
     base_url: String,
     timeout: Duration,
+    /// Idle connections kept open per host by the HTTP client.
+    pool_max_idle_per_host: usize,
+    /// How long an idle pooled connection is kept before it is closed.
+    pool_idle_timeout: Duration,
     max_retries: usize,
     throughput_ops_sec: f64,
     reserve_capacity: f64,
     /// Cached headers for performance - Arc for cheap cloning
     cached_headers: Arc<HeaderMap>,
+    /// Betas sent with every message request, ahead of any per-request betas.
+    default_betas: Vec<String>,
+    /// Limits in-flight message requests across clones of this client; `None` is unlimited.
+    request_permits: Option<Arc<Semaphore>>,
+    /// Request and token allowances shared across clones of this client; `None` is unlimited.
+    rate_limits: Option<Arc<Mutex<TokenBucket>>>,
+    /// Whether a message with no content blocks is requested a second time.
+    retry_empty_responses: bool,
+    /// The most bytes read from one response body, or one stream in total.
+    max_response_bytes: usize,
+    /// Applied to every message request, in registration order.
+    request_middleware: Middlewares<dyn RequestMiddleware>,
+    /// Applied to every message received, in registration order.
+    response_middleware: Middlewares<dyn ResponseMiddleware>,
+    /// Consulted before every [`send`](Self::send); `None` disables caching.
+    response_cache: Option<SharedResponseCache>,
+    /// Run over every request body and its headers just before sending; `None` sends unsigned.
+    request_signer: Option<SharedRequestSigner>,
+}
+
+/// A response cache shared across clones of a client.
+#[derive(Clone)]
+struct SharedResponseCache(Arc<dyn ResponseCache>);
+
+impl std::fmt::Debug for SharedResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedResponseCache")
+    }
+}
+
+/// A request signer shared across clones of a client.
+#[derive(Clone)]
+struct SharedRequestSigner(Arc<dyn RequestSigner>);
+
+impl std::fmt::Debug for SharedRequestSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedRequestSigner")
+    }
+}
+
+/// The middleware of one kind registered with a client.
+struct Middlewares<M: ?Sized>(Vec<Arc<M>>);
+
+impl<M: ?Sized> Clone for Middlewares<M> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<M: ?Sized> Default for Middlewares<M> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<M: ?Sized> std::fmt::Debug for Middlewares<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Middlewares({})", self.0.len())
+    }
+}
+
+impl Middlewares<dyn ResponseMiddleware> {
+    fn apply(&self, message: &mut Message) {
+        for middleware in &self.0 {
+            middleware.transform(message);
+        }
+    }
 }
 
 impl Anthropic {
@@ -130,6 +358,9 @@ impl Anthropic {
     ///
     /// The base URL is resolved from the CLAUDIUS_BASE_URL or ANTHROPIC_BASE_URL environment
     /// variables, in that order. If neither is set, the default Anthropic API URL is used.
+    ///
+    /// With the `compression` feature (enabled by default), requests advertise
+    /// `accept-encoding: gzip, deflate` and compressed responses are decoded transparently.
     pub fn new(api_key: Option<String>) -> Result<Self> {
         let api_key = match api_key {
             Some(key) => Self::resolve_api_key(&key)?,
@@ -147,18 +378,17 @@ impl Anthropic {
         };
 
         let timeout = DEFAULT_TIMEOUT;
-        let client = ReqwestClient::builder()
-            .timeout(timeout)
-            .pool_max_idle_per_host(10) // Connection pooling optimization
-            .pool_idle_timeout(Duration::from_secs(90))
-            .tcp_keepalive(Duration::from_secs(60))
-            .build()
-            .map_err(|e| {
-                Error::http_client(
-                    format!("Failed to build HTTP client: {e}"),
-                    Some(Box::new(e)),
-                )
-            })?;
+        let client = Self::build_http_client(
+            timeout,
+            DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            DEFAULT_POOL_IDLE_TIMEOUT,
+        )
+        .map_err(|e| {
+            Error::http_client(
+                format!("Failed to build HTTP client: {e}"),
+                Some(Box::new(e)),
+            )
+        })?;
 
         // Pre-build headers for performance
         let cached_headers = Arc::new(Self::build_default_headers(&api_key)?);
@@ -173,10 +403,21 @@ impl Anthropic {
             client,
             base_url,
             timeout,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout: DEFAULT_POOL_IDLE_TIMEOUT,
             max_retries: 3,
             throughput_ops_sec: 1.0 / 60.0,
             reserve_capacity: 1.0 / 60.0,
             cached_headers,
+            default_betas: Vec::new(),
+            request_permits: None,
+            rate_limits: None,
+            retry_empty_responses: false,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            request_middleware: Middlewares::default(),
+            response_middleware: Middlewares::default(),
+            response_cache: None,
+            request_signer: None,
         })
     }
 
@@ -214,24 +455,64 @@ impl Anthropic {
     pub fn with_timeout(mut self, timeout: Duration) -> Result<Self> {
         self.timeout = timeout;
 
-        // Recreate the client with the new timeout and performance optimizations
-        let client = ReqwestClient::builder()
-            .timeout(timeout)
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(90))
-            .tcp_keepalive(Duration::from_secs(60))
-            .build()
-            .map_err(|e| {
+        // Recreate the client with the new timeout, keeping the pool settings
+        self.client =
+            Self::build_http_client(timeout, self.pool_max_idle_per_host, self.pool_idle_timeout)
+                .map_err(|e| {
                 Error::http_client(
                     "Failed to build HTTP client with new timeout",
                     Some(Box::new(e)),
                 )
             })?;
+        Ok(self)
+    }
+
+    /// Set how many idle connections the client keeps open per host.
+    ///
+    /// Reusing a pooled connection skips the TCP and TLS handshakes, which matters for agents
+    /// that make many short sequential calls.  Defaults to 10; 0 disables pooling.
+    pub fn with_pool_max_idle_per_host(mut self, max_idle: usize) -> Result<Self> {
+        self.pool_max_idle_per_host = max_idle;
+        self.client = Self::build_http_client(self.timeout, max_idle, self.pool_idle_timeout)
+            .map_err(|e| {
+                Error::http_client(
+                    "Failed to build HTTP client with new pool size",
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(self)
+    }
 
-        self.client = client;
+    /// Set how long an idle pooled connection is kept before it is closed.
+    ///
+    /// Defaults to 90 seconds.  Raise it to keep connections warm across pauses between calls.
+    pub fn with_pool_idle_timeout(mut self, idle_timeout: Duration) -> Result<Self> {
+        self.pool_idle_timeout = idle_timeout;
+        self.client =
+            Self::build_http_client(self.timeout, self.pool_max_idle_per_host, idle_timeout)
+                .map_err(|e| {
+                    Error::http_client(
+                        "Failed to build HTTP client with new pool idle timeout",
+                        Some(Box::new(e)),
+                    )
+                })?;
         Ok(self)
     }
 
+    /// Build the underlying HTTP client.
+    fn build_http_client(
+        timeout: Duration,
+        pool_max_idle_per_host: usize,
+        pool_idle_timeout: Duration,
+    ) -> std::result::Result<ReqwestClient, reqwest::Error> {
+        ReqwestClient::builder()
+            .timeout(timeout)
+            .pool_max_idle_per_host(pool_max_idle_per_host)
+            .pool_idle_timeout(pool_idle_timeout)
+            .tcp_keepalive(Duration::from_secs(60))
+            .build()
+    }
+
     /// Set the maximum number of retries for this client.
     ///
     /// This method allows you to specify how many times to retry failed requests.
@@ -261,6 +542,231 @@ impl Anthropic {
         self.with_base_url(base_url).with_timeout(timeout)
     }
 
+    /// Set the `user-agent` header sent with every request.
+    pub fn with_user_agent(self, user_agent: impl AsRef<str>) -> Result<Self> {
+        self.with_header(header::USER_AGENT.as_str(), user_agent)
+    }
+
+    /// Add a custom header to every request made by this client.
+    ///
+    /// Setting the same header again replaces the previous value.  The `x-api-key`,
+    /// `anthropic-version`, and `content-type` headers required by the API take precedence:
+    /// custom values for them are ignored.
+    pub fn with_header(mut self, name: impl AsRef<str>, value: impl AsRef<str>) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_ref().as_bytes()).map_err(|e| {
+            Error::validation(
+                format!("Invalid header name '{}': {e}", name.as_ref()),
+                Some("header".to_string()),
+            )
+        })?;
+        let value = HeaderValue::from_str(value.as_ref()).map_err(|e| {
+            Error::validation(
+                format!("Invalid value for header '{name}': {e}"),
+                Some("header".to_string()),
+            )
+        })?;
+        if !REQUIRED_HEADERS.contains(&name.as_str()) {
+            Arc::make_mut(&mut self.cached_headers).insert(name, value);
+        }
+        Ok(self)
+    }
+
+    /// Set the `anthropic-version` header sent with every request.
+    ///
+    /// Defaults to the version this crate is built against.  Pinning a different version lets
+    /// callers adopt an API revision without waiting for a new release.
+    pub fn with_api_version(mut self, version: impl AsRef<str>) -> Result<Self> {
+        let version = version.as_ref().trim();
+        if version.is_empty() {
+            return Err(Error::validation(
+                "API version must not be empty",
+                Some("api_version".to_string()),
+            ));
+        }
+        let value = HeaderValue::from_str(version).map_err(|e| {
+            Error::validation(
+                format!("Invalid API version '{version}': {e}"),
+                Some("api_version".to_string()),
+            )
+        })?;
+        Arc::make_mut(&mut self.cached_headers).insert("anthropic-version", value);
+        Ok(self)
+    }
+
+    /// Enable beta features for every message request made by this client.
+    ///
+    /// These are merged with each request's own betas; see
+    /// [`MessageCreateParams::merge_betas`].
+    pub fn with_betas(mut self, betas: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.default_betas = betas.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Limit how many message requests this client has in flight at once.
+    ///
+    /// [`send`](Self::send) and [`stream`](Self::stream) wait for a free slot before issuing
+    /// a request.  A slot is held across retries, and for a stream until the stream is
+    /// dropped.  The limit is shared by every clone of the client made after this call.
+    /// Requests are unlimited by default.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Result<Self> {
+        if max_concurrency == 0 {
+            return Err(Error::validation(
+                "max concurrency must be greater than 0",
+                Some("max_concurrency".to_string()),
+            ));
+        }
+        self.request_permits = Some(Arc::new(Semaphore::new(max_concurrency)));
+        Ok(self)
+    }
+
+    /// Throttle message requests to stay under per-minute request and token limits.
+    ///
+    /// [`send`](Self::send) and [`stream`](Self::stream) sleep until both allowances cover the
+    /// request instead of letting the API reject it.  Each request is charged its `max_tokens`,
+    /// an upper bound on the output it can produce; pass the limit that applies to output
+    /// tokens, or lower `tokens_per_min` to leave room for input.  The allowances are shared by
+    /// every clone of the client made after this call.  Requests are unlimited by default.
+    pub fn with_rate_limits(mut self, requests_per_min: u32, tokens_per_min: u32) -> Result<Self> {
+        if requests_per_min == 0 {
+            return Err(Error::validation(
+                "requests per minute must be greater than 0",
+                Some("requests_per_min".to_string()),
+            ));
+        }
+        if tokens_per_min == 0 {
+            return Err(Error::validation(
+                "tokens per minute must be greater than 0",
+                Some("tokens_per_min".to_string()),
+            ));
+        }
+        let bucket = TokenBucket::new(requests_per_min, tokens_per_min, Instant::now());
+        self.rate_limits = Some(Arc::new(Mutex::new(bucket)));
+        Ok(self)
+    }
+
+    /// Retry once when the API returns an assistant message with no content blocks.
+    ///
+    /// Applies to [`send`](Self::send), [`send_raw`](Self::send_raw), and
+    /// [`stream`](Self::stream).  A stream is only known to be empty once `message_stop`
+    /// arrives, so with this enabled [`stream`](Self::stream) holds back its events until the
+    /// first content block starts.  Disabled by default.
+    pub fn with_retry_empty_responses(mut self, retry: bool) -> Self {
+        self.retry_empty_responses = retry;
+        self
+    }
+
+    /// Cap the bytes read from a response body, defaulting to 64 MiB.
+    ///
+    /// A non-streaming response larger than this, including an error body or batch results,
+    /// fails with [`Error::ResponseTooLarge`] before it is fully buffered.  For streaming
+    /// responses the limit applies to the total bytes of the event stream, which ends with
+    /// that error once the limit is passed.
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// Add a middleware that transforms every message request before it is sent.
+    ///
+    /// Middleware runs in the order it was added, before the request is validated; see
+    /// [`RequestMiddleware`].
+    pub fn with_request_middleware(mut self, middleware: Arc<dyn RequestMiddleware>) -> Self {
+        self.request_middleware.0.push(middleware);
+        self
+    }
+
+    /// Run the registered middleware over `params`.
+    fn apply_request_middleware(&self, params: &mut MessageCreateParams) {
+        for middleware in &self.request_middleware.0 {
+            middleware.transform(params);
+        }
+    }
+
+    /// Add a middleware that transforms every message received.
+    ///
+    /// Middleware runs in the order it was added: on the deserialized message for
+    /// [`send`](Self::send) and [`send_raw`](Self::send_raw), and on the accumulated message
+    /// for [`stream_with_logger`](Self::stream_with_logger) and agent turns.  The raw events
+    /// from [`stream`](Self::stream) and the raw body from [`send_raw`](Self::send_raw) are
+    /// passed through unchanged.
+    pub fn with_response_middleware(mut self, middleware: Arc<dyn ResponseMiddleware>) -> Self {
+        self.response_middleware.0.push(middleware);
+        self
+    }
+
+    /// Serve repeated requests from `cache` instead of the API.
+    ///
+    /// [`send`](Self::send) runs the request middleware, looks up the resulting request's
+    /// [`cache_key_with_betas`](MessageCreateParams::cache_key_with_betas) under the client's
+    /// default betas, and stores every new response under it.  A cached message still passes
+    /// through the response middleware.  Streaming requests and the other send variants bypass
+    /// the cache.
+    pub fn with_response_cache(mut self, cache: Arc<dyn ResponseCache>) -> Self {
+        self.response_cache = Some(SharedResponseCache(cache));
+        self
+    }
+
+    /// Sign every request with `signer` just before it is sent.
+    ///
+    /// The signer sees the serialized body and the complete headers, including the API key,
+    /// and runs again for each retry; see [`RequestSigner`].  A later call replaces the signer.
+    pub fn with_request_signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.request_signer = Some(SharedRequestSigner(signer));
+        self
+    }
+
+    /// Run the request signer, if any, over `body` and `headers`.
+    fn sign_request(&self, body: &[u8], headers: &mut HeaderMap) -> Result<()> {
+        match &self.request_signer {
+            Some(signer) => signer.0.sign(body, headers),
+            None => Ok(()),
+        }
+    }
+
+    /// Run the registered response middleware over `message`.
+    pub(crate) fn apply_response_middleware(&self, message: &mut Message) {
+        self.response_middleware.apply(message);
+    }
+
+    /// Wait for a request slot if concurrency is limited.
+    async fn acquire_request_permit(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        let Some(permits) = &self.request_permits else {
+            return Ok(None);
+        };
+        Arc::clone(permits)
+            .acquire_owned()
+            .await
+            .map(Some)
+            .map_err(|_| Error::unknown("request concurrency limiter was closed"))
+    }
+
+    /// Wait until the rate limits, if any, allow a request for `params`.
+    async fn wait_for_rate_limits(&self, params: &MessageCreateParams) {
+        let Some(rate_limits) = &self.rate_limits else {
+            return;
+        };
+        let wait = rate_limits
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .reserve(params.max_tokens, Instant::now());
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+
+    /// Computes the `anthropic-beta` header for `params`, if any betas apply.
+    fn beta_header(&self, params: &MessageCreateParams) -> Result<Option<HeaderValue>> {
+        let betas = params.merge_betas(&self.default_betas);
+        if betas.is_empty() {
+            return Ok(None);
+        }
+        HeaderValue::from_str(&betas.join(","))
+            .map(Some)
+            .map_err(|e| {
+                Error::validation(format!("Invalid beta name: {e}"), Some("betas".to_string()))
+            })
+    }
+
     /// Build default headers for API requests (static method for initialization).
     fn build_default_headers(api_key: &str) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
@@ -366,7 +872,7 @@ impl Anthropic {
     }
 
     /// Process API response errors and convert to our Error type
-    async fn process_error_response(response: Response) -> Error {
+    async fn process_error_response(&self, response: Response) -> Error {
         let status = response.status();
         let status_code = status.as_u16();
 
@@ -397,14 +903,9 @@ impl Anthropic {
             param: Option<String>,
         }
 
-        let error_body = match response.text().await {
-            Ok(body) => body,
-            Err(e) => {
-                return Error::http_client(
-                    format!("Failed to read error response: {e}"),
-                    Some(Box::new(e)),
-                );
-            }
+        let error_body = match self.read_body(response).await {
+            Ok(body) => String::from_utf8_lossy(&body).into_owned(),
+            Err(err) => return err,
         };
 
         // Try to parse as JSON first
@@ -440,15 +941,11 @@ impl Anthropic {
 
     /// Convert reqwest errors to appropriate Error types
     fn map_request_error(&self, e: reqwest::Error) -> Error {
-        if e.is_timeout() {
-            Error::timeout(
-                format!("Request timed out: {e}"),
-                Some(self.timeout.as_secs_f64()),
-            )
-        } else if e.is_connect() {
-            Error::connection(format!("Connection error: {e}"), Some(Box::new(e)))
-        } else {
-            Error::http_client(format!("Request failed: {e}"), Some(Box::new(e)))
+        match Error::from(e) {
+            Error::Timeout { message, .. } => {
+                Error::timeout(message, Some(self.timeout.as_secs_f64()))
+            }
+            err => err,
         }
     }
 
@@ -459,33 +956,66 @@ impl Anthropic {
         body: &impl serde::Serialize,
         headers: Option<HeaderMap>,
     ) -> Result<T> {
-        let headers = headers.unwrap_or_else(|| self.default_headers());
+        let mut headers = headers.unwrap_or_else(|| self.default_headers());
+        let body = serialize_body(body)?;
+        self.sign_request(&body, &mut headers)?;
 
         let response = self
             .client
             .post(url)
             .headers(headers)
-            .json(body)
+            .body(body)
             .send()
             .await
             .map_err(|e| self.map_request_error(e))?;
 
         if !response.status().is_success() {
-            return Err(Self::process_error_response(response).await);
+            return Err(self.process_error_response(response).await);
         }
 
-        response.json::<T>().await.map_err(|e| {
+        self.read_json(response).await
+    }
+
+    /// Read and decode a response body, failing once it exceeds `max_response_bytes`.
+    async fn read_json<T: serde::de::DeserializeOwned>(&self, response: Response) -> Result<T> {
+        let body = self.read_body(response).await?;
+        serde_json::from_slice(&body).map_err(|e| {
             Error::serialization(format!("Failed to parse response: {e}"), Some(Box::new(e)))
         })
     }
 
+    /// Read a response body, failing once it exceeds `max_response_bytes`.
+    async fn read_body(&self, mut response: Response) -> Result<Vec<u8>> {
+        let limit = self.max_response_bytes;
+        if response
+            .content_length()
+            .is_some_and(|len| len > limit as u64)
+        {
+            return Err(Error::response_too_large(limit));
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| self.map_request_error(e))?
+        {
+            if body.len() + chunk.len() > limit {
+                return Err(Error::response_too_large(limit));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
     /// Execute a GET request with error handling
     async fn execute_get_request<T: serde::de::DeserializeOwned>(
         &self,
         url: &str,
         query_params: Option<&[(String, String)]>,
     ) -> Result<T> {
-        let mut request = self.client.get(url).headers(self.default_headers());
+        let mut headers = self.default_headers();
+        self.sign_request(&[], &mut headers)?;
+        let mut request = self.client.get(url).headers(headers);
 
         if let Some(params) = query_params {
             for (key, value) in params {
@@ -499,21 +1029,128 @@ impl Anthropic {
             .map_err(|e| self.map_request_error(e))?;
 
         if !response.status().is_success() {
-            return Err(Self::process_error_response(response).await);
+            return Err(self.process_error_response(response).await);
         }
 
-        response.json::<T>().await.map_err(|e| {
-            Error::serialization(format!("Failed to parse response: {e}"), Some(Box::new(e)))
-        })
+        self.read_json(response).await
     }
 
     /// Send a message to the API and get a non-streaming response.
     pub async fn send(&self, mut params: MessageCreateParams) -> Result<Message> {
+        self.apply_request_middleware(&mut params);
+        let cached = match &self.response_cache {
+            Some(cache) => Some((cache, params.cache_key_with_betas(&self.default_betas)?)),
+            None => None,
+        };
+        if let Some((cache, key)) = &cached
+            && let Some(mut message) = cache.0.get(key)
+        {
+            self.apply_response_middleware(&mut message);
+            return Ok(message);
+        }
+        let mut message: Message = if self.retry_empty_responses {
+            let message: Message = self.send_as(params.clone()).await?;
+            if message.is_empty() {
+                self.send_as(params).await?
+            } else {
+                message
+            }
+        } else {
+            self.send_as(params).await?
+        };
+        if let Some((cache, key)) = &cached {
+            cache.0.put(key, &message);
+        }
+        self.apply_response_middleware(&mut message);
+        Ok(message)
+    }
+
+    /// Sends `params`, runs any requested tools with `tool_handler`, and sends again until the
+    /// model stops asking for tools.
+    ///
+    /// `tool_handler` receives each tool use in the order the model requested them.  `Ok` text
+    /// becomes a tool result and `Err` text an error result.  The assistant message and the
+    /// tool results are appended to `params.messages` before each follow-up request.
+    ///
+    /// At most `max_tool_rounds` rounds of tools run.  If the model still wants a tool after
+    /// that, the last message is returned as-is, with a stop reason of
+    /// [`StopReason::ToolUse`].
+    pub async fn send_with_tools<F, Fut>(
+        &self,
+        mut params: MessageCreateParams,
+        tool_handler: F,
+        max_tool_rounds: usize,
+    ) -> Result<Message>
+    where
+        F: Fn(ToolUseBlock) -> Fut,
+        Fut: Future<Output = std::result::Result<String, String>>,
+    {
+        let mut rounds = 0;
+        loop {
+            let message = self.send(params.clone()).await?;
+            if message.stop_reason != Some(StopReason::ToolUse) || rounds >= max_tool_rounds {
+                return Ok(message);
+            }
+            rounds += 1;
+            let mut results = vec![];
+            for block in &message.content {
+                if let ContentBlock::ToolUse(tool_use) = block {
+                    let id = tool_use.id.clone();
+                    let result = match tool_handler(tool_use.clone()).await {
+                        Ok(text) => ToolResultBlock::ok(id, text),
+                        Err(text) => ToolResultBlock::error(id, text),
+                    };
+                    results.push(ContentBlock::ToolResult(result));
+                }
+            }
+            params.messages.push(MessageParam::from(message));
+            params
+                .messages
+                .push(MessageParam::new_with_blocks(results, MessageRole::User));
+        }
+    }
+
+    /// Send a message to the API and get both the typed message and the raw response body.
+    ///
+    /// The raw body keeps any fields that [`Message`] does not model yet.
+    pub async fn send_raw(
+        &self,
+        mut params: MessageCreateParams,
+    ) -> Result<(Message, serde_json::Value)> {
+        self.apply_request_middleware(&mut params);
+        let retry = self.retry_empty_responses.then(|| params.clone());
+        let (message, raw) = self.send_raw_once(params).await?;
+        let (mut message, raw) = match retry {
+            Some(params) if message.is_empty() => self.send_raw_once(params).await?,
+            _ => (message, raw),
+        };
+        self.apply_response_middleware(&mut message);
+        Ok((message, raw))
+    }
+
+    async fn send_raw_once(
+        &self,
+        params: MessageCreateParams,
+    ) -> Result<(Message, serde_json::Value)> {
+        let raw: serde_json::Value = self.send_as(params).await?;
+        let message = serde_json::from_value(raw.clone()).map_err(|e| {
+            Error::serialization(format!("Failed to parse response: {e}"), Some(Box::new(e)))
+        })?;
+        Ok((message, raw))
+    }
+
+    /// Send a non-streaming message request, decoding the response body as `T`.
+    ///
+    /// The caller has already run the request middleware over `params`.
+    async fn send_as<T: serde::de::DeserializeOwned>(
+        &self,
+        mut params: MessageCreateParams,
+    ) -> Result<T> {
         let start = Instant::now();
         CLIENT_REQUESTS.click();
 
         // Validate parameters first
-        if let Err(err) = params.validate() {
+        if let Err(err) = params.validate_with_betas(&self.default_betas) {
             CLIENT_REQUEST_ERRORS.click();
             CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
             return Err(err);
@@ -522,18 +1159,30 @@ impl Anthropic {
         // Ensure stream is disabled
         params.stream = false;
 
-        // Check if structured outputs beta header is needed
-        let headers = if params.requires_structured_outputs_beta() {
-            let mut headers = self.default_headers();
-            headers.insert(
-                "anthropic-beta",
-                HeaderValue::from_static(STRUCTURED_OUTPUTS_BETA),
-            );
-            Some(headers)
-        } else {
-            None
+        // Merge client, per-request, and auto-detected betas into one header
+        let beta_header = match self.beta_header(&params) {
+            Ok(beta_header) => beta_header,
+            Err(err) => {
+                CLIENT_REQUEST_ERRORS.click();
+                CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+                return Err(err);
+            }
         };
+        let headers = beta_header.map(|beta_header| {
+            let mut headers = self.default_headers();
+            headers.insert("anthropic-beta", beta_header);
+            headers
+        });
 
+        let permit = match self.acquire_request_permit().await {
+            Ok(permit) => permit,
+            Err(err) => {
+                CLIENT_REQUEST_ERRORS.click();
+                CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+                return Err(err);
+            }
+        };
+        self.wait_for_rate_limits(&params).await;
         let result = self
             .retry_with_backoff(|| async {
                 let url = self.build_url("messages");
@@ -541,6 +1190,7 @@ impl Anthropic {
                     .await
             })
             .await;
+        drop(permit);
 
         CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
         if result.is_err() {
@@ -565,18 +1215,79 @@ impl Anthropic {
         result
     }
 
+    /// Send a message to the API and record the exchange.
+    ///
+    /// On success the request and response are written to `recorder` as one JSON line that
+    /// [`Replay`](crate::Replay) can serve back later.  A failure to write the recording is
+    /// returned as an error.
+    pub async fn send_with_recorder<W: std::io::Write + Send>(
+        &self,
+        params: MessageCreateParams,
+        recorder: &Recorder<W>,
+    ) -> Result<Message> {
+        let message = self.send(params.clone()).await?;
+        recorder.record(&params, &message)?;
+        Ok(message)
+    }
+
     /// Send a message to the API and get a streaming response.
     ///
     /// Returns a stream of MessageStreamEvent objects that can be processed incrementally.
+    /// Dropping the stream before it ends closes the connection and abandons the request.
     pub async fn stream(
         &self,
         params: &MessageCreateParams,
     ) -> Result<impl Stream<Item = Result<MessageStreamEvent>> + use<>> {
-        let start = Instant::now();
-        CLIENT_REQUESTS.click();
+        let params = if self.request_middleware.0.is_empty() {
+            Cow::Borrowed(params)
+        } else {
+            let mut params = params.clone();
+            self.apply_request_middleware(&mut params);
+            Cow::Owned(params)
+        };
+        let mut stream = Box::pin(self.stream_once(&params).await?);
+        let mut prefix = Vec::new();
+        if self.retry_empty_responses && stream_is_empty(&mut stream, &mut prefix).await {
+            // Release the first stream, and any request slot it holds, before retrying.
+            drop(stream);
+            prefix.clear();
+            stream = Box::pin(self.stream_once(&params).await?);
+        }
+        Ok(futures::stream::iter(prefix).chain(stream))
+    }
+
+    /// Like [`stream`](Self::stream), but also returns a [`StreamHandle`] that can abort the
+    /// request from elsewhere.
+    ///
+    /// Dropping the stream still ends the request; the handle is for code that does not own
+    /// the stream, such as a cancel button while another task renders the response.
+    pub async fn stream_with_handle(
+        &self,
+        params: &MessageCreateParams,
+    ) -> Result<(AbortableStream, StreamHandle)> {
+        let stream = self.stream(params).await?;
+        let state = Arc::new(Mutex::new(AbortState {
+            stream: Some(Box::pin(stream)),
+            ..AbortState::default()
+        }));
+        Ok((
+            AbortableStream {
+                state: Arc::clone(&state),
+            },
+            StreamHandle { state },
+        ))
+    }
+
+    /// Open one streaming request without the empty-response retry.
+    async fn stream_once(
+        &self,
+        params: &MessageCreateParams,
+    ) -> Result<impl Stream<Item = Result<MessageStreamEvent>> + use<>> {
+        let start = Instant::now();
+        CLIENT_REQUESTS.click();
 
         // Validate parameters first
-        if let Err(err) = params.validate() {
+        if let Err(err) = params.validate_with_betas(&self.default_betas) {
             CLIENT_REQUEST_ERRORS.click();
             CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
             return Err(err);
@@ -593,9 +1304,33 @@ impl Anthropic {
             return Err(err);
         }
 
-        // Check if structured outputs beta header is needed
-        let needs_beta = params.requires_structured_outputs_beta();
+        // Merge client, per-request, and auto-detected betas into one header
+        let beta_header = match self.beta_header(params) {
+            Ok(beta_header) => beta_header,
+            Err(err) => {
+                CLIENT_REQUEST_ERRORS.click();
+                CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+                return Err(err);
+            }
+        };
+        let body = match serialize_body(params) {
+            Ok(body) => body,
+            Err(err) => {
+                CLIENT_REQUEST_ERRORS.click();
+                CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+                return Err(err);
+            }
+        };
 
+        let permit = match self.acquire_request_permit().await {
+            Ok(permit) => permit,
+            Err(err) => {
+                CLIENT_REQUEST_ERRORS.click();
+                CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+                return Err(err);
+            }
+        };
+        self.wait_for_rate_limits(params).await;
         let response = self
             .retry_with_backoff(|| async {
                 let url = self.build_url("messages");
@@ -605,24 +1340,22 @@ impl Anthropic {
                     header::ACCEPT,
                     HeaderValue::from_static("text/event-stream"),
                 );
-                if needs_beta {
-                    headers.insert(
-                        "anthropic-beta",
-                        HeaderValue::from_static(STRUCTURED_OUTPUTS_BETA),
-                    );
+                if let Some(beta_header) = &beta_header {
+                    headers.insert("anthropic-beta", beta_header.clone());
                 }
+                self.sign_request(&body, &mut headers)?;
 
                 let response = self
                     .client
                     .post(&url)
                     .headers(headers)
-                    .json(&params)
+                    .body(body.clone())
                     .send()
                     .await
                     .map_err(|e| self.map_request_error(e))?;
 
                 if !response.status().is_success() {
-                    return Err(Self::process_error_response(response).await);
+                    return Err(self.process_error_response(response).await);
                 }
 
                 Ok(response)
@@ -641,8 +1374,13 @@ impl Anthropic {
         // Get the byte stream from the response
         let stream = response.bytes_stream();
 
-        // Create an SSE processor
-        Ok(process_sse(stream))
+        // Create an SSE processor, holding the request slot until the stream is dropped
+        Ok(
+            process_sse_limited(stream, self.max_response_bytes).map(move |event| {
+                let _held = &permit;
+                event
+            }),
+        )
     }
 
     /// Send a message to the API with logging and get a streaming response.
@@ -661,7 +1399,12 @@ impl Anthropic {
     ) -> Result<LoggingStream<'a>> {
         let raw_stream = self.stream(params).await?;
         let (accumulating_stream, receiver) = AccumulatingStream::new(raw_stream);
-        Ok(LoggingStream::new(accumulating_stream, receiver, logger))
+        Ok(LoggingStream::new(
+            accumulating_stream,
+            receiver,
+            logger,
+            self.response_middleware.clone(),
+        ))
     }
 
     /// Count tokens for a message.
@@ -725,6 +1468,25 @@ impl Anthropic {
         result
     }
 
+    /// List every available model, following pagination cursors as the stream is consumed.
+    ///
+    /// `params` sets the page size and betas; its `after_id` is used as the starting cursor.
+    pub fn list_all_models(
+        &self,
+        params: Option<ModelListParams>,
+    ) -> impl Stream<Item = Result<ModelInfo>> + '_ {
+        let params = params.unwrap_or_default();
+        let start = params.after_id.clone();
+        paginate(move |cursor| {
+            let params = ModelListParams {
+                after_id: cursor.or_else(|| start.clone()),
+                before_id: None,
+                ..params.clone()
+            };
+            async move { self.list_models(Some(params)).await.map(Page::from) }
+        })
+    }
+
     /// Retrieve information about a specific model.
     ///
     /// Returns detailed information about the specified model, including its
@@ -745,6 +1507,217 @@ impl Anthropic {
         }
         result
     }
+
+    /// Retrieve the status of a message batch.
+    pub async fn get_batch(&self, batch_id: &str) -> Result<MessageBatch> {
+        let start = Instant::now();
+        CLIENT_REQUESTS.click();
+        let result = self
+            .retry_with_backoff(|| async {
+                let url = self.build_url(&format!("messages/batches/{batch_id}"));
+                self.execute_get_request(&url, None).await
+            })
+            .await;
+
+        CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+        if result.is_err() {
+            CLIENT_REQUEST_ERRORS.click();
+        }
+        result
+    }
+
+    /// List message batches, most recent first.
+    ///
+    /// Use the parameters to control pagination.
+    pub async fn list_batches(&self, params: Option<BatchListParams>) -> Result<BatchListResponse> {
+        let start = Instant::now();
+        CLIENT_REQUESTS.click();
+        let result = self
+            .retry_with_backoff(|| async {
+                let url = self.build_url("messages/batches");
+
+                let query_params = params.as_ref().map(|p| {
+                    let mut params = Vec::new();
+                    if let Some(ref after_id) = p.after_id {
+                        params.push(("after_id".to_string(), after_id.clone()));
+                    }
+                    if let Some(ref before_id) = p.before_id {
+                        params.push(("before_id".to_string(), before_id.clone()));
+                    }
+                    if let Some(limit) = p.limit {
+                        params.push(("limit".to_string(), limit.to_string()));
+                    }
+                    params
+                });
+
+                self.execute_get_request(&url, query_params.as_deref())
+                    .await
+            })
+            .await;
+
+        CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+        if result.is_err() {
+            CLIENT_REQUEST_ERRORS.click();
+        }
+        result
+    }
+
+    /// List every message batch, following pagination cursors as the stream is consumed.
+    ///
+    /// `params` sets the page size; its `after_id` is used as the starting cursor.
+    pub fn list_all_batches(
+        &self,
+        params: Option<BatchListParams>,
+    ) -> impl Stream<Item = Result<MessageBatch>> + '_ {
+        let params = params.unwrap_or_default();
+        let start = params.after_id.clone();
+        paginate(move |cursor| {
+            let params = BatchListParams {
+                after_id: cursor.or_else(|| start.clone()),
+                before_id: None,
+                ..params.clone()
+            };
+            async move { self.list_batches(Some(params)).await.map(Page::from) }
+        })
+    }
+
+    /// Download the results of a message batch that has ended.
+    pub async fn get_batch_results(&self, batch_id: &str) -> Result<Vec<BatchResultEntry>> {
+        let start = Instant::now();
+        CLIENT_REQUESTS.click();
+        let result = self
+            .retry_with_backoff(|| async {
+                let url = self.build_url(&format!("messages/batches/{batch_id}/results"));
+                let mut headers = self.default_headers();
+                self.sign_request(&[], &mut headers)?;
+                let response = self
+                    .client
+                    .get(&url)
+                    .headers(headers)
+                    .send()
+                    .await
+                    .map_err(|e| self.map_request_error(e))?;
+                if !response.status().is_success() {
+                    return Err(self.process_error_response(response).await);
+                }
+                let body = self.read_body(response).await?;
+                let body = String::from_utf8(body).map_err(|e| {
+                    Error::serialization(
+                        format!("Batch results are not valid UTF-8: {e}"),
+                        Some(Box::new(e)),
+                    )
+                })?;
+                parse_batch_results_jsonl(&body)
+            })
+            .await;
+
+        CLIENT_REQUEST_DURATION.add(start.elapsed().as_secs_f64());
+        if result.is_err() {
+            CLIENT_REQUEST_ERRORS.click();
+        }
+        result
+    }
+
+    /// Follow a message batch until it ends, then yield its results.
+    ///
+    /// The batch status is polled every `poll_interval`, yielding
+    /// [`BatchProgress::Processing`] with the request counts after each poll, including the
+    /// one that sees the batch end.  The results are then downloaded and yielded one
+    /// [`BatchProgress::Result`] at a time.  An error is yielded and ends the stream.
+    pub fn stream_batch_results_live(
+        &self,
+        batch_id: &str,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<BatchProgress>> + '_ {
+        let batch_id = batch_id.to_string();
+        futures::stream::unfold(LiveBatch::Poll { first: true }, move |state| {
+            let batch_id = batch_id.clone();
+            async move {
+                match state {
+                    LiveBatch::Poll { first } => {
+                        if !first {
+                            sleep(poll_interval).await;
+                        }
+                        match self.get_batch(&batch_id).await {
+                            Ok(batch) => {
+                                let next =
+                                    if batch.processing_status == BatchProcessingStatus::Ended {
+                                        LiveBatch::Fetch
+                                    } else {
+                                        LiveBatch::Poll { first: false }
+                                    };
+                                Some((Ok(BatchProgress::Processing(batch.request_counts)), next))
+                            }
+                            Err(err) => Some((Err(err), LiveBatch::Done)),
+                        }
+                    }
+                    LiveBatch::Fetch => match self.get_batch_results(&batch_id).await {
+                        Ok(results) => {
+                            let mut results = results.into_iter();
+                            let entry = results.next()?;
+                            Some((
+                                Ok(BatchProgress::Result(entry)),
+                                LiveBatch::Results(results),
+                            ))
+                        }
+                        Err(err) => Some((Err(err), LiveBatch::Done)),
+                    },
+                    LiveBatch::Results(mut results) => {
+                        let entry = results.next()?;
+                        Some((
+                            Ok(BatchProgress::Result(entry)),
+                            LiveBatch::Results(results),
+                        ))
+                    }
+                    LiveBatch::Done => None,
+                }
+            }
+        })
+    }
+}
+
+/// Where [`Anthropic::stream_batch_results_live`] is in following a batch.
+enum LiveBatch {
+    /// Poll the batch status, first sleeping unless this is the first poll.
+    Poll { first: bool },
+    /// The batch has ended; download its results.
+    Fetch,
+    /// Yield the remaining results.
+    Results(std::vec::IntoIter<BatchResultEntry>),
+    /// An error ended the stream.
+    Done,
+}
+
+/// Reads `stream` into `prefix` until the response is known to have content.
+///
+/// Returns true if `message_stop` arrived before any content block started.  Errors and an early
+/// end of stream are left in `prefix` for the caller to see.
+async fn stream_is_empty<S>(stream: &mut S, prefix: &mut Vec<Result<MessageStreamEvent>>) -> bool
+where
+    S: Stream<Item = Result<MessageStreamEvent>> + Unpin,
+{
+    while let Some(event) = stream.next().await {
+        let verdict = match &event {
+            Ok(MessageStreamEvent::ContentBlockStart(_)) | Err(_) => Some(false),
+            Ok(MessageStreamEvent::MessageStop(_)) => Some(true),
+            Ok(_) => None,
+        };
+        prefix.push(event);
+        if let Some(empty) = verdict {
+            return empty;
+        }
+    }
+    false
+}
+
+/// Serialize a request body to the JSON bytes that are sent and signed.
+fn serialize_body(body: &impl serde::Serialize) -> Result<Vec<u8>> {
+    serde_json::to_vec(body).map_err(|e| {
+        Error::serialization(
+            format!("Failed to serialize request: {e}"),
+            Some(Box::new(e)),
+        )
+    })
 }
 
 #[cfg(test)]
@@ -753,18 +1726,13 @@ mod tests {
     use std::sync::Arc;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
+    use crate::test_support::{MockServer, Reply, sse_events};
+
     #[tokio::test]
     async fn retry_logic_with_backoff() {
-        let client = Anthropic {
-            api_key: "test".to_string(),
-            client: ReqwestClient::new(),
-            base_url: "http://localhost".to_string(),
-            timeout: Duration::from_secs(1),
-            max_retries: 2,
-            throughput_ops_sec: 1.0 / 60.0,
-            reserve_capacity: 1.0 / 60.0,
-            cached_headers: Arc::new(HeaderMap::new()),
-        };
+        let client = Anthropic::new(Some("test".to_string()))
+            .unwrap()
+            .with_max_retries(2);
 
         let attempt_counter = Arc::new(AtomicUsize::new(0));
         let counter_clone = attempt_counter.clone();
@@ -788,17 +1756,839 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn retry_logic_with_non_retryable_error() {
-        let client = Anthropic {
-            api_key: "test".to_string(),
-            client: ReqwestClient::new(),
-            base_url: "http://localhost".to_string(),
-            timeout: Duration::from_secs(1),
-            max_retries: 2,
-            throughput_ops_sec: 1.0 / 60.0,
-            reserve_capacity: 1.0 / 60.0,
-            cached_headers: Arc::new(HeaderMap::new()),
+    async fn reqwest_connect_error_maps_to_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let err = ReqwestClient::new()
+            .get(format!("http://{addr}/"))
+            .send()
+            .await
+            .unwrap_err();
+        let err = Error::from(err);
+        assert!(err.is_connection(), "unexpected error: {err:?}");
+        assert!(err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn reqwest_timeout_error_maps_to_timeout() {
+        let server = MockServer::start([Reply::silent()]);
+
+        let client = ReqwestClient::builder()
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let err = client
+            .get(format!("{}/", server.url()))
+            .send()
+            .await
+            .unwrap_err();
+        let err = Error::from(err);
+        assert!(err.is_timeout(), "unexpected error: {err:?}");
+    }
+
+    #[tokio::test]
+    async fn reqwest_other_errors_map_to_http_client() {
+        let err = ReqwestClient::new()
+            .get("http://[::1")
+            .send()
+            .await
+            .unwrap_err();
+        let err = Error::from(err);
+        assert!(
+            matches!(err, Error::HttpClient { .. }),
+            "unexpected error: {err:?}"
+        );
+        assert!(!err.is_retryable());
+    }
+
+    fn message_body(id: &str, text: &str) -> String {
+        serde_json::json!({
+            "id": id,
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [{"type": "text", "text": text}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 3, "output_tokens": 1}
+        })
+        .to_string()
+    }
+
+    fn tool_use_body(id: &str, tool_use_id: &str, input: serde_json::Value) -> String {
+        serde_json::json!({
+            "id": id,
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [{"type": "tool_use", "id": tool_use_id, "name": "lookup", "input": input}],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 3, "output_tokens": 1}
+        })
+        .to_string()
+    }
+
+    fn hello_params() -> MessageCreateParams {
+        MessageCreateParams::new(
+            16,
+            vec![crate::MessageParam::user("hi")],
+            crate::Model::Known(crate::KnownModel::ClaudeHaiku45),
+        )
+    }
+
+    #[tokio::test]
+    async fn list_all_models_follows_cursor_across_pages() {
+        let model = |id: &str| {
+            serde_json::json!({
+                "id": id,
+                "created_at": "2025-02-19T00:00:00Z",
+                "display_name": id,
+                "type": "model"
+            })
         };
+        let pages = vec![
+            serde_json::json!({
+                "data": [model("model-a"), model("model-b")],
+                "has_more": true,
+                "first_id": "model-a",
+                "last_id": "model-b"
+            })
+            .to_string(),
+            serde_json::json!({
+                "data": [model("model-c")],
+                "has_more": false,
+                "first_id": "model-c",
+                "last_id": "model-c"
+            })
+            .to_string(),
+        ];
+        let server = MockServer::start(pages.into_iter().map(Reply::json));
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+
+        let ids: Vec<String> = client
+            .list_all_models(Some(ModelListParams::new().with_limit(2)))
+            .map(|model| model.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec!["model-a", "model-b", "model-c"]);
+
+        let requests = server.requests().await;
+        assert_eq!(requests.len(), 2);
+        let first = requests[0].request_line();
+        assert!(!first.contains("after_id"), "{first}");
+        let second = requests[1].request_line();
+        assert!(second.contains("after_id=model-b"), "{second}");
+    }
+
+    #[tokio::test]
+    async fn paginate_stops_after_error() {
+        let mut calls = 0;
+        let items: Vec<Result<u32>> = paginate(|cursor: Option<String>| {
+            calls += 1;
+            let call = calls;
+            async move {
+                match call {
+                    1 => {
+                        assert_eq!(cursor, None);
+                        Ok(Page {
+                            data: vec![1, 2],
+                            has_more: true,
+                            last_id: Some("2".to_string()),
+                        })
+                    }
+                    _ => Err(Error::unknown("page failed")),
+                }
+            }
+        })
+        .collect()
+        .await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(*items[0].as_ref().unwrap(), 1);
+        assert_eq!(*items[1].as_ref().unwrap(), 2);
+        assert!(items[2].is_err());
+        assert_eq!(calls, 2);
+    }
+
+    #[tokio::test]
+    async fn send_with_recorder_round_trips_through_replay() {
+        let server = MockServer::start([Reply::json(message_body("msg_1", "hi"))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let recorder = Recorder::new(Vec::new());
+        let sent = client
+            .send_with_recorder(hello_params(), &recorder)
+            .await
+            .unwrap();
+        server.requests().await;
+
+        let jsonl = String::from_utf8(recorder.into_inner()).unwrap();
+        let replay = crate::Replay::from_jsonl(&jsonl).unwrap();
+        assert_eq!(replay.send(&hello_params()).unwrap(), sent);
+        assert_eq!(replay.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn send_raw_keeps_unmodeled_fields() {
+        let mut body: serde_json::Value =
+            serde_json::from_str(&message_body("msg_raw", "hello")).unwrap();
+        body["container"] = serde_json::json!({"id": "container_1"});
+        let server = MockServer::start([Reply::json(body.to_string())]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url());
+
+        let (message, raw) = client.send_raw(hello_params()).await.unwrap();
+        server.requests().await;
+        assert_eq!(message.id, "msg_raw");
+        assert_eq!(raw["container"]["id"], "container_1");
+        assert_eq!(raw["id"], "msg_raw");
+    }
+
+    fn empty_message_body(id: &str) -> String {
+        let mut body: serde_json::Value = serde_json::from_str(&message_body(id, "")).unwrap();
+        body["content"] = serde_json::json!([]);
+        body.to_string()
+    }
+
+    fn sse_body(id: &str, text: Option<&str>) -> String {
+        use serde_json::json;
+
+        let mut events = vec![json!({"type": "message_start", "message": {
+            "id": id, "type": "message", "role": "assistant",
+            "model": "claude-haiku-4-5", "content": [], "stop_reason": null,
+            "usage": {"input_tokens": 3, "output_tokens": 1}
+        }})];
+        if let Some(text) = text {
+            events.push(json!({"type": "content_block_start", "index": 0,
+                "content_block": {"type": "text", "text": ""}}));
+            events.push(json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "text_delta", "text": text}}));
+            events.push(json!({"type": "content_block_stop", "index": 0}));
+        }
+        events.push(json!({"type": "message_delta",
+            "delta": {"stop_reason": "end_turn", "stop_sequence": null},
+            "usage": {"output_tokens": 1}}));
+        events.push(json!({"type": "message_stop"}));
+        sse_events(&events)
+    }
+
+    /// A streaming reply that sends `message_start` and then stalls until the client hangs up.
+    fn stalled_stream() -> Reply {
+        let first_event = sse_body("msg_stalled", None)
+            .split_inclusive("\n\n")
+            .next()
+            .unwrap()
+            .to_string();
+        Reply::sse(first_event).stalled()
+    }
+
+    #[tokio::test]
+    async fn dropping_stream_closes_connection() {
+        let server = MockServer::start([stalled_stream()]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let params = hello_params().with_stream(true);
+
+        let mut stream = Box::pin(client.stream(&params).await.unwrap());
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(MessageStreamEvent::MessageStart(_)))
+        ));
+        assert_eq!(server.closed_connections(), 0);
+        drop(stream);
+
+        tokio::time::timeout(Duration::from_secs(5), server.requests())
+            .await
+            .expect("connection was not closed");
+    }
+
+    #[tokio::test]
+    async fn stream_handle_abort_closes_connection() {
+        let server = MockServer::start([stalled_stream()]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let params = hello_params().with_stream(true);
+
+        let (mut stream, handle) = client.stream_with_handle(&params).await.unwrap();
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(MessageStreamEvent::MessageStart(_)))
+        ));
+        let reader = tokio::spawn(async move { stream.next().await.is_none() });
+        tokio::task::yield_now().await;
+        assert!(!handle.is_aborted());
+        handle.clone().abort();
+        assert!(handle.is_aborted());
+
+        assert!(reader.await.unwrap(), "aborted stream should end");
+        tokio::time::timeout(Duration::from_secs(5), server.requests())
+            .await
+            .expect("connection was not closed");
+    }
+
+    #[tokio::test]
+    async fn send_rejects_response_over_max_bytes() {
+        let body = message_body("msg_big", &"x".repeat(4096));
+        let server = MockServer::start([Reply::json(body.clone()), Reply::json(body)]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_max_response_bytes(1024);
+
+        let err = client.send(hello_params()).await.unwrap_err();
+        assert!(err.is_response_too_large(), "{err}");
+        assert!(!err.is_retryable());
+
+        let client = client.with_max_response_bytes(1024 * 1024);
+        assert_eq!(client.send(hello_params()).await.unwrap().id, "msg_big");
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn error_and_batch_result_bodies_respect_max_bytes() {
+        let big = "x".repeat(4096);
+        let server = MockServer::start([
+            Reply::json(big.clone()).with_status("400 Bad Request"),
+            Reply::json(big),
+        ]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_max_response_bytes(1024);
+
+        let err = client.send(hello_params()).await.unwrap_err();
+        assert!(err.is_response_too_large(), "{err}");
+        let err = client.get_batch_results("msgbatch_big").await.unwrap_err();
+        assert!(err.is_response_too_large(), "{err}");
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stream_rejects_events_over_max_bytes() {
+        let server = MockServer::start([Reply::sse(sse_body("msg_big", Some(&"x".repeat(4096))))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_max_response_bytes(2048);
+        let params = hello_params().with_stream(true);
+
+        let events: Vec<_> = client.stream(&params).await.unwrap().collect().await;
+        assert!(
+            matches!(events.last(), Some(Err(err)) if err.is_response_too_large()),
+            "{events:?}"
+        );
+        assert!(
+            events
+                .iter()
+                .all(|event| !matches!(event, Ok(MessageStreamEvent::MessageStop(_))))
+        );
+        server.requests().await;
+    }
+
+    #[tokio::test]
+    async fn send_retries_empty_response_once() {
+        let server = MockServer::start([
+            Reply::json(empty_message_body("msg_empty")),
+            Reply::json(message_body("msg_full", "hello")),
+        ]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_retry_empty_responses(true);
+
+        let message = client.send(hello_params()).await.unwrap();
+        assert_eq!(message.id, "msg_full");
+        assert!(!message.is_empty());
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn send_keeps_empty_response_by_default() {
+        let server = MockServer::start([Reply::json(empty_message_body("msg_empty"))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url());
+
+        let message = client.send(hello_params()).await.unwrap();
+        assert_eq!(message.id, "msg_empty");
+        assert!(message.is_empty());
+        assert_eq!(server.requests().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stream_retries_empty_response_once() {
+        let server = MockServer::start([
+            Reply::sse(sse_body("msg_empty", None)),
+            Reply::sse(sse_body("msg_full", Some("hello"))),
+        ]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_concurrency(1)
+            .unwrap()
+            .with_retry_empty_responses(true);
+        let mut params = hello_params();
+        params.stream = true;
+
+        let events: Vec<MessageStreamEvent> = client
+            .stream(&params)
+            .await
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        assert_eq!(server.requests().await.len(), 2);
+        assert!(matches!(
+            &events[0],
+            MessageStreamEvent::MessageStart(start) if start.message.id == "msg_full"
+        ));
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, MessageStreamEvent::ContentBlockStart(_)))
+        );
+        assert!(matches!(
+            events.last(),
+            Some(MessageStreamEvent::MessageStop(_))
+        ));
+    }
+
+    struct AppendStopSequence(&'static str);
+
+    impl RequestMiddleware for AppendStopSequence {
+        fn transform(&self, params: &mut MessageCreateParams) {
+            params
+                .stop_sequences
+                .get_or_insert_with(Vec::new)
+                .push(self.0.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn request_middleware_runs_in_registration_order() {
+        let server = MockServer::start([Reply::json(message_body("msg_mw", "hello"))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_request_middleware(Arc::new(AppendStopSequence("###")))
+            .with_request_middleware(Arc::new(|params: &mut MessageCreateParams| {
+                let sequences = params.stop_sequences.take().unwrap_or_default();
+                params.stop_sequences = Some(sequences.into_iter().chain(["@@@".into()]).collect());
+            }));
+
+        let message = client.send(hello_params()).await.unwrap();
+        assert_eq!(message.id, "msg_mw");
+        let request = server.requests().await.remove(0);
+        assert_eq!(
+            request.json()["stop_sequences"],
+            serde_json::json!(["###", "@@@"])
+        );
+    }
+
+    fn uppercase_text(message: &mut Message) {
+        for block in &mut message.content {
+            if let crate::ContentBlock::Text(text) = block {
+                text.text = text.text.to_uppercase();
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct CapturingLogger(std::sync::Mutex<Option<Message>>);
+
+    impl ClientLogger for CapturingLogger {
+        fn log_response(&self, _message: &Message) {}
+
+        fn log_stream_event(&self, _event: &MessageStreamEvent) {}
+
+        fn log_stream_message(&self, message: &Message) {
+            *self.0.lock().unwrap() = Some(message.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn response_middleware_transforms_sent_message() {
+        let server = MockServer::start([Reply::json(message_body("msg_mw", "hello"))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_response_middleware(Arc::new(uppercase_text))
+            .with_response_middleware(Arc::new(|message: &mut Message| {
+                message.id.push_str("_seen");
+            }));
+
+        let message = client.send(hello_params()).await.unwrap();
+        assert_eq!(message.id, "msg_mw_seen");
+        assert!(matches!(
+            &message.content[0],
+            crate::ContentBlock::Text(text) if text.text == "HELLO"
+        ));
+    }
+
+    #[tokio::test]
+    async fn response_middleware_transforms_streamed_message() {
+        let server = MockServer::start([Reply::sse(sse_body("msg_mw", Some("hello")))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_response_middleware(Arc::new(uppercase_text));
+        let mut params = hello_params();
+        params.stream = true;
+
+        let logger = CapturingLogger::default();
+        let stream = client.stream_with_logger(&params, &logger).await.unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert!(events.iter().all(Result::is_ok));
+        let message = logger.0.lock().unwrap().take().unwrap();
+        assert!(matches!(
+            &message.content[0],
+            crate::ContentBlock::Text(text) if text.text == "HELLO"
+        ));
+    }
+
+    #[tokio::test]
+    async fn max_concurrency_caps_in_flight_requests() {
+        const LIMIT: usize = 2;
+        const TASKS: usize = 8;
+
+        let server = MockServer::start((0..TASKS).map(|_| {
+            Reply::json(message_body("msg_1", "hello")).with_delay(Duration::from_millis(50))
+        }));
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_concurrency(LIMIT)
+            .unwrap();
+        let tasks: Vec<_> = (0..TASKS)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.send(hello_params()).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        let max = server.max_in_flight();
+        server.requests().await;
+        assert!((1..=LIMIT).contains(&max), "{max} requests were in flight");
+    }
+
+    fn batch_body(status: &str, processing: u64, succeeded: u64) -> String {
+        serde_json::json!({
+            "id": "msgbatch_live",
+            "type": "message_batch",
+            "processing_status": status,
+            "request_counts": {
+                "processing": processing,
+                "succeeded": succeeded,
+                "errored": 0,
+                "canceled": 0,
+                "expired": 0
+            },
+            "created_at": "2025-06-01T12:00:00Z",
+            "expires_at": "2025-06-02T12:00:00Z",
+            "ended_at": null,
+            "results_url": null
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn list_all_batches_follows_cursor_across_pages() {
+        let batch = |id: &str| {
+            let mut batch: serde_json::Value =
+                serde_json::from_str(&batch_body("ended", 0, 1)).unwrap();
+            batch["id"] = serde_json::json!(id);
+            batch
+        };
+        let pages = vec![
+            serde_json::json!({
+                "data": [batch("msgbatch_c"), batch("msgbatch_b")],
+                "has_more": true,
+                "first_id": "msgbatch_c",
+                "last_id": "msgbatch_b"
+            })
+            .to_string(),
+            serde_json::json!({
+                "data": [batch("msgbatch_a")],
+                "has_more": false,
+                "first_id": "msgbatch_a",
+                "last_id": "msgbatch_a"
+            })
+            .to_string(),
+        ];
+        let server = MockServer::start(pages.into_iter().map(Reply::json));
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+
+        let ids: Vec<String> = client
+            .list_all_batches(Some(BatchListParams::new().with_limit(2)))
+            .map(|batch| batch.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec!["msgbatch_c", "msgbatch_b", "msgbatch_a"]);
+
+        let requests = server.requests().await;
+        assert_eq!(requests.len(), 2);
+        let first = requests[0].request_line();
+        assert!(first.starts_with("GET /v1/messages/batches?"), "{first}");
+        assert!(first.contains("limit=2"), "{first}");
+        assert!(!first.contains("after_id"), "{first}");
+        let second = requests[1].request_line();
+        assert!(second.contains("after_id=msgbatch_b"), "{second}");
+    }
+
+    #[tokio::test]
+    async fn stream_batch_results_live_reports_progress_then_results() {
+        let results = [
+            serde_json::json!({"custom_id": "a", "result": {"type": "succeeded",
+                "message": serde_json::from_str::<serde_json::Value>(&message_body("msg_a", "one")).unwrap()}}),
+            serde_json::json!({"custom_id": "b", "result": {"type": "expired"}}),
+        ]
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+        let server = MockServer::start([
+            Reply::json(batch_body("in_progress", 2, 0)),
+            Reply::json(batch_body("ended", 0, 1)),
+            Reply::json(results),
+        ]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url());
+
+        let updates: Vec<BatchProgress> = client
+            .stream_batch_results_live("msgbatch_live", Duration::from_millis(1))
+            .map(|update| update.unwrap())
+            .collect()
+            .await;
+        assert_eq!(updates.len(), 4);
+        assert!(
+            matches!(&updates[0], BatchProgress::Processing(counts) if counts.completed() == 0)
+        );
+        assert!(
+            matches!(&updates[1], BatchProgress::Processing(counts) if counts.completed() == 1)
+        );
+        assert!(
+            matches!(&updates[2], BatchProgress::Result(entry) if entry.message().unwrap().id == "msg_a")
+        );
+        assert!(matches!(&updates[3], BatchProgress::Result(entry) if entry.custom_id == "b"));
+        assert_eq!(
+            server
+                .requests()
+                .await
+                .iter()
+                .map(|request| request.request_line())
+                .collect::<Vec<_>>(),
+            vec![
+                "GET /v1/messages/batches/msgbatch_live HTTP/1.1",
+                "GET /v1/messages/batches/msgbatch_live HTTP/1.1",
+                "GET /v1/messages/batches/msgbatch_live/results HTTP/1.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn max_concurrency_must_be_positive() {
+        let err = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_max_concurrency(0)
+            .unwrap_err();
+        assert!(err.is_validation());
+    }
+
+    #[test]
+    fn rate_limits_must_be_positive() {
+        let client = Anthropic::new(Some("test-key".to_string())).unwrap();
+        assert!(
+            client
+                .clone()
+                .with_rate_limits(0, 1_000)
+                .unwrap_err()
+                .is_validation()
+        );
+        assert!(
+            client
+                .clone()
+                .with_rate_limits(50, 0)
+                .unwrap_err()
+                .is_validation()
+        );
+        assert!(client.with_rate_limits(50, 1_000).is_ok());
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn send_decodes_gzip_response() {
+        use std::io::Write;
+
+        use crate::{ContentBlock, StopReason};
+
+        let body = message_body("msg_gzip", "compressed");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let server =
+            MockServer::start([Reply::json(compressed).with_header("content-encoding", "gzip")]);
+
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let message = client.send(hello_params()).await.unwrap();
+        assert_eq!(message.id, "msg_gzip");
+        assert_eq!(message.stop_reason, Some(StopReason::EndTurn));
+        assert!(matches!(
+            &message.content[0],
+            ContentBlock::Text(text) if text.text == "compressed"
+        ));
+
+        let request = server.requests().await.remove(0);
+        let accept_encoding = request.header("accept-encoding").unwrap();
+        assert!(accept_encoding.contains("gzip"), "{accept_encoding}");
+    }
+
+    #[tokio::test]
+    async fn custom_headers_are_sent_with_requests() {
+        let server = MockServer::start([Reply::json(message_body("msg_1", "hi"))]);
+
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_user_agent("gateway-test/1.0")
+            .unwrap()
+            .with_header("x-route", "blue")
+            .unwrap()
+            .with_header("x-api-key", "spoofed")
+            .unwrap();
+        client.send(hello_params()).await.unwrap();
+
+        let request = server.requests().await.remove(0);
+        assert_eq!(request.header("user-agent"), Some("gateway-test/1.0"));
+        assert_eq!(request.header("x-route"), Some("blue"));
+        assert_eq!(request.header("x-api-key"), Some("test-key"));
+        assert_eq!(
+            request.header("anthropic-version"),
+            Some(ANTHROPIC_API_VERSION)
+        );
+    }
+
+    #[tokio::test]
+    async fn api_version_overrides_header() {
+        let server = MockServer::start([Reply::json(message_body("msg_1", "hi"))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_api_version("2099-01-01")
+            .unwrap();
+        client.send(hello_params()).await.unwrap();
+
+        let request = server.requests().await.remove(0);
+        assert_eq!(request.header("anthropic-version"), Some("2099-01-01"));
+    }
+
+    #[test]
+    fn api_version_must_not_be_empty() {
+        let client = Anthropic::new(Some("test-key".to_string())).unwrap();
+        let err = client.with_api_version("  ").unwrap_err();
+        assert!(err.is_validation());
+    }
+
+    #[tokio::test]
+    async fn betas_are_merged_into_one_header() {
+        use crate::types::OutputFormat;
+
+        let server = MockServer::start([Reply::json(message_body("msg_1", "hi"))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_betas(["client-beta", "shared-beta"]);
+        let params = hello_params()
+            .with_betas(["shared-beta", "request-beta"])
+            .with_output_format(OutputFormat::json_schema(
+                serde_json::json!({"type": "object"}),
+            ));
+        client.send(params).await.unwrap();
+
+        let request = server.requests().await.remove(0);
+        assert_eq!(
+            request.headers("anthropic-beta"),
+            vec!["client-beta,shared-beta,request-beta,structured-outputs-2025-11-13"]
+        );
+    }
+
+    #[tokio::test]
+    async fn request_signer_adds_header_over_body() {
+        fn signature(body: &[u8]) -> String {
+            let hash = body.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+            });
+            format!("{hash:016x}")
+        }
+
+        let signed = Arc::new(Mutex::new(Vec::new()));
+        let signer = {
+            let signed = Arc::clone(&signed);
+            move |body: &[u8], headers: &mut HeaderMap| -> Result<()> {
+                assert!(headers.contains_key("x-api-key"));
+                signed.lock().unwrap().extend_from_slice(body);
+                headers.insert(
+                    "x-signature",
+                    HeaderValue::from_str(&signature(body)).unwrap(),
+                );
+                Ok(())
+            }
+        };
+        let server = MockServer::start([Reply::json(message_body("msg_1", "hi"))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_request_signer(Arc::new(signer));
+        client.send(hello_params()).await.unwrap();
+
+        let request = server.requests().await.remove(0);
+        let body = signed.lock().unwrap().clone();
+        let sent: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(sent["model"], hello_params().model.to_string());
+        assert_eq!(
+            request.header("x-signature"),
+            Some(signature(&body).as_str())
+        );
+    }
+
+    #[test]
+    fn with_header_rejects_invalid_names() {
+        let err = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_header("bad header", "value")
+            .unwrap_err();
+        assert!(err.is_validation(), "unexpected error: {err:?}");
+    }
+
+    #[tokio::test]
+    async fn retry_logic_with_non_retryable_error() {
+        let client = Anthropic::new(Some("test".to_string()))
+            .unwrap()
+            .with_max_retries(2);
 
         let attempt_counter = Arc::new(AtomicUsize::new(0));
         let counter_clone = attempt_counter.clone();
@@ -821,16 +2611,9 @@ mod tests {
 
     #[tokio::test]
     async fn retry_logic_max_retries_exceeded() {
-        let client = Anthropic {
-            api_key: "test".to_string(),
-            client: ReqwestClient::new(),
-            base_url: "http://localhost".to_string(),
-            timeout: Duration::from_secs(1),
-            max_retries: 2,
-            throughput_ops_sec: 1.0 / 60.0,
-            reserve_capacity: 1.0 / 60.0,
-            cached_headers: Arc::new(HeaderMap::new()),
-        };
+        let client = Anthropic::new(Some("test".to_string()))
+            .unwrap()
+            .with_max_retries(2);
 
         let attempt_counter = Arc::new(AtomicUsize::new(0));
         let counter_clone = attempt_counter.clone();
@@ -854,16 +2637,9 @@ mod tests {
     #[tokio::test]
     async fn error_529_is_retryable() {
         // Test that 529 errors are properly mapped to rate_limit and are retryable
-        let client = Anthropic {
-            api_key: "test".to_string(),
-            client: ReqwestClient::new(),
-            base_url: "http://localhost".to_string(),
-            timeout: Duration::from_secs(1),
-            max_retries: 2,
-            throughput_ops_sec: 1.0 / 60.0,
-            reserve_capacity: 1.0 / 60.0,
-            cached_headers: Arc::new(HeaderMap::new()),
-        };
+        let client = Anthropic::new(Some("test".to_string()))
+            .unwrap()
+            .with_max_retries(2);
 
         let attempt_counter = Arc::new(AtomicUsize::new(0));
         let counter_clone = attempt_counter.clone();
@@ -1066,6 +2842,27 @@ mod tests {
         assert!(headers1.contains_key("content-type"));
     }
 
+    #[test]
+    fn pool_settings_survive_timeout_changes() {
+        let client = Anthropic::new(Some("test_key".to_string())).unwrap();
+        assert_eq!(
+            client.pool_max_idle_per_host,
+            DEFAULT_POOL_MAX_IDLE_PER_HOST
+        );
+        assert_eq!(client.pool_idle_timeout, DEFAULT_POOL_IDLE_TIMEOUT);
+
+        let client = client
+            .with_pool_max_idle_per_host(32)
+            .unwrap()
+            .with_pool_idle_timeout(Duration::from_secs(300))
+            .unwrap()
+            .with_timeout(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(client.pool_max_idle_per_host, 32);
+        assert_eq!(client.pool_idle_timeout, Duration::from_secs(300));
+        assert_eq!(client.timeout, Duration::from_secs(5));
+    }
+
     #[test]
     fn request_error_mapping() {
         let client = Anthropic::new(Some("test_key".to_string())).unwrap();
@@ -1081,16 +2878,10 @@ mod tests {
         use std::sync::atomic::{AtomicUsize, Ordering};
         use tokio::spawn;
 
-        let client = Anthropic {
-            api_key: "test".to_string(),
-            client: ReqwestClient::new(),
-            base_url: "http://localhost".to_string(),
-            timeout: Duration::from_secs(1),
-            max_retries: 1,
-            throughput_ops_sec: 1.0,
-            reserve_capacity: 1.0,
-            cached_headers: Arc::new(HeaderMap::new()),
-        };
+        let client = Anthropic::new(Some("test".to_string()))
+            .unwrap()
+            .with_max_retries(1)
+            .with_backoff_params(1.0, 1.0);
 
         let attempt_counter = Arc::new(AtomicUsize::new(0));
         let mut handles = vec![];
@@ -1123,4 +2914,96 @@ mod tests {
         // Verify all operations executed
         assert_eq!(attempt_counter.load(Ordering::SeqCst), 3);
     }
+
+    #[tokio::test]
+    async fn send_with_tools_runs_tools_until_done() {
+        let server = MockServer::start([
+            Reply::json(tool_use_body(
+                "msg_1",
+                "toolu_1",
+                serde_json::json!({"key": "answer"}),
+            )),
+            Reply::json(message_body("msg_2", "It is 42.")),
+        ]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        let handler_calls = Arc::clone(&calls);
+        let message = client
+            .send_with_tools(
+                hello_params(),
+                move |tool_use| {
+                    handler_calls.lock().unwrap().push(tool_use.clone());
+                    async move { Ok("42".to_string()) }
+                },
+                3,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(message.id, "msg_2");
+        assert_eq!(message.stop_reason, Some(StopReason::EndTurn));
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "toolu_1");
+        assert_eq!(calls[0].input, serde_json::json!({"key": "answer"}));
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn send_with_tools_stops_at_round_limit() {
+        let server = MockServer::start([Reply::json(tool_use_body(
+            "msg_1",
+            "toolu_1",
+            serde_json::json!({}),
+        ))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+
+        let message = client
+            .send_with_tools(
+                hello_params(),
+                |_tool_use| async { Err("tools are disabled".to_string()) },
+                0,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(message.id, "msg_1");
+        assert_eq!(message.stop_reason, Some(StopReason::ToolUse));
+        assert_eq!(server.requests().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn response_cache_serves_repeated_requests() {
+        let server = MockServer::start([
+            Reply::json(message_body("msg_1", "first")),
+            Reply::json(message_body("msg_2", "second")),
+        ]);
+        let cache = Arc::new(crate::MemoryResponseCache::new());
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0)
+            .with_response_cache(cache.clone());
+
+        let first = client.send(hello_params()).await.unwrap();
+        let again = client.send(hello_params()).await.unwrap();
+        assert_eq!(first.id, "msg_1");
+        assert_eq!(again, first);
+        assert_eq!(cache.len(), 1);
+
+        let other = client
+            .send(hello_params().with_message("and again"))
+            .await
+            .unwrap();
+        assert_eq!(other.id, "msg_2");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(server.requests().await.len(), 2);
+    }
 }
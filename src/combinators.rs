@@ -0,0 +1,854 @@
+//! Combinators over streams of [`MessageStreamEvent`]s.
+//!
+//! These helpers consume or adapt the streams returned by [`Anthropic::stream`] so that
+//! callers don't have to match on every event variant themselves.
+//!
+//! [`Anthropic::stream`]: crate::Anthropic::stream
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, StreamExt};
+
+use crate::{
+    AccumulatingStream, Anthropic, ContentBlock, ContentBlockDelta, ContentBlockDeltaEvent,
+    ContentBlockStartEvent, ContentBlockStopEvent, Error, InputJsonDelta, Message,
+    MessageCreateParams, MessageCreateTemplate, MessageDelta, MessageDeltaEvent, MessageDeltaUsage,
+    MessageStartEvent, MessageStopEvent, MessageStreamEvent, Result, SignatureDelta, TextBlock,
+    TextCitation, TextDelta, ThinkingBlock, ThinkingDelta,
+};
+
+/// Drains `stream` and returns every citation it carried, in arrival order.
+///
+/// Citations are collected both from text blocks in `content_block_start` events and from
+/// `citations_delta` events.  The first error in the stream is returned.
+pub async fn collect_citations<S>(stream: S) -> Result<Vec<TextCitation>>
+where
+    S: Stream<Item = Result<MessageStreamEvent>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut citations = vec![];
+    while let Some(event) = stream.next().await {
+        match event? {
+            MessageStreamEvent::ContentBlockStart(start) => {
+                if let ContentBlock::Text(text) = start.content_block {
+                    citations.extend(text.citations.into_iter().flatten());
+                }
+            }
+            MessageStreamEvent::ContentBlockDelta(event) => {
+                if let ContentBlockDelta::CitationsDelta(delta) = event.delta {
+                    citations.push(TextCitation::from(delta.citation));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(citations)
+}
+
+/// Drains `stream`, returning the reconstructed message along with every event it carried.
+///
+/// Unlike an [`AccumulatingStream`] consumed for its message alone, this keeps each event, so
+/// memory grows with the length of the response: a long answer streamed as many small text
+/// deltas is held roughly twice, once as events and once in the message.  The first error in
+/// the stream is returned.
+pub async fn collect_all<S>(stream: S) -> Result<(Message, Vec<MessageStreamEvent>)>
+where
+    S: Stream<Item = Result<MessageStreamEvent>> + Send + 'static,
+{
+    let (mut stream, message) = AccumulatingStream::new(stream);
+    let mut events = vec![];
+    while let Some(event) = stream.next().await {
+        events.push(event?);
+    }
+    let message = message
+        .await
+        .map_err(|_| Error::streaming("stream ended without producing a message", None))??;
+    Ok((message, events))
+}
+
+/// Synthesizes the stream of events that would have produced `message`.
+///
+/// This is the inverse of accumulating a stream, and is meant for fixtures: feeding the events
+/// to an [`AccumulatingStream`] or [`collect_all`] yields `message` again.  Each block gets a
+/// `content_block_start`, at most one delta per field, and a `content_block_stop`:
+///
+/// - text streams as one `text_delta`; citations are carried on the start event.
+/// - tool use streams its input as one `input_json_delta`.
+/// - thinking streams as a `thinking_delta` followed by a `signature_delta`.
+/// - other blocks, including server tool use, arrive whole in the start event.
+///
+/// The `message_start` usage reports zero output tokens, and the final count follows in
+/// `message_delta` with the stop reason and stop sequence.
+pub fn message_to_events(message: &Message) -> Vec<MessageStreamEvent> {
+    let mut start = message.clone();
+    start.content = vec![];
+    start.stop_reason = None;
+    start.stop_sequence = None;
+    start.usage.output_tokens = 0;
+    let mut events = vec![MessageStreamEvent::MessageStart(MessageStartEvent::new(
+        start,
+    ))];
+
+    for (index, block) in message.content.iter().enumerate() {
+        let (start, deltas) = match block {
+            ContentBlock::Text(text) => (
+                ContentBlock::Text(TextBlock {
+                    text: String::new(),
+                    ..text.clone()
+                }),
+                vec![ContentBlockDelta::TextDelta(TextDelta::new(
+                    text.text.clone(),
+                ))],
+            ),
+            ContentBlock::ToolUse(tool_use) => {
+                let mut start = tool_use.clone();
+                start.input = serde_json::Value::Object(serde_json::Map::new());
+                (
+                    ContentBlock::ToolUse(start),
+                    vec![ContentBlockDelta::InputJsonDelta(InputJsonDelta::new(
+                        tool_use.input.to_string(),
+                    ))],
+                )
+            }
+            ContentBlock::Thinking(thinking) => (
+                ContentBlock::Thinking(ThinkingBlock {
+                    thinking: String::new(),
+                    signature: String::new(),
+                }),
+                vec![
+                    ContentBlockDelta::ThinkingDelta(ThinkingDelta::new(thinking.thinking.clone())),
+                    ContentBlockDelta::SignatureDelta(SignatureDelta::new(
+                        thinking.signature.clone(),
+                    )),
+                ],
+            ),
+            other => (other.clone(), vec![]),
+        };
+        events.push(MessageStreamEvent::ContentBlockStart(
+            ContentBlockStartEvent::new(start, index),
+        ));
+        events.extend(deltas.into_iter().map(|delta| {
+            MessageStreamEvent::ContentBlockDelta(ContentBlockDeltaEvent::new(delta, index))
+        }));
+        events.push(MessageStreamEvent::ContentBlockStop(
+            ContentBlockStopEvent::new(index),
+        ));
+    }
+
+    let mut delta = MessageDelta::new();
+    delta.stop_reason = message.stop_reason;
+    delta.stop_sequence = message.stop_sequence.clone();
+    events.push(MessageStreamEvent::MessageDelta(MessageDeltaEvent::new(
+        delta,
+        MessageDeltaUsage::new(message.usage.output_tokens),
+    )));
+    events.push(MessageStreamEvent::MessageStop(MessageStopEvent::new()));
+    events
+}
+
+/// Ends `stream` once more than `max_output_tokens` output tokens have been reported.
+///
+/// Output tokens are read from the cumulative usage on `message_delta` events.  The event
+/// that crosses the limit is passed through, followed by a synthetic `message_stop`, and the
+/// source is dropped.  This only stops consuming the response: the server may keep generating
+/// (and billing) until it notices the closed connection.
+pub fn take_until_tokens<S>(
+    stream: S,
+    max_output_tokens: u32,
+) -> impl Stream<Item = Result<MessageStreamEvent>>
+where
+    S: Stream<Item = Result<MessageStreamEvent>>,
+{
+    let limit = i64::from(max_output_tokens);
+    futures::stream::unfold(
+        (Some(Box::pin(stream)), false),
+        move |(stream, exceeded)| async move {
+            if exceeded {
+                let stop = MessageStreamEvent::MessageStop(MessageStopEvent::new());
+                return Some((Ok(stop), (None, false)));
+            }
+            let mut stream = stream?;
+            let event = stream.next().await?;
+            let exceeded = matches!(
+                &event,
+                Ok(MessageStreamEvent::MessageDelta(delta))
+                    if i64::from(delta.usage.output_tokens) > limit
+            );
+            Some((event, (Some(stream), exceeded)))
+        },
+    )
+}
+
+/// Regroups the text deltas of `stream` into whole sentences.
+///
+/// A sentence ends at `.`, `?`, or `!` followed by whitespace; each is yielded with surrounding
+/// whitespace trimmed.  Whatever text remains when the stream ends is yielded last, even
+/// without a terminator.  Events other than text deltas are dropped and errors are passed
+/// through.  This suits speech synthesis or UIs that reveal a response a sentence at a time.
+pub fn sentence_chunks<S>(stream: S) -> impl Stream<Item = Result<String>>
+where
+    S: Stream<Item = Result<MessageStreamEvent>>,
+{
+    futures::stream::unfold(
+        (Some(Box::pin(stream)), String::new()),
+        |(mut stream, mut buffer)| async move {
+            loop {
+                if let Some(sentence) = take_sentence(&mut buffer) {
+                    return Some((Ok(sentence), (stream, buffer)));
+                }
+                let source = stream.as_mut()?;
+                match source.next().await {
+                    Some(Ok(MessageStreamEvent::ContentBlockDelta(event))) => {
+                        if let ContentBlockDelta::TextDelta(text) = event.delta {
+                            buffer.push_str(&text.text);
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Some((Err(err), (stream, buffer))),
+                    None => {
+                        let rest = buffer.trim().to_string();
+                        if rest.is_empty() {
+                            return None;
+                        }
+                        return Some((Ok(rest), (None, String::new())));
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// Removes and returns the first complete sentence in `buffer`, if there is one.
+fn take_sentence(buffer: &mut String) -> Option<String> {
+    let mut chars = buffer.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if matches!(c, '.' | '?' | '!')
+            && let Some(&(end, next)) = chars.peek()
+            && next.is_whitespace()
+        {
+            let sentence = buffer[..end].trim().to_string();
+            *buffer = buffer[end..].trim_start().to_string();
+            return Some(sentence);
+        }
+    }
+    None
+}
+
+/// Splits the deltas of `stream` into its thinking text and its response text.
+///
+/// The first stream yields the text of every `thinking_delta` and the second the text of every
+/// `text_delta`, each in arrival order.  Other events go to neither, and errors are delivered
+/// to both.  The source is driven by a spawned task, so this must be called within a Tokio
+/// runtime; as with [`broadcast`], a full output pauses the source, and dropping one output
+/// leaves the other unaffected.
+pub fn partition_deltas<S>(
+    stream: S,
+) -> (
+    impl Stream<Item = Result<String>> + Send + Unpin + 'static,
+    impl Stream<Item = Result<String>> + Send + Unpin + 'static,
+)
+where
+    S: Stream<Item = Result<MessageStreamEvent>> + Send + 'static,
+{
+    let (thinking_tx, thinking_rx) = mpsc::channel(BROADCAST_BUFFER);
+    let (text_tx, text_rx) = mpsc::channel(BROADCAST_BUFFER);
+    tokio::spawn(async move {
+        let mut stream = std::pin::pin!(stream);
+        let mut thinking_tx = Some(thinking_tx);
+        let mut text_tx = Some(text_tx);
+        while thinking_tx.is_some() || text_tx.is_some() {
+            let Some(item) = stream.next().await else {
+                break;
+            };
+            let (thinking, text) = match item {
+                Ok(MessageStreamEvent::ContentBlockDelta(event)) => match event.delta {
+                    ContentBlockDelta::ThinkingDelta(delta) => (Some(Ok(delta.thinking)), None),
+                    ContentBlockDelta::TextDelta(delta) => (None, Some(Ok(delta.text))),
+                    _ => (None, None),
+                },
+                Ok(_) => (None, None),
+                Err(err) => (Some(Err(err.clone())), Some(Err(err))),
+            };
+            for (sender, item) in [(&mut thinking_tx, thinking), (&mut text_tx, text)] {
+                if let Some(item) = item
+                    && let Some(tx) = sender
+                    && tx.send(item).await.is_err()
+                {
+                    *sender = None;
+                }
+            }
+        }
+    });
+    (thinking_rx, text_rx)
+}
+
+/// The number of items each [`broadcast`] output buffers before the source is paused.
+const BROADCAST_BUFFER: usize = 16;
+
+/// Clones every item of `stream` to `n` independently consumable streams.
+///
+/// The source is driven by a spawned task, so this must be called within a Tokio runtime.
+/// Each output buffers a bounded number of items; once any live output is full the source is
+/// not polled until that output is drained, so the slowest consumer sets the pace.  Dropping
+/// an output stops delivery to it without affecting the others, and the source is dropped once
+/// every output has been dropped.
+pub fn broadcast<S, T>(stream: S, n: usize) -> Vec<impl Stream<Item = T> + Send + Unpin + 'static>
+where
+    S: Stream<Item = T> + Send + 'static,
+    T: Clone + Send + 'static,
+{
+    let (mut senders, receivers): (Vec<_>, Vec<_>) =
+        (0..n).map(|_| mpsc::channel(BROADCAST_BUFFER)).unzip();
+    tokio::spawn(async move {
+        let mut stream = std::pin::pin!(stream);
+        while !senders.is_empty() {
+            let Some(item) = stream.next().await else {
+                break;
+            };
+            let mut idx = 0;
+            while idx < senders.len() {
+                if senders[idx].send(item.clone()).await.is_err() {
+                    senders.swap_remove(idx);
+                } else {
+                    idx += 1;
+                }
+            }
+        }
+    });
+    receivers
+}
+
+/// Reads `stream` ahead of its consumer, holding up to roughly `capacity` items.
+///
+/// The source is driven by a spawned task, so this must be called within a Tokio runtime.
+/// That task stops polling the source whenever the buffer is full, so a slow consumer slows
+/// reading from the connection instead of letting events pile up: memory use is bounded by
+/// `capacity` items (plus one in flight) no matter how far the consumer falls behind.  A
+/// `capacity` of zero still buffers one item.  Dropping the returned stream stops the task and
+/// drops the source.
+pub fn buffered<S, T>(stream: S, capacity: usize) -> impl Stream<Item = T> + Send + Unpin + 'static
+where
+    S: Stream<Item = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let (mut sender, receiver) = mpsc::channel(capacity.saturating_sub(1));
+    tokio::spawn(async move {
+        let mut stream = std::pin::pin!(stream);
+        while let Some(item) = stream.next().await {
+            if sender.send(item).await.is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Splits `stream` into two streams that each see every item.
+///
+/// This is [`broadcast`] with two outputs.
+pub fn tee<S, T>(
+    stream: S,
+) -> (
+    impl Stream<Item = T> + Send + Unpin + 'static,
+    impl Stream<Item = T> + Send + Unpin + 'static,
+)
+where
+    S: Stream<Item = T> + Send + 'static,
+    T: Clone + Send + 'static,
+{
+    let mut outputs = broadcast(stream, 2).into_iter();
+    let first = outputs
+        .next()
+        .expect("broadcast returns one stream per output");
+    let second = outputs
+        .next()
+        .expect("broadcast returns one stream per output");
+    (first, second)
+}
+
+/// A boxed stream of events, as returned by the functions from [`client`] and [`client_with`].
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<MessageStreamEvent>> + Send>>;
+
+/// A pending [`EventStream`], resolved once the API accepts the request.
+pub type PendingEventStream = Pin<Box<dyn Future<Output = Result<EventStream>> + Send>>;
+
+/// Connection settings for [`client_with`].
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    /// The API key; `None` reads `CLAUDIUS_API_KEY` or `ANTHROPIC_API_KEY` like [`Anthropic::new`].
+    pub api_key: Option<String>,
+
+    /// Betas to enable on every request.
+    pub betas: Vec<String>,
+
+    /// The API base URL, if not the default.
+    pub base_url: Option<String>,
+
+    /// The request timeout, if not the default.
+    pub timeout: Option<Duration>,
+}
+
+impl ClientConfig {
+    /// Creates a configuration with the given API key and default settings.
+    pub fn new(api_key: Option<String>) -> Self {
+        Self {
+            api_key,
+            ..Self::default()
+        }
+    }
+
+    /// Enable beta features on every request.
+    pub fn with_betas(mut self, betas: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.betas = betas.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Send requests to `base_url` instead of the default API endpoint.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Set the request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Returns a function that streams the response to a [`MessageCreateTemplate`].
+///
+/// This is [`client_with`] with only an API key configured.
+pub fn client(
+    api_key: Option<String>,
+) -> Result<impl Fn(MessageCreateTemplate) -> PendingEventStream + Clone + Send + Sync + 'static> {
+    client_with(ClientConfig::new(api_key))
+}
+
+/// Returns a function that streams the response to a [`MessageCreateTemplate`] using a client
+/// built from `config`.
+///
+/// Each template is applied over [`MessageCreateParams::default`] with streaming enabled, so a
+/// template that sets no model or `max_tokens` gets those defaults.
+///
+/// # Example
+///
+/// ```no_run
+/// # use claudius::combinators::{ClientConfig, client_with};
+/// # use claudius::{KnownModel, MessageCreateTemplate, MessageParam};
+/// # async fn example() -> claudius::Result<()> {
+/// let stream_template = client_with(
+///     ClientConfig::new(None).with_betas(["context-1m-2025-08-07"]),
+/// )?;
+/// let template = MessageCreateTemplate::new()
+///     .with_model(KnownModel::ClaudeSonnet45)
+///     .with_messages(vec![MessageParam::user("Hello!")]);
+/// let events = stream_template(template).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn client_with(
+    config: ClientConfig,
+) -> Result<impl Fn(MessageCreateTemplate) -> PendingEventStream + Clone + Send + Sync + 'static> {
+    let mut client = Anthropic::new(config.api_key)?.with_betas(config.betas);
+    if let Some(base_url) = config.base_url {
+        client = client.with_base_url(base_url);
+    }
+    if let Some(timeout) = config.timeout {
+        client = client.with_timeout(timeout)?;
+    }
+    Ok(
+        move |template: MessageCreateTemplate| -> PendingEventStream {
+            let client = client.clone();
+            Box::pin(async move {
+                let params = template.apply(MessageCreateParams::default().with_stream(true));
+                let stream = client.stream(&params).await?;
+                Ok(Box::pin(stream) as EventStream)
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CitationCharLocation, CitationContentBlockLocation, CitationPageLocation, CitationSpan,
+        CitationWebSearchResultLocation, CitationsDelta,
+    };
+
+    fn citation_event(delta: CitationsDelta) -> Result<MessageStreamEvent> {
+        Ok(MessageStreamEvent::ContentBlockDelta(
+            ContentBlockDeltaEvent::new(ContentBlockDelta::CitationsDelta(delta), 0),
+        ))
+    }
+
+    #[tokio::test]
+    async fn collect_citations_gathers_every_variant() {
+        let events = vec![
+            Ok(MessageStreamEvent::ContentBlockStart(
+                ContentBlockStartEvent::new(ContentBlock::Text(TextBlock::new("")), 0),
+            )),
+            Ok(MessageStreamEvent::ContentBlockDelta(
+                ContentBlockDeltaEvent::new(
+                    ContentBlockDelta::TextDelta(TextDelta::new("cited".to_string())),
+                    0,
+                ),
+            )),
+            citation_event(CitationsDelta::with_char_location(
+                CitationCharLocation::new("a".to_string(), 0, 0, 1, None),
+            )),
+            citation_event(CitationsDelta::with_page_location(
+                CitationPageLocation::new("b".to_string(), 1, 2, 3, None),
+            )),
+            citation_event(CitationsDelta::with_content_block_location(
+                CitationContentBlockLocation::new("c".to_string(), 2, 0, 1, None),
+            )),
+            citation_event(CitationsDelta::with_web_search_result_location(
+                CitationWebSearchResultLocation::new(
+                    "d".to_string(),
+                    "idx".to_string(),
+                    "https://example.com".to_string(),
+                    None,
+                ),
+            )),
+            Ok(MessageStreamEvent::MessageStop(MessageStopEvent::new())),
+        ];
+
+        let citations = collect_citations(futures::stream::iter(events))
+            .await
+            .unwrap();
+        let spans: Vec<Option<CitationSpan>> =
+            citations.iter().map(TextCitation::as_span).collect();
+        assert_eq!(
+            spans,
+            vec![
+                Some(CitationSpan::new(0, 0, 1, "a")),
+                Some(CitationSpan::new(1, 2, 3, "b")),
+                Some(CitationSpan::new(2, 0, 1, "c")),
+                None,
+            ]
+        );
+        assert_eq!(citations[3].cited_text(), "d");
+    }
+
+    #[tokio::test]
+    async fn collect_citations_propagates_errors() {
+        let events: Vec<Result<MessageStreamEvent>> = vec![Err(Error::streaming("boom", None))];
+        let result = collect_citations(futures::stream::iter(events)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn collect_all_returns_message_and_events() {
+        let start = Message::new(
+            "msg_1".to_string(),
+            vec![],
+            crate::Model::Known(crate::KnownModel::ClaudeHaiku45),
+            crate::Usage::new(5, 0),
+        );
+        let events = vec![
+            MessageStreamEvent::MessageStart(MessageStartEvent::new(start)),
+            MessageStreamEvent::ContentBlockStart(ContentBlockStartEvent::new(
+                ContentBlock::Text(TextBlock::new("")),
+                0,
+            )),
+            MessageStreamEvent::ContentBlockDelta(ContentBlockDeltaEvent::new(
+                ContentBlockDelta::TextDelta(TextDelta::new("Hello".to_string())),
+                0,
+            )),
+            MessageStreamEvent::ContentBlockDelta(ContentBlockDeltaEvent::new(
+                ContentBlockDelta::TextDelta(TextDelta::new(", world".to_string())),
+                0,
+            )),
+            MessageStreamEvent::ContentBlockStop(ContentBlockStopEvent::new(0)),
+            MessageStreamEvent::MessageDelta(MessageDeltaEvent::new(
+                MessageDelta::new().with_stop_reason(crate::StopReason::EndTurn),
+                MessageDeltaUsage::new(3),
+            )),
+            MessageStreamEvent::MessageStop(MessageStopEvent::new()),
+        ];
+
+        let (message, seen) =
+            collect_all(futures::stream::iter(events.clone().into_iter().map(Ok)))
+                .await
+                .unwrap();
+        assert_eq!(seen, events);
+        assert_eq!(message.id, "msg_1");
+        assert_eq!(
+            message.content,
+            vec![ContentBlock::Text(TextBlock::new("Hello, world"))]
+        );
+        assert_eq!(message.stop_reason, Some(crate::StopReason::EndTurn));
+        assert_eq!(message.usage.output_tokens, 3);
+    }
+
+    #[tokio::test]
+    async fn message_to_events_round_trips() {
+        let message =
+            Message::new(
+                "msg_fixture".to_string(),
+                vec![
+                    ContentBlock::Thinking(ThinkingBlock {
+                        thinking: "Let me check.".to_string(),
+                        signature: "sig".to_string(),
+                    }),
+                    ContentBlock::Text(TextBlock::new("Looking it up.").with_citation(
+                        TextCitation::char_location("a".to_string(), 0, 0, 1, None),
+                    )),
+                    ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                        "toolu_1",
+                        "lookup",
+                        serde_json::json!({"query": "weather", "days": [1, 2]}),
+                    )),
+                ],
+                crate::Model::Known(crate::KnownModel::ClaudeHaiku45),
+                crate::Usage::new(12, 34).with_cache_read_input_tokens(5),
+            )
+            .with_stop_reason(crate::StopReason::ToolUse);
+
+        let events = message_to_events(&message);
+        assert!(matches!(events[0], MessageStreamEvent::MessageStart(_)));
+        assert!(matches!(
+            events.last(),
+            Some(MessageStreamEvent::MessageStop(_))
+        ));
+        let deltas = events
+            .iter()
+            .filter(|event| matches!(event, MessageStreamEvent::ContentBlockDelta(_)))
+            .count();
+        assert_eq!(deltas, 4);
+
+        let (rebuilt, seen) =
+            collect_all(futures::stream::iter(events.clone().into_iter().map(Ok)))
+                .await
+                .unwrap();
+        assert_eq!(seen, events);
+        assert_eq!(rebuilt, message);
+    }
+
+    #[tokio::test]
+    async fn collect_all_propagates_errors() {
+        let events: Vec<Result<MessageStreamEvent>> = vec![
+            Ok(MessageStreamEvent::Ping),
+            Err(Error::streaming("boom", None)),
+        ];
+        assert!(collect_all(futures::stream::iter(events)).await.is_err());
+    }
+
+    fn output_tokens_event(output_tokens: i32) -> Result<MessageStreamEvent> {
+        Ok(MessageStreamEvent::MessageDelta(MessageDeltaEvent::new(
+            MessageDelta::new(),
+            MessageDeltaUsage::new(output_tokens),
+        )))
+    }
+
+    fn output_tokens_seen(events: &[Result<MessageStreamEvent>]) -> Vec<i32> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(MessageStreamEvent::MessageDelta(delta)) => Some(delta.usage.output_tokens),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn take_until_tokens_stops_after_threshold() {
+        let events = vec![
+            output_tokens_event(5),
+            output_tokens_event(10),
+            output_tokens_event(11),
+            output_tokens_event(20),
+            Ok(MessageStreamEvent::MessageStop(MessageStopEvent::new())),
+        ];
+        let seen: Vec<_> = take_until_tokens(futures::stream::iter(events), 10)
+            .collect()
+            .await;
+        assert_eq!(output_tokens_seen(&seen), vec![5, 10, 11]);
+        assert_eq!(seen.len(), 4);
+        assert!(matches!(
+            seen.last(),
+            Some(Ok(MessageStreamEvent::MessageStop(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn take_until_tokens_passes_short_streams_through() {
+        let events = vec![
+            output_tokens_event(3),
+            Ok(MessageStreamEvent::MessageStop(MessageStopEvent::new())),
+        ];
+        let seen: Vec<_> = take_until_tokens(futures::stream::iter(events), 10)
+            .collect()
+            .await;
+        assert_eq!(seen.len(), 2);
+        assert_eq!(output_tokens_seen(&seen), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn sentence_chunks_splits_across_deltas() {
+        let text = |text: &str| {
+            Ok(MessageStreamEvent::ContentBlockDelta(
+                ContentBlockDeltaEvent::new(
+                    ContentBlockDelta::TextDelta(TextDelta::new(text.to_string())),
+                    0,
+                ),
+            ))
+        };
+        let events = vec![
+            Ok(MessageStreamEvent::ContentBlockStart(
+                ContentBlockStartEvent::new(ContentBlock::Text(TextBlock::new("")), 0),
+            )),
+            text("Hello there"),
+            text(". How are"),
+            text(" you? I'm fine!"),
+            text(" Version 1.5 is out"),
+            text("... and"),
+            text(" then"),
+            Ok(MessageStreamEvent::MessageStop(MessageStopEvent::new())),
+        ];
+        let sentences: Vec<_> = sentence_chunks(futures::stream::iter(events))
+            .map(|sentence| sentence.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            sentences,
+            vec![
+                "Hello there.",
+                "How are you?",
+                "I'm fine!",
+                "Version 1.5 is out...",
+                "and then",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn partition_deltas_routes_thinking_and_text() {
+        let delta = |index: usize, delta: ContentBlockDelta| {
+            Ok(MessageStreamEvent::ContentBlockDelta(
+                ContentBlockDeltaEvent::new(delta, index),
+            ))
+        };
+        let thinking =
+            |text: &str| ContentBlockDelta::ThinkingDelta(ThinkingDelta::new(text.to_string()));
+        let text = |text: &str| ContentBlockDelta::TextDelta(TextDelta::new(text.to_string()));
+        let events = vec![
+            Ok(MessageStreamEvent::ContentBlockStart(
+                ContentBlockStartEvent::new(ContentBlock::Thinking(ThinkingBlock::new("", "")), 0),
+            )),
+            delta(0, thinking("Two plus ")),
+            delta(1, text("The answer")),
+            delta(0, thinking("two is four.")),
+            delta(
+                0,
+                ContentBlockDelta::SignatureDelta(SignatureDelta::new("sig".to_string())),
+            ),
+            delta(1, text(" is 4.")),
+            Ok(MessageStreamEvent::MessageStop(MessageStopEvent::new())),
+        ];
+        let (thinking, text) = partition_deltas(futures::stream::iter(events));
+        let (thinking, text) = futures::join!(
+            thinking.map(|chunk| chunk.unwrap()).collect::<Vec<_>>(),
+            text.map(|chunk| chunk.unwrap()).collect::<Vec<_>>()
+        );
+        assert_eq!(thinking, vec!["Two plus ", "two is four."]);
+        assert_eq!(text, vec!["The answer", " is 4."]);
+    }
+
+    #[tokio::test]
+    async fn buffered_preserves_order_with_small_capacity() {
+        let output = buffered(futures::stream::iter(0..100), 2);
+        let items: Vec<_> = output
+            .then(|item| async move {
+                tokio::task::yield_now().await;
+                item
+            })
+            .collect()
+            .await;
+        assert_eq!(items, (0..100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn buffered_applies_backpressure() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let produced = Arc::new(AtomicUsize::new(0));
+        let source = {
+            let produced = Arc::clone(&produced);
+            futures::stream::iter(0..100).inspect(move |_| {
+                produced.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let mut output = buffered(source, 4);
+        assert_eq!(output.next().await, Some(0));
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        // One item was consumed, at most `capacity` are buffered, and one may be in flight.
+        assert!(produced.load(Ordering::SeqCst) <= 6, "{produced:?}");
+    }
+
+    #[tokio::test]
+    async fn tee_delivers_every_item_to_both_outputs() {
+        let (left, right) = tee(futures::stream::iter(vec![1, 2, 3]));
+        let (left, right) = futures::join!(left.collect::<Vec<_>>(), right.collect::<Vec<_>>());
+        assert_eq!(left, vec![1, 2, 3]);
+        assert_eq!(right, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn broadcast_continues_after_an_output_is_dropped() {
+        let mut outputs = broadcast(futures::stream::iter(0..(BROADCAST_BUFFER * 4)), 3);
+        drop(outputs.remove(0));
+        let collected =
+            futures::future::join_all(outputs.into_iter().map(|output| output.collect::<Vec<_>>()))
+                .await;
+        for items in collected {
+            assert_eq!(items, (0..(BROADCAST_BUFFER * 4)).collect::<Vec<_>>());
+        }
+    }
+
+    #[tokio::test]
+    async fn client_with_sends_configured_betas() {
+        use crate::test_support::{MockServer, Reply, sse_events};
+
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","model":"claude-haiku-4-5","content":[],"stop_reason":null,"usage":{"input_tokens":3,"output_tokens":1}}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn","stop_sequence":null},"usage":{"output_tokens":1}}"#,
+            r#"{"type":"message_stop"}"#,
+        ]
+        .map(|event| serde_json::from_str::<serde_json::Value>(event).unwrap());
+        let server = MockServer::start([Reply::sse(sse_events(&events))]);
+
+        let stream_template = client_with(
+            ClientConfig::new(Some("test-key".to_string()))
+                .with_betas(["first-beta", "second-beta"])
+                .with_base_url(server.url())
+                .with_timeout(Duration::from_secs(5)),
+        )
+        .unwrap();
+        let template = MessageCreateTemplate::new()
+            .with_max_tokens(16)
+            .with_messages(vec![crate::MessageParam::user("hi")]);
+        let (message, _) = collect_all(stream_template(template).await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(message.id, "msg_1");
+
+        let request = server.requests().await.remove(0);
+        assert!(
+            request.request_line().starts_with("POST /v1/messages "),
+            "{request:?}"
+        );
+        assert_eq!(
+            request.headers("anthropic-beta"),
+            vec!["first-beta,second-beta"]
+        );
+    }
+}
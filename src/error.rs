@@ -156,6 +156,12 @@ pub enum Error {
         source: Option<Arc<dyn error::Error + Send + Sync>>,
     },
 
+    /// A response body exceeded the client's configured size limit.
+    ResponseTooLarge {
+        /// The limit, in bytes, that the response exceeded.
+        limit: usize,
+    },
+
     /// Unknown error.
     Unknown {
         /// Human-readable error message.
@@ -338,6 +344,11 @@ impl Error {
         }
     }
 
+    /// Creates a new error for a response larger than `limit` bytes.
+    pub fn response_too_large(limit: usize) -> Self {
+        Error::ResponseTooLarge { limit }
+    }
+
     /// Creates a new unknown error.
     pub fn unknown(message: impl Into<String>) -> Self {
         Error::Unknown {
@@ -401,11 +412,31 @@ impl Error {
     }
 
     /// Returns true if this error is retryable.
+    ///
+    /// Rate limits, overloaded or unavailable servers, internal server errors, timeouts, and
+    /// connection failures are retryable.  Authentication, permission, not-found, invalid
+    /// request, and billing errors are not.  For [`Error::Api`], a recognized API error type
+    /// takes precedence over the status code.
     pub fn is_retryable(&self) -> bool {
         match self {
-            Error::Api { status_code, .. } => {
-                matches!(status_code, 408 | 409 | 429 | 500..=599)
-            }
+            Error::Api {
+                status_code,
+                error_type,
+                ..
+            } => match error_type.as_deref() {
+                Some("rate_limit_error" | "overloaded_error" | "api_error" | "timeout_error") => {
+                    true
+                }
+                Some(
+                    "authentication_error"
+                    | "permission_error"
+                    | "not_found_error"
+                    | "invalid_request_error"
+                    | "billing_error"
+                    | "request_too_large",
+                ) => false,
+                _ => matches!(status_code, 408 | 409 | 429 | 500..=599),
+            },
             Error::Timeout { .. } => true,
             Error::Connection { .. } => true,
             Error::RateLimit { .. } => true,
@@ -420,6 +451,11 @@ impl Error {
         matches!(self, Error::ToDo { .. })
     }
 
+    /// Returns true if a response exceeded the client's size limit.
+    pub fn is_response_too_large(&self) -> bool {
+        matches!(self, Error::ResponseTooLarge { .. })
+    }
+
     /// Returns true if this error is a validation error.
     pub fn is_validation(&self) -> bool {
         matches!(self, Error::Validation { .. })
@@ -434,10 +470,21 @@ impl Error {
         }
     }
 
-    /// Returns the status code associated with this error, if any.
+    /// Returns the HTTP status code associated with this error, if any.
+    ///
+    /// Errors the client maps to a dedicated variant report that variant's canonical status:
+    /// 400, 401, 403, 404, 429, 500, or 503.  Errors that did not come from an HTTP response,
+    /// such as connection failures, return `None`.
     pub fn status_code(&self) -> Option<u16> {
         match self {
             Error::Api { status_code, .. } => Some(*status_code),
+            Error::BadRequest { .. } => Some(400),
+            Error::Authentication { .. } => Some(401),
+            Error::Permission { .. } => Some(403),
+            Error::NotFound { .. } => Some(404),
+            Error::RateLimit { .. } => Some(429),
+            Error::InternalServer { .. } => Some(500),
+            Error::ServiceUnavailable { .. } => Some(503),
             _ => None,
         }
     }
@@ -573,6 +620,9 @@ impl fmt::Display for Error {
             Error::Encoding { message, .. } => {
                 write!(f, "Encoding error: {message}")
             }
+            Error::ResponseTooLarge { limit } => {
+                write!(f, "Response too large: exceeded the limit of {limit} bytes")
+            }
             Error::Unknown { message } => {
                 write!(f, "Unknown error: {message}")
             }
@@ -622,6 +672,18 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Error::timeout(format!("Request timed out: {err}"), None)
+        } else if err.is_connect() {
+            Error::connection(format!("Connection error: {err}"), Some(Box::new(err)))
+        } else {
+            Error::http_client(format!("Request failed: {err}"), Some(Box::new(err)))
+        }
+    }
+}
+
 impl From<url::ParseError> for Error {
     fn from(err: url::ParseError) -> Self {
         Error::url(format!("URL parse error: {err}"), Some(err))
@@ -636,3 +698,83 @@ impl From<Utf8Error> for Error {
 
 /// A specialized Result type for Claudius operations.
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api(status_code: u16, error_type: &str) -> Error {
+        Error::api(
+            status_code,
+            Some(error_type.to_string()),
+            "message".to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let retryable = [
+            Error::rate_limit("slow down", Some(1)),
+            Error::service_unavailable("overloaded", None),
+            Error::internal_server("oops", None),
+            Error::timeout("timed out", None),
+            Error::connection("refused", None),
+            api(529, "overloaded_error"),
+            api(429, "rate_limit_error"),
+            api(504, "timeout_error"),
+            api(500, "api_error"),
+            Error::api(502, None, "bad gateway".to_string(), None),
+        ];
+        for err in retryable {
+            assert!(err.is_retryable(), "expected retryable: {err:?}");
+        }
+    }
+
+    #[test]
+    fn non_retryable_errors() {
+        let non_retryable = [
+            Error::authentication("bad key"),
+            Error::permission("denied"),
+            Error::not_found("missing", None, None),
+            Error::bad_request("invalid", None),
+            Error::validation("invalid", None),
+            Error::serialization("bad json", None),
+            api(401, "authentication_error"),
+            api(403, "permission_error"),
+            api(404, "not_found_error"),
+            api(400, "invalid_request_error"),
+            api(402, "billing_error"),
+            api(413, "request_too_large"),
+        ];
+        for err in non_retryable {
+            assert!(!err.is_retryable(), "expected non-retryable: {err:?}");
+        }
+    }
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(api(529, "overloaded_error").status_code(), Some(529));
+        assert_eq!(Error::bad_request("invalid", None).status_code(), Some(400));
+        assert_eq!(Error::authentication("bad key").status_code(), Some(401));
+        assert_eq!(Error::permission("denied").status_code(), Some(403));
+        assert_eq!(
+            Error::not_found("missing", None, None).status_code(),
+            Some(404)
+        );
+        assert_eq!(
+            Error::rate_limit("slow down", None).status_code(),
+            Some(429)
+        );
+        assert_eq!(
+            Error::internal_server("oops", None).status_code(),
+            Some(500)
+        );
+        assert_eq!(
+            Error::service_unavailable("overloaded", None).status_code(),
+            Some(503)
+        );
+        assert_eq!(Error::connection("refused", None).status_code(), None);
+        assert_eq!(Error::timeout("timed out", None).status_code(), None);
+    }
+}
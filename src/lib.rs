@@ -6,7 +6,15 @@
 //! Anthropic's Claude AI models, including support for streaming responses, tool use,
 //! and agent-based interactions.
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod chat;
+pub mod combinators;
+
+/// The HTTP header types a [`RequestSigner`] works with.
+pub mod header {
+    pub use reqwest::header::{HeaderMap, HeaderValue};
+}
 
 mod accumulating_stream;
 mod agent;
@@ -16,27 +24,43 @@ mod client;
 mod client_logger;
 mod error;
 mod json_schema;
+mod middleware;
 mod observability;
+mod openai;
 mod prompt;
+mod rate_limit;
+mod recording;
 mod render;
+mod response_cache;
 mod sse;
+#[cfg(test)]
+mod test_support;
 mod types;
 
 pub use accumulating_stream::AccumulatingStream;
 pub use agent::{
-    Agent, Budget, FileSystem, IntermediateToolResult, Mount, MountHierarchy, Permissions,
-    TokenKind, Tool, ToolCallback, ToolResult, ToolSearchFileSystem, TurnOutcome, TurnStep,
+    Agent, BashSession, Budget, CustomToolWithHandler, FileSystem, IntermediateToolResult, Mount,
+    MountHierarchy, OwnedBudgetAllocation, PathFileSystem, Permissions, TokenKind, Tool,
+    ToolCallback, ToolResult, ToolSearchFileSystem, TurnConfig, TurnOutcome, TurnStep,
+    align_tool_results, fn_tool, fn_tool_stateful,
 };
-pub use client::{Anthropic, LoggingStream};
+pub use cache_control::{MAX_CACHE_BREAKPOINTS, cacheable_context};
+pub use client::{AbortableStream, Anthropic, LoggingStream, Page, StreamHandle, paginate};
 pub use client_logger::ClientLogger;
 pub use error::{Error, Result};
 pub use json_schema::JsonSchema;
+pub use middleware::{RequestMiddleware, RequestSigner, ResponseMiddleware};
 pub use observability::register_biometrics;
+pub use openai::{from_openai_messages, to_openai_messages};
 pub use prompt::{
     PromptTestConfig, PromptTestResult, assert_contains, assert_max_length, assert_min_length,
     assert_not_contains, assert_test_passed, test_prompt,
 };
-pub use render::{AgentStreamContext, PlainTextRenderer, Renderer, StreamContext};
+pub use recording::{RecordedExchange, Recorder, RecordingLogger, Replay};
+pub use render::{
+    AgentStreamContext, ChannelRenderer, PlainTextRenderer, RenderEvent, Renderer, StreamContext,
+};
+pub use response_cache::{MemoryResponseCache, ResponseCache};
 pub use types::*;
 
 /// Pushes a message to the messages vector, or merges it with the last message if they have the same role.
@@ -59,10 +83,18 @@ pub fn push_or_merge_message(messages: &mut Vec<MessageParam>, to_push: MessageP
 ///
 /// Handles all combinations of string and array content types, converting between
 /// them as necessary to produce a unified message content.
+///
+/// The API requires `tool_result` blocks to come before any other content in a user message,
+/// so when the merged content is an array, tool results are moved to the front.  Otherwise
+/// blocks keep their order: existing content first, then new content.  For example, merging
+/// `[tool_result(a)]` after `"text"` yields `[tool_result(a), text]`, and merging
+/// `[tool_result(b), "more"]` after `[tool_result(a), "text"]` yields
+/// `[tool_result(a), tool_result(b), "text", "more"]`.
 pub fn merge_message_content(existing: &mut MessageParamContent, new: MessageParamContent) {
     match (&mut *existing, new) {
         (MessageParamContent::Array(existing_blocks), MessageParamContent::Array(new_blocks)) => {
             existing_blocks.extend(new_blocks);
+            hoist_tool_results(existing_blocks);
         }
         (MessageParamContent::Array(existing_blocks), MessageParamContent::String(new_string)) => {
             existing_blocks.push(ContentBlock::Text(crate::TextBlock::new(new_string)));
@@ -72,6 +104,7 @@ pub fn merge_message_content(existing: &mut MessageParamContent, new: MessagePar
                 existing_string.clone(),
             ))];
             combined.extend(new_blocks);
+            hoist_tool_results(&mut combined);
             *existing = MessageParamContent::Array(combined);
         }
         (MessageParamContent::String(existing_string), MessageParamContent::String(new_string)) => {
@@ -79,3 +112,183 @@ pub fn merge_message_content(existing: &mut MessageParamContent, new: MessagePar
         }
     }
 }
+
+/// Returns an error if two `tool_use` blocks in `message` share an id.
+///
+/// The API rejects a conversation that reuses a tool use id, which is easy to do when tool
+/// uses are synthesized for tests or replays; see [`ToolUseBlock::new_with_random_id`].
+pub fn validate_tool_use_ids(message: &Message) -> Result<()> {
+    validate_tool_use_id_blocks(&message.content)
+}
+
+/// Returns an error naming the first tool use id that appears twice in `blocks`.
+pub(crate) fn validate_tool_use_id_blocks<'a>(
+    blocks: impl IntoIterator<Item = &'a ContentBlock>,
+) -> Result<()> {
+    let mut seen = std::collections::HashSet::new();
+    for block in blocks {
+        if let ContentBlock::ToolUse(tool_use) = block
+            && !seen.insert(tool_use.id.as_str())
+        {
+            return Err(Error::validation(
+                format!("duplicate tool_use id {:?}", tool_use.id),
+                Some("messages".to_string()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Moves `tool_result` blocks ahead of all other blocks, preserving relative order.
+fn hoist_tool_results(blocks: &mut Vec<ContentBlock>) {
+    let (mut tool_results, others): (Vec<_>, Vec<_>) = std::mem::take(blocks)
+        .into_iter()
+        .partition(|block| matches!(block, ContentBlock::ToolResult(_)));
+    tool_results.extend(others);
+    *blocks = tool_results;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_result(id: &str) -> ContentBlock {
+        ContentBlock::ToolResult(
+            ToolResultBlock::new(id.to_string()).with_string_content(format!("result {id}")),
+        )
+    }
+
+    fn text(text: &str) -> ContentBlock {
+        ContentBlock::Text(TextBlock::new(text))
+    }
+
+    #[test]
+    fn validate_tool_use_ids_rejects_duplicates() {
+        let tool_use = |id: &str| {
+            ContentBlock::ToolUse(ToolUseBlock::new(id, "lookup", serde_json::json!({})))
+        };
+        let message = |content| {
+            Message::new(
+                "msg_1".to_string(),
+                content,
+                Model::Known(KnownModel::ClaudeHaiku45),
+                Usage::new(1, 1),
+            )
+        };
+
+        assert!(
+            validate_tool_use_ids(&message(vec![tool_use("a"), text("b"), tool_use("b")])).is_ok()
+        );
+        let err =
+            validate_tool_use_ids(&message(vec![tool_use("a"), tool_use("b"), tool_use("a")]))
+                .unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("\"a\""), "{err}");
+
+        let fresh = vec![
+            ContentBlock::ToolUse(ToolUseBlock::new_with_random_id(
+                "lookup",
+                serde_json::json!({}),
+            )),
+            ContentBlock::ToolUse(ToolUseBlock::new_with_random_id(
+                "lookup",
+                serde_json::json!({}),
+            )),
+        ];
+        assert!(validate_tool_use_ids(&message(fresh)).is_ok());
+    }
+
+    #[test]
+    fn merge_strings_concatenates() {
+        let mut content = MessageParamContent::String("hello".to_string());
+        merge_message_content(
+            &mut content,
+            MessageParamContent::String(" world".to_string()),
+        );
+        assert_eq!(
+            content,
+            MessageParamContent::String("hello world".to_string())
+        );
+    }
+
+    #[test]
+    fn merge_tool_results_after_string_puts_tool_results_first() {
+        let mut content = MessageParamContent::String("note".to_string());
+        merge_message_content(
+            &mut content,
+            MessageParamContent::Array(vec![tool_result("a"), tool_result("b")]),
+        );
+        assert_eq!(
+            content,
+            MessageParamContent::Array(vec![tool_result("a"), tool_result("b"), text("note")])
+        );
+    }
+
+    #[test]
+    fn merge_string_after_tool_results_appends_text() {
+        let mut content = MessageParamContent::Array(vec![tool_result("a")]);
+        merge_message_content(
+            &mut content,
+            MessageParamContent::String("next".to_string()),
+        );
+        assert_eq!(
+            content,
+            MessageParamContent::Array(vec![tool_result("a"), text("next")])
+        );
+    }
+
+    #[test]
+    fn merge_interleaved_arrays_keeps_tool_results_in_order() {
+        let mut content = MessageParamContent::Array(vec![tool_result("a"), text("first")]);
+        merge_message_content(
+            &mut content,
+            MessageParamContent::Array(vec![tool_result("b"), text("second")]),
+        );
+        assert_eq!(
+            content,
+            MessageParamContent::Array(vec![
+                tool_result("a"),
+                tool_result("b"),
+                text("first"),
+                text("second"),
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_without_tool_results_preserves_order() {
+        let mut content = MessageParamContent::String("one".to_string());
+        merge_message_content(
+            &mut content,
+            MessageParamContent::Array(vec![text("two"), text("three")]),
+        );
+        assert_eq!(
+            content,
+            MessageParamContent::Array(vec![text("one"), text("two"), text("three")])
+        );
+    }
+
+    #[test]
+    fn push_or_merge_combines_tool_result_messages() {
+        let mut messages = vec![MessageParam::user("continue")];
+        push_or_merge_message(
+            &mut messages,
+            MessageParam::new(
+                MessageParamContent::Array(vec![tool_result("a")]),
+                MessageRole::User,
+            ),
+        );
+        push_or_merge_message(
+            &mut messages,
+            MessageParam::new(
+                MessageParamContent::Array(vec![tool_result("b")]),
+                MessageRole::User,
+            ),
+        );
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].content,
+            MessageParamContent::Array(vec![tool_result("a"), tool_result("b"), text("continue")])
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Request and response middleware for the Anthropic client.
+//!
+//! A [`RequestMiddleware`] registered with
+//! [`Anthropic::with_request_middleware`](crate::Anthropic::with_request_middleware) sees every
+//! message request before it is validated and sent, so cross-cutting changes such as a shared
+//! system prompt prefix or a tool deny-list live in one place instead of in every agent.  A
+//! [`ResponseMiddleware`] does the same for the messages that come back, and a
+//! [`RequestSigner`] adds headers computed over the exact bytes sent.
+
+use reqwest::header::HeaderMap;
+
+use crate::{Message, MessageCreateParams, Result};
+
+/// Transforms message requests before the client sends them.
+///
+/// Middleware runs in registration order on every call to
+/// [`send`](crate::Anthropic::send), [`send_raw`](crate::Anthropic::send_raw), and
+/// [`stream`](crate::Anthropic::stream), before the request is validated.  Each middleware sees
+/// the changes made by the ones registered before it.
+///
+/// Any `Fn(&mut MessageCreateParams) + Send + Sync` closure is a middleware.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use claudius::{Anthropic, MessageCreateParams, RequestMiddleware};
+/// struct NoTools;
+///
+/// impl RequestMiddleware for NoTools {
+///     fn transform(&self, params: &mut MessageCreateParams) {
+///         params.tools = None;
+///         params.tool_choice = None;
+///     }
+/// }
+///
+/// let client = Anthropic::new(Some("api-key".to_string()))?
+///     .with_request_middleware(Arc::new(NoTools));
+/// # Ok::<(), claudius::Error>(())
+/// ```
+pub trait RequestMiddleware: Send + Sync {
+    /// Modifies `params` in place.
+    fn transform(&self, params: &mut MessageCreateParams);
+}
+
+impl<F> RequestMiddleware for F
+where
+    F: Fn(&mut MessageCreateParams) + Send + Sync,
+{
+    fn transform(&self, params: &mut MessageCreateParams) {
+        self(params)
+    }
+}
+
+/// Transforms messages after the client receives them.
+///
+/// Middleware runs in registration order on the message from
+/// [`send`](crate::Anthropic::send) and [`send_raw`](crate::Anthropic::send_raw), and on the
+/// message accumulated by [`stream_with_logger`](crate::Anthropic::stream_with_logger) and
+/// agent turns.  Use it to scrub PII, trim whitespace, or normalize tool inputs.
+///
+/// Any `Fn(&mut Message) + Send + Sync` closure is a middleware.
+pub trait ResponseMiddleware: Send + Sync {
+    /// Modifies `message` in place.
+    fn transform(&self, message: &mut Message);
+}
+
+impl<F> ResponseMiddleware for F
+where
+    F: Fn(&mut Message) + Send + Sync,
+{
+    fn transform(&self, message: &mut Message) {
+        self(message)
+    }
+}
+
+/// Signs requests before the client sends them.
+///
+/// A signer registered with
+/// [`Anthropic::with_request_signer`](crate::Anthropic::with_request_signer) is called once per
+/// HTTP attempt, retries included, with the serialized request body and the headers about to
+/// be sent.  The headers already carry the API key, version, and any beta or custom headers,
+/// so a signer can cover them or add its own, as a proxy that checks an HMAC of the body
+/// requires.  Requests without a body, such as listing models, are signed over an empty body.
+/// An error aborts the request and is returned to the caller.
+///
+/// Any `Fn(&[u8], &mut HeaderMap) -> Result<()> + Send + Sync` closure is a signer; the header
+/// types are re-exported from [`claudius::header`](crate::header).
+pub trait RequestSigner: Send + Sync {
+    /// Adds or replaces entries in `headers` for a request whose body is `body`.
+    fn sign(&self, body: &[u8], headers: &mut HeaderMap) -> Result<()>;
+}
+
+impl<F> RequestSigner for F
+where
+    F: Fn(&[u8], &mut HeaderMap) -> Result<()> + Send + Sync,
+{
+    fn sign(&self, body: &[u8], headers: &mut HeaderMap) -> Result<()> {
+        self(body, headers)
+    }
+}
@@ -1,5 +1,10 @@
+use std::future::Future;
+use std::time::Duration;
+
 use biometrics::{Collector, Counter, Moments};
 
+use crate::{ToolResult, ToolUseBlock};
+
 pub(crate) static CLIENT_REQUESTS: Counter = Counter::new("claudius.client.requests");
 pub(crate) static CLIENT_REQUEST_ERRORS: Counter = Counter::new("claudius.client.request_errors");
 pub(crate) static CLIENT_REQUEST_RETRIES: Counter = Counter::new("claudius.client.retries");
@@ -42,3 +47,82 @@ pub fn register_biometrics(collector: &Collector) {
     collector.register_counter(&AGENT_TOOL_ERRORS);
     collector.register_moments(&AGENT_TOOL_DURATION);
 }
+
+/// Traces one tool call when the `tracing` feature is enabled, and does nothing otherwise.
+///
+/// Each call gets a `tool_call` span carrying the tool name, tool use id, and input size, with
+/// `compute_tool_result` and `apply_tool_result` child spans and a closing event that records
+/// the outcome and duration.
+pub(crate) struct ToolCallTrace {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl ToolCallTrace {
+    pub(crate) fn start(tool_use: &ToolUseBlock) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            let input_bytes = serde_json::to_vec(&tool_use.input).map_or(0, |input| input.len());
+            Self {
+                span: tracing::info_span!(
+                    "tool_call",
+                    tool = %tool_use.name,
+                    tool_use_id = %tool_use.id,
+                    input_bytes,
+                ),
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            _ = tool_use;
+            Self {}
+        }
+    }
+
+    pub(crate) async fn compute<F: Future>(&self, compute: F) -> F::Output {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+            compute
+                .instrument(tracing::info_span!(parent: &self.span, "compute_tool_result"))
+                .await
+        }
+        #[cfg(not(feature = "tracing"))]
+        compute.await
+    }
+
+    pub(crate) async fn apply<F: Future>(&self, apply: F) -> F::Output {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+            apply
+                .instrument(tracing::info_span!(parent: &self.span, "apply_tool_result"))
+                .await
+        }
+        #[cfg(not(feature = "tracing"))]
+        apply.await
+    }
+
+    pub(crate) fn finish(&self, result: &ToolResult, elapsed: Duration) {
+        #[cfg(feature = "tracing")]
+        {
+            let duration_ms = elapsed.as_secs_f64() * 1_000.0;
+            match result {
+                std::ops::ControlFlow::Continue(Ok(_)) => {
+                    tracing::info!(parent: &self.span, success = true, duration_ms, "tool call finished");
+                }
+                std::ops::ControlFlow::Continue(Err(_)) => {
+                    tracing::warn!(parent: &self.span, success = false, duration_ms, "tool call returned an error");
+                }
+                std::ops::ControlFlow::Break(err) => {
+                    tracing::error!(parent: &self.span, success = false, duration_ms, error = %err, "tool call aborted the turn");
+                }
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            _ = result;
+            _ = elapsed;
+        }
+    }
+}
@@ -0,0 +1,528 @@
+//! Converting conversations to and from the OpenAI chat-completions message format.
+
+use serde_json::{Value, json};
+
+use crate::{
+    Base64ImageSource, Content, ContentBlock, Error, ImageBlock, ImageMediaType, ImageSource,
+    MessageParam, MessageParamContent, MessageRole, Result, SystemPrompt, TextBlock,
+    ToolResultBlock, ToolResultBlockContent, ToolUseBlock, UrlImageSource, push_or_merge_message,
+};
+
+/// Converts `messages` to an OpenAI chat-completions `messages` array.
+///
+/// The mapping is best-effort:
+///
+/// - Text becomes `{"role", "content"}`; several text blocks in one message are joined by
+///   newlines.
+/// - `tool_use` blocks become `tool_calls` on the assistant message, with the input serialized
+///   as the JSON-string `arguments`.
+/// - Each `tool_result` becomes a `{"role": "tool", "tool_call_id", "content"}` message placed
+///   before the rest of the user turn.  `is_error` has no equivalent and is dropped.
+/// - User images become `image_url` parts, with base64 data inlined as a `data:` URL.  Images
+///   uploaded through the Files API, images inside tool results, and documents cannot be
+///   expressed and are replaced by `[image]` or `[document]` text.
+/// - Thinking, redacted thinking, and server tool blocks are dropped.
+pub fn to_openai_messages(messages: &[MessageParam]) -> Value {
+    let mut out = vec![];
+    for message in messages {
+        match (message.role, &message.content) {
+            (MessageRole::User, MessageParamContent::String(text)) => {
+                out.push(json!({"role": "user", "content": text}));
+            }
+            (MessageRole::Assistant, MessageParamContent::String(text)) => {
+                out.push(json!({"role": "assistant", "content": text}));
+            }
+            (MessageRole::User, MessageParamContent::Array(blocks)) => push_user(&mut out, blocks),
+            (MessageRole::Assistant, MessageParamContent::Array(blocks)) => {
+                push_assistant(&mut out, blocks)
+            }
+        }
+    }
+    Value::Array(out)
+}
+
+/// Parses an OpenAI chat-completions `messages` array into a system prompt and messages.
+///
+/// This is the inverse of [`to_openai_messages`]:
+///
+/// - `system` and `developer` messages are joined by blank lines into the returned
+///   [`SystemPrompt`] rather than appearing in the conversation.
+/// - `content` may be a string, an array of `text` and `image_url` parts, or `null`.  Data URLs
+///   become base64 images; other URLs are passed through.
+/// - Assistant `tool_calls` become `tool_use` blocks; their `arguments` must be a JSON string.
+/// - `tool` messages become `tool_result` blocks in the following user turn.
+/// - Consecutive messages with the same role are merged, as the Messages API requires turns to
+///   alternate.
+///
+/// Unsupported fields such as `name`, `refusal`, `audio`, and the legacy `function_call` are
+/// ignored.  Unknown roles, content parts other than `text` and `image_url`, and malformed
+/// tool calls are validation errors.
+pub fn from_openai_messages(value: &Value) -> Result<(Option<SystemPrompt>, Vec<MessageParam>)> {
+    let Some(entries) = value.as_array() else {
+        return Err(Error::validation(
+            "OpenAI messages must be a JSON array",
+            Some("messages".to_string()),
+        ));
+    };
+    let mut system = vec![];
+    let mut messages = vec![];
+    for (idx, entry) in entries.iter().enumerate() {
+        let param = |field: &str| Some(format!("messages[{idx}].{field}"));
+        let role = entry["role"].as_str().unwrap_or_default();
+        match role {
+            "system" | "developer" => {
+                let text = content_blocks(&entry["content"], idx)?
+                    .into_iter()
+                    .filter_map(|block| match block {
+                        ContentBlock::Text(text) => Some(text.text),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                system.push(text);
+            }
+            "user" => {
+                let blocks = content_blocks(&entry["content"], idx)?;
+                push_blocks(&mut messages, blocks, MessageRole::User);
+            }
+            "assistant" => {
+                let mut blocks = content_blocks(&entry["content"], idx)?;
+                for call in entry["tool_calls"].as_array().into_iter().flatten() {
+                    blocks.push(ContentBlock::ToolUse(tool_use(call, idx)?));
+                }
+                push_blocks(&mut messages, blocks, MessageRole::Assistant);
+            }
+            "tool" => {
+                let Some(tool_call_id) = entry["tool_call_id"].as_str() else {
+                    return Err(Error::validation(
+                        "tool message is missing tool_call_id",
+                        param("tool_call_id"),
+                    ));
+                };
+                let text = content_blocks(&entry["content"], idx)?
+                    .into_iter()
+                    .filter_map(|block| match block {
+                        ContentBlock::Text(text) => Some(text.text),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let result = ToolResultBlock::ok(tool_call_id, text);
+                push_blocks(
+                    &mut messages,
+                    vec![ContentBlock::ToolResult(result)],
+                    MessageRole::User,
+                );
+            }
+            _ => {
+                return Err(Error::validation(
+                    format!("unsupported role {:?}", entry["role"]),
+                    param("role"),
+                ));
+            }
+        }
+    }
+    let system = (!system.is_empty()).then(|| SystemPrompt::from_string(system.join("\n\n")));
+    Ok((system, messages))
+}
+
+fn push_blocks(messages: &mut Vec<MessageParam>, blocks: Vec<ContentBlock>, role: MessageRole) {
+    if !blocks.is_empty() {
+        push_or_merge_message(messages, MessageParam::new_with_blocks(blocks, role));
+    }
+}
+
+/// Parses an OpenAI `content` field of the message at `idx`.
+fn content_blocks(content: &Value, idx: usize) -> Result<Vec<ContentBlock>> {
+    let parts = match content {
+        Value::Null => return Ok(vec![]),
+        Value::String(text) if text.is_empty() => return Ok(vec![]),
+        Value::String(text) => return Ok(vec![ContentBlock::Text(TextBlock::new(text))]),
+        Value::Array(parts) => parts,
+        _ => {
+            return Err(Error::validation(
+                "content must be a string, an array of parts, or null",
+                Some(format!("messages[{idx}].content")),
+            ));
+        }
+    };
+    let mut blocks = vec![];
+    for (part_idx, part) in parts.iter().enumerate() {
+        let param = Some(format!("messages[{idx}].content[{part_idx}]"));
+        match part["type"].as_str() {
+            Some("text") => match part["text"].as_str() {
+                Some(text) => blocks.push(ContentBlock::Text(TextBlock::new(text))),
+                None => return Err(Error::validation("text part is missing text", param)),
+            },
+            Some("image_url") => {
+                let url = part["image_url"]["url"]
+                    .as_str()
+                    .or_else(|| part["image_url"].as_str());
+                match url {
+                    Some(url) => blocks.push(ContentBlock::Image(image_block(url, param)?)),
+                    None => return Err(Error::validation("image_url part is missing url", param)),
+                }
+            }
+            _ => {
+                return Err(Error::validation(
+                    format!("unsupported content part type {:?}", part["type"]),
+                    param,
+                ));
+            }
+        }
+    }
+    Ok(blocks)
+}
+
+fn image_block(url: &str, param: Option<String>) -> Result<ImageBlock> {
+    let Some(data_url) = url.strip_prefix("data:") else {
+        return Ok(ImageBlock::new_with_url(UrlImageSource::new(url)));
+    };
+    let Some((media_type, data)) = data_url.split_once(";base64,") else {
+        return Err(Error::validation("data URL must be base64 encoded", param));
+    };
+    let media_type = match media_type {
+        "image/jpeg" => ImageMediaType::Jpeg,
+        "image/png" => ImageMediaType::Png,
+        "image/gif" => ImageMediaType::Gif,
+        "image/webp" => ImageMediaType::Webp,
+        _ => {
+            return Err(Error::validation(
+                format!("unsupported image media type {media_type:?}"),
+                param,
+            ));
+        }
+    };
+    Ok(ImageBlock::new_with_base64(Base64ImageSource::new(
+        data.to_string(),
+        media_type,
+    )))
+}
+
+fn tool_use(call: &Value, idx: usize) -> Result<ToolUseBlock> {
+    let param = Some(format!("messages[{idx}].tool_calls"));
+    let (Some(id), Some(name)) = (call["id"].as_str(), call["function"]["name"].as_str()) else {
+        return Err(Error::validation(
+            "tool call is missing id or function name",
+            param,
+        ));
+    };
+    let input = match &call["function"]["arguments"] {
+        Value::String(arguments) if arguments.trim().is_empty() => json!({}),
+        Value::String(arguments) => serde_json::from_str(arguments).map_err(|err| {
+            Error::validation(
+                format!("tool call {id} has invalid JSON arguments: {err}"),
+                param.clone(),
+            )
+        })?,
+        _ => {
+            return Err(Error::validation(
+                format!("tool call {id} arguments must be a JSON string"),
+                param,
+            ));
+        }
+    };
+    Ok(ToolUseBlock::new(id, name, input))
+}
+
+fn push_user(out: &mut Vec<Value>, blocks: &[ContentBlock]) {
+    let mut parts = vec![];
+    let mut has_image = false;
+    for block in blocks {
+        match block {
+            ContentBlock::ToolResult(result) => out.push(tool_message(result)),
+            ContentBlock::Image(image) => match image_url(image) {
+                Some(url) => {
+                    has_image = true;
+                    parts.push(json!({"type": "image_url", "image_url": {"url": url}}));
+                }
+                None => parts.push(text_part("[image]")),
+            },
+            other => {
+                if let Some(text) = block_text(other) {
+                    parts.push(text_part(&text));
+                }
+            }
+        }
+    }
+    if parts.is_empty() {
+        return;
+    }
+    let content = if has_image {
+        Value::Array(parts)
+    } else {
+        Value::String(join_text_parts(&parts))
+    };
+    out.push(json!({"role": "user", "content": content}));
+}
+
+fn push_assistant(out: &mut Vec<Value>, blocks: &[ContentBlock]) {
+    let mut texts = vec![];
+    let mut tool_calls = vec![];
+    for block in blocks {
+        match block {
+            ContentBlock::ToolUse(tool_use) => tool_calls.push(json!({
+                "id": tool_use.id,
+                "type": "function",
+                "function": {
+                    "name": tool_use.name,
+                    "arguments": tool_use.input.to_string(),
+                },
+            })),
+            other => texts.extend(block_text(other)),
+        }
+    }
+    if texts.is_empty() && tool_calls.is_empty() {
+        return;
+    }
+    let content = if texts.is_empty() {
+        Value::Null
+    } else {
+        Value::String(texts.join("\n"))
+    };
+    let mut message = json!({"role": "assistant", "content": content});
+    if !tool_calls.is_empty() {
+        message["tool_calls"] = Value::Array(tool_calls);
+    }
+    out.push(message);
+}
+
+fn tool_message(result: &ToolResultBlock) -> Value {
+    let content = match &result.content {
+        None => String::new(),
+        Some(ToolResultBlockContent::String(text)) => text.clone(),
+        Some(ToolResultBlockContent::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Content::Text(text) => text.text.clone(),
+                Content::Image(_) => "[image]".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    json!({
+        "role": "tool",
+        "tool_call_id": result.tool_use_id,
+        "content": content,
+    })
+}
+
+/// Returns the text a block contributes to a message, if any.
+fn block_text(block: &ContentBlock) -> Option<String> {
+    match block {
+        ContentBlock::Text(text) => Some(text.text.clone()),
+        ContentBlock::Document(_) => Some("[document]".to_string()),
+        _ => None,
+    }
+}
+
+fn image_url(image: &ImageBlock) -> Option<String> {
+    match &image.source {
+        ImageSource::Url(source) => Some(source.url.clone()),
+        ImageSource::Base64(source) => {
+            let media_type = match source.media_type {
+                ImageMediaType::Jpeg => "image/jpeg",
+                ImageMediaType::Png => "image/png",
+                ImageMediaType::Gif => "image/gif",
+                ImageMediaType::Webp => "image/webp",
+            };
+            Some(format!("data:{media_type};base64,{}", source.data))
+        }
+        ImageSource::File(_) => None,
+    }
+}
+
+fn text_part(text: &str) -> Value {
+    json!({"type": "text", "text": text})
+}
+
+fn join_text_parts(parts: &[Value]) -> String {
+    parts
+        .iter()
+        .filter_map(|part| part["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_only_conversation() {
+        let messages = vec![
+            MessageParam::user("What is 2 + 2?"),
+            MessageParam::assistant("4"),
+            MessageParam::new_with_blocks(
+                vec![
+                    ContentBlock::Text(TextBlock::new("And 3 + 3?")),
+                    ContentBlock::Text(TextBlock::new("Be brief.")),
+                ],
+                MessageRole::User,
+            ),
+        ];
+        assert_eq!(
+            to_openai_messages(&messages),
+            json!([
+                {"role": "user", "content": "What is 2 + 2?"},
+                {"role": "assistant", "content": "4"},
+                {"role": "user", "content": "And 3 + 3?\nBe brief."},
+            ])
+        );
+    }
+
+    #[test]
+    fn tool_call_turn() {
+        let messages = vec![
+            MessageParam::user("Weather in Paris?"),
+            MessageParam::new_with_blocks(
+                vec![
+                    ContentBlock::Text(TextBlock::new("Checking.")),
+                    ContentBlock::ToolUse(ToolUseBlock::new(
+                        "toolu_1",
+                        "get_weather",
+                        json!({"city": "Paris"}),
+                    )),
+                ],
+                MessageRole::Assistant,
+            ),
+            MessageParam::new_with_blocks(
+                vec![
+                    ContentBlock::ToolResult(ToolResultBlock::ok("toolu_1", "18C and sunny")),
+                    ContentBlock::Text(TextBlock::new("Thanks!")),
+                ],
+                MessageRole::User,
+            ),
+        ];
+        assert_eq!(
+            to_openai_messages(&messages),
+            json!([
+                {"role": "user", "content": "Weather in Paris?"},
+                {
+                    "role": "assistant",
+                    "content": "Checking.",
+                    "tool_calls": [{
+                        "id": "toolu_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"},
+                    }],
+                },
+                {"role": "tool", "tool_call_id": "toolu_1", "content": "18C and sunny"},
+                {"role": "user", "content": "Thanks!"},
+            ])
+        );
+    }
+
+    #[test]
+    fn images_become_image_url_parts() {
+        let messages = vec![MessageParam::new_with_blocks(
+            vec![
+                ContentBlock::Text(TextBlock::new("Describe this.")),
+                ContentBlock::Image(ImageBlock::new_with_url(UrlImageSource::new(
+                    "https://example.com/cat.png",
+                ))),
+            ],
+            MessageRole::User,
+        )];
+        assert_eq!(
+            to_openai_messages(&messages),
+            json!([{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "Describe this."},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}},
+                ],
+            }])
+        );
+    }
+
+    #[test]
+    fn import_multi_turn_transcript_with_tool_call() {
+        let transcript = json!([
+            {"role": "system", "content": "You are terse."},
+            {"role": "user", "content": "Weather in Paris?"},
+            {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"},
+                }],
+            },
+            {"role": "tool", "tool_call_id": "call_1", "content": "18C and sunny"},
+            {"role": "user", "content": [{"type": "text", "text": "Thanks!"}]},
+            {"role": "assistant", "content": "Anytime.", "refusal": null},
+        ]);
+        let (system, messages) = from_openai_messages(&transcript).unwrap();
+        assert_eq!(
+            system,
+            Some(SystemPrompt::from_string("You are terse.".to_string()))
+        );
+        assert_eq!(
+            messages,
+            vec![
+                MessageParam::new_with_blocks(
+                    vec![ContentBlock::Text(TextBlock::new("Weather in Paris?"))],
+                    MessageRole::User,
+                ),
+                MessageParam::new_with_blocks(
+                    vec![ContentBlock::ToolUse(ToolUseBlock::new(
+                        "call_1",
+                        "get_weather",
+                        json!({"city": "Paris"}),
+                    ))],
+                    MessageRole::Assistant,
+                ),
+                MessageParam::new_with_blocks(
+                    vec![
+                        ContentBlock::ToolResult(ToolResultBlock::ok("call_1", "18C and sunny")),
+                        ContentBlock::Text(TextBlock::new("Thanks!")),
+                    ],
+                    MessageRole::User,
+                ),
+                MessageParam::new_with_blocks(
+                    vec![ContentBlock::Text(TextBlock::new("Anytime."))],
+                    MessageRole::Assistant,
+                ),
+            ]
+        );
+        // Round-trips back to the same OpenAI messages, minus the system prompt and refusal.
+        let exported = to_openai_messages(&messages);
+        assert_eq!(exported[1]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(exported[2], transcript[3]);
+    }
+
+    #[test]
+    fn import_data_url_image() {
+        let transcript = json!([{
+            "role": "user",
+            "content": [{"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}],
+        }]);
+        let (system, messages) = from_openai_messages(&transcript).unwrap();
+        assert!(system.is_none());
+        assert_eq!(
+            messages,
+            vec![MessageParam::new_with_blocks(
+                vec![ContentBlock::Image(ImageBlock::new_with_base64(
+                    Base64ImageSource::new("AAAA".to_string(), ImageMediaType::Png),
+                ))],
+                MessageRole::User,
+            )]
+        );
+    }
+
+    #[test]
+    fn import_rejects_unknown_roles_and_bad_arguments() {
+        let err = from_openai_messages(&json!([{"role": "function", "content": "x"}])).unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("messages[0].role"), "{err}");
+
+        let err = from_openai_messages(&json!([{
+            "role": "assistant",
+            "tool_calls": [{"id": "c", "function": {"name": "f", "arguments": "{not json"}}],
+        }]))
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid JSON arguments"), "{err}");
+    }
+}
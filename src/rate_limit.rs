@@ -0,0 +1,124 @@
+//! Client-side rate limiting.
+//!
+//! The Messages API limits both requests per minute and tokens per minute.  A [`TokenBucket`]
+//! tracks both so the client can wait out a limit before sending rather than being rejected
+//! with a 429.
+//!
+//! Each limit is a bucket that holds up to one minute's allowance and refills continuously.  A
+//! request debits its cost immediately, even if that drives the balance negative, and is told
+//! how long to wait for the balance to recover.  Debiting up front means concurrent callers
+//! queue behind one another instead of all waking at the same moment.
+
+use std::time::{Duration, Instant};
+
+/////////////////////////////////////////////// Bucket /////////////////////////////////////////////
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    per_sec: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn per_minute(limit: u32, now: Instant) -> Self {
+        let capacity = f64::from(limit);
+        Self {
+            capacity,
+            per_sec: capacity / 60.0,
+            available: capacity,
+            updated: now,
+        }
+    }
+
+    fn reserve(&mut self, amount: f64, now: Instant) -> Duration {
+        if now > self.updated {
+            let elapsed = now.duration_since(self.updated).as_secs_f64();
+            self.available = (self.available + elapsed * self.per_sec).min(self.capacity);
+            self.updated = now;
+        }
+        // Anything larger than a full bucket could never be satisfied; let it through once the
+        // bucket is full instead.
+        self.available -= amount.min(self.capacity);
+        if self.available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.available / self.per_sec)
+        }
+    }
+}
+
+/////////////////////////////////////////// TokenBucket ////////////////////////////////////////////
+
+/// Request and token allowances for a client, refilled continuously over each minute.
+#[derive(Debug)]
+pub struct TokenBucket {
+    requests: Bucket,
+    tokens: Bucket,
+}
+
+impl TokenBucket {
+    /// Creates a bucket that starts full at `now`.
+    ///
+    /// Both limits must be greater than zero.
+    pub fn new(requests_per_min: u32, tokens_per_min: u32, now: Instant) -> Self {
+        Self {
+            requests: Bucket::per_minute(requests_per_min, now),
+            tokens: Bucket::per_minute(tokens_per_min, now),
+        }
+    }
+
+    /// Debits one request and `tokens` tokens at `now`, returning how long to wait before
+    /// sending.
+    pub fn reserve(&mut self, tokens: u32, now: Instant) -> Duration {
+        let requests = self.requests.reserve(1.0, now);
+        let tokens = self.tokens.reserve(f64::from(tokens), now);
+        requests.max(tokens)
+    }
+}
+
+/////////////////////////////////////////////// tests //////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_per_minute_throttles_after_burst() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(60, 1_000_000, start);
+        for _ in 0..60 {
+            assert_eq!(bucket.reserve(1, start), Duration::ZERO);
+        }
+        // One request refills every second; queued requests wait one more second each.
+        assert_eq!(bucket.reserve(1, start), Duration::from_secs(1));
+        assert_eq!(bucket.reserve(1, start), Duration::from_secs(2));
+        // Three seconds later both queued requests are paid off and one more has refilled.
+        let later = start + Duration::from_secs(3);
+        assert_eq!(bucket.reserve(1, later), Duration::ZERO);
+        assert_eq!(bucket.reserve(1, later), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn tokens_per_minute_throttles_by_estimate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1_000, 6_000, start);
+        assert_eq!(bucket.reserve(4_000, start), Duration::ZERO);
+        // 100 tokens refill per second, so a 2,000 token shortfall waits 20 seconds.
+        assert_eq!(bucket.reserve(4_000, start), Duration::from_secs(20));
+        // Refill never exceeds one minute's allowance.
+        let idle = start + Duration::from_secs(600);
+        assert_eq!(bucket.reserve(6_000, idle), Duration::ZERO);
+        assert_eq!(bucket.reserve(100, idle), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn oversized_request_waits_for_full_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1_000, 600, start);
+        assert_eq!(bucket.reserve(100, start), Duration::ZERO);
+        // A request larger than the whole allowance only waits for the bucket to refill.
+        assert_eq!(bucket.reserve(10_000, start), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,232 @@
+//! Recording and replaying Messages API exchanges.
+//!
+//! A [`Recorder`] writes every request sent and message received as one JSON line, and a
+//! [`Replay`] serves a recording back in order so that a real session can be turned into a
+//! deterministic regression test.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ClientLogger, Error, Message, MessageCreateParams, MessageStreamEvent, Result};
+
+/// A single request and the message the API returned for it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordedExchange {
+    /// The parameters that were sent.
+    pub request: MessageCreateParams,
+
+    /// The message that was received.
+    pub response: Message,
+}
+
+/// Writes [`RecordedExchange`]s to a writer as JSON lines.
+///
+/// Use [`Anthropic::send_with_recorder`](crate::Anthropic::send_with_recorder) for
+/// non-streaming requests, or pass [`Recorder::logger`] to
+/// [`Anthropic::stream_with_logger`](crate::Anthropic::stream_with_logger) to record the
+/// message reconstructed from a stream.
+pub struct Recorder<W: Write + Send> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> Recorder<W> {
+    /// Creates a recorder that appends to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Writes one exchange as a line of JSON and flushes the writer.
+    pub fn record(&self, request: &MessageCreateParams, response: &Message) -> Result<()> {
+        let exchange = RecordedExchange {
+            request: request.clone(),
+            response: response.clone(),
+        };
+        let mut line = serde_json::to_string(&exchange)?;
+        line.push('\n');
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| Error::unknown("recorder writer lock poisoned"))?;
+        writer
+            .write_all(line.as_bytes())
+            .and_then(|()| writer.flush())
+            .map_err(|err| Error::io("failed to write recorded exchange", err))
+    }
+
+    /// Returns a [`ClientLogger`] that records `request` with the message a stream produces.
+    ///
+    /// Write errors are dropped, as [`ClientLogger`] cannot report them.
+    pub fn logger<'a>(&'a self, request: &'a MessageCreateParams) -> RecordingLogger<'a, W> {
+        RecordingLogger {
+            recorder: self,
+            request,
+        }
+    }
+
+    /// Consumes the recorder, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A [`ClientLogger`] that records a single request, created by [`Recorder::logger`].
+pub struct RecordingLogger<'a, W: Write + Send> {
+    recorder: &'a Recorder<W>,
+    request: &'a MessageCreateParams,
+}
+
+impl<W: Write + Send> ClientLogger for RecordingLogger<'_, W> {
+    fn log_response(&self, message: &Message) {
+        let _ = self.recorder.record(self.request, message);
+    }
+
+    fn log_stream_event(&self, _event: &MessageStreamEvent) {}
+
+    fn log_stream_message(&self, message: &Message) {
+        let _ = self.recorder.record(self.request, message);
+    }
+}
+
+/// Serves recorded responses in the order they were recorded.
+///
+/// Each call to [`Replay::send`] must present the same request that was recorded at that
+/// position; a request that differs, or one made after the recording is used up, is an error.
+/// This keeps a replayed session from silently drifting away from the recording.
+#[derive(Debug, Default)]
+pub struct Replay {
+    exchanges: Mutex<VecDeque<RecordedExchange>>,
+    served: Mutex<usize>,
+}
+
+impl Replay {
+    /// Creates a replay from recorded exchanges.
+    pub fn new(exchanges: impl IntoIterator<Item = RecordedExchange>) -> Self {
+        Self {
+            exchanges: Mutex::new(exchanges.into_iter().collect()),
+            served: Mutex::new(0),
+        }
+    }
+
+    /// Parses a recording written by [`Recorder`].
+    ///
+    /// Blank lines are ignored.
+    pub fn from_jsonl(jsonl: &str) -> Result<Self> {
+        let exchanges = jsonl
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                serde_json::from_str(line).map_err(|err| {
+                    Error::serialization(
+                        format!("invalid recording on line {}: {err}", idx + 1),
+                        Some(Box::new(err)),
+                    )
+                })
+            })
+            .collect::<Result<Vec<RecordedExchange>>>()?;
+        Ok(Self::new(exchanges))
+    }
+
+    /// Returns the next recorded response, checking that `params` matches its request.
+    pub fn send(&self, params: &MessageCreateParams) -> Result<Message> {
+        let mut exchanges = self
+            .exchanges
+            .lock()
+            .map_err(|_| Error::unknown("replay lock poisoned"))?;
+        let mut served = self
+            .served
+            .lock()
+            .map_err(|_| Error::unknown("replay lock poisoned"))?;
+        let Some(exchange) = exchanges.front() else {
+            return Err(Error::validation(
+                format!("replay exhausted after {} exchanges", *served),
+                None,
+            ));
+        };
+        if serde_json::to_value(&exchange.request)? != serde_json::to_value(params)? {
+            return Err(Error::validation(
+                format!("request {} does not match the recording", *served),
+                Some("params".to_string()),
+            ));
+        }
+        *served += 1;
+        let exchange = exchanges.pop_front().expect("front was checked above");
+        Ok(exchange.response)
+    }
+
+    /// Returns the number of recorded exchanges not yet served.
+    pub fn remaining(&self) -> usize {
+        self.exchanges
+            .lock()
+            .map(|exchanges| exchanges.len())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentBlock, KnownModel, MessageParam, Model, TextBlock, Usage};
+
+    fn params(text: &str) -> MessageCreateParams {
+        MessageCreateParams::new(
+            64,
+            vec![MessageParam::user(text)],
+            Model::Known(KnownModel::ClaudeHaiku45),
+        )
+    }
+
+    fn message(id: &str, text: &str) -> Message {
+        Message::new(
+            id.to_string(),
+            vec![ContentBlock::Text(TextBlock::new(text))],
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(3, 1),
+        )
+    }
+
+    #[test]
+    fn replay_serves_recording_in_order() {
+        let recorder = Recorder::new(Vec::new());
+        recorder
+            .record(&params("one"), &message("msg_1", "first"))
+            .unwrap();
+        recorder
+            .logger(&params("two"))
+            .log_stream_message(&message("msg_2", "second"));
+        let jsonl = String::from_utf8(recorder.into_inner()).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+
+        let replay = Replay::from_jsonl(&jsonl).unwrap();
+        assert_eq!(replay.remaining(), 2);
+        assert_eq!(replay.send(&params("one")).unwrap().id, "msg_1");
+        assert_eq!(replay.send(&params("two")).unwrap().id, "msg_2");
+        let err = replay.send(&params("three")).unwrap_err();
+        assert!(err.to_string().contains("exhausted after 2"), "{err}");
+    }
+
+    #[test]
+    fn replay_rejects_mismatched_request() {
+        let replay = Replay::new([RecordedExchange {
+            request: params("one"),
+            response: message("msg_1", "first"),
+        }]);
+        let err = replay.send(&params("other")).unwrap_err();
+        assert!(err.is_validation());
+        assert_eq!(replay.remaining(), 1);
+        assert!(replay.send(&params("one")).is_ok());
+    }
+
+    #[test]
+    fn from_jsonl_reports_line_numbers() {
+        let err = Replay::from_jsonl("\n{}\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+    }
+}
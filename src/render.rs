@@ -3,11 +3,15 @@
 //! This module provides renderer traits and plain-text implementations for
 //! both chat output and agent streaming output.
 
+use std::collections::VecDeque;
 use std::io::{self, Stdout, Write};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use tokio::sync::mpsc;
+
 use crate::StopReason;
+use crate::types::MessageDeltaUsage;
 
 /// ANSI escape code for dim text (used for thinking blocks).
 const ANSI_DIM: &str = "\x1b[2m";
@@ -121,6 +125,17 @@ pub trait Renderer: Send {
         _ = stop_reason;
     }
 
+    /// Called when each streamed response begins (on its `message_start` event).
+    fn start_message(&mut self, context: &dyn StreamContext) {
+        _ = context;
+    }
+
+    /// Called on each `message_delta` event with the response's cumulative usage.
+    fn update_usage(&mut self, context: &dyn StreamContext, usage: &MessageDeltaUsage) {
+        _ = context;
+        _ = usage;
+    }
+
     /// Print a chunk of regular response text.
     ///
     /// This is called incrementally as tokens are streamed from the API.
@@ -432,6 +447,237 @@ impl Renderer for PlainTextRenderer {
     }
 }
 
+/////////////////////////////////////////// Channel ////////////////////////////////////////////
+
+/// A structured rendering event produced by [`ChannelRenderer`].
+///
+/// Each variant corresponds to one [`Renderer`] callback, carrying its arguments as owned data
+/// so that a GUI can consume the stream without parsing terminal output.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderEvent {
+    /// A stream began.
+    AgentStart {
+        /// The stream's label, if any.
+        label: Option<String>,
+        /// The stream's nesting depth.
+        depth: usize,
+    },
+    /// A stream finished.
+    AgentFinish {
+        /// The stream's label, if any.
+        label: Option<String>,
+        /// The stream's nesting depth.
+        depth: usize,
+        /// Why the model stopped, if known.
+        stop_reason: Option<StopReason>,
+    },
+    /// A chunk of response text.
+    Text(String),
+    /// A chunk of thinking text.
+    Thinking(String),
+    /// An error message.
+    Error(String),
+    /// An informational message.
+    Info(String),
+    /// The model started a tool call.
+    ToolUseStart {
+        /// The name of the tool being called.
+        name: String,
+        /// The id of the tool use block.
+        id: String,
+    },
+    /// A chunk of tool input JSON.
+    ToolInput(String),
+    /// The current tool call's input is complete.
+    ToolUseFinish,
+    /// A tool result started.
+    ToolResultStart {
+        /// The id of the tool use this result answers.
+        tool_use_id: String,
+        /// Whether the result is an error.
+        is_error: bool,
+    },
+    /// Text content of the current tool result.
+    ToolResultText(String),
+    /// The current tool result is complete.
+    ToolResultFinish,
+    /// A response is complete.
+    ResponseFinish,
+    /// The stream was interrupted.
+    Interrupted,
+}
+
+/// Renderer that forwards every callback as a [`RenderEvent`] over a bounded channel.
+///
+/// Renderer callbacks are synchronous, so events are sent with `try_send` rather than blocking
+/// the stream on a slow consumer.  While the channel is full, events wait in a backlog in which
+/// consecutive text, thinking, tool input and tool result chunks are merged into one event, so
+/// a slow consumer sees fewer, larger chunks instead of stalling the stream.  Call
+/// [`ChannelRenderer::flush`] once the stream ends to deliver whatever is still waiting.  Once
+/// the receiver is dropped the renderer discards its backlog and asks the stream to stop via
+/// [`Renderer::should_interrupt`].
+pub struct ChannelRenderer {
+    events: mpsc::Sender<RenderEvent>,
+    backlog: VecDeque<RenderEvent>,
+}
+
+impl ChannelRenderer {
+    /// Creates a renderer that sends its events to `events`.
+    pub fn new(events: mpsc::Sender<RenderEvent>) -> Self {
+        Self {
+            events,
+            backlog: VecDeque::new(),
+        }
+    }
+
+    /// Creates a renderer along with the receiver for its events, buffering up to `capacity`
+    /// events in the channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn channel(capacity: usize) -> (Self, mpsc::Receiver<RenderEvent>) {
+        let (tx, rx) = mpsc::channel(capacity);
+        (Self::new(tx), rx)
+    }
+
+    /// Waits for the consumer to take every event still held back by a full channel.
+    pub async fn flush(&mut self) {
+        while let Some(event) = self.backlog.pop_front() {
+            if self.events.send(event).await.is_err() {
+                self.backlog.clear();
+            }
+        }
+    }
+
+    fn send(&mut self, event: RenderEvent) {
+        self.drain_backlog();
+        if self.backlog.is_empty() {
+            match self.events.try_send(event) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(event)) => self.backlog.push_back(event),
+                // A closed channel is reported through should_interrupt.
+                Err(mpsc::error::TrySendError::Closed(_)) => {}
+            }
+        } else if let Some(last) = self.backlog.back_mut()
+            && let Some(event) = coalesce(last, event)
+        {
+            self.backlog.push_back(event);
+        }
+    }
+
+    fn drain_backlog(&mut self) {
+        while let Some(event) = self.backlog.pop_front() {
+            match self.events.try_send(event) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(event)) => {
+                    self.backlog.push_front(event);
+                    return;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    self.backlog.clear();
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Appends `event` to `last` when both are chunks of the same stream, returning `event` when
+/// they cannot be merged.
+fn coalesce(last: &mut RenderEvent, event: RenderEvent) -> Option<RenderEvent> {
+    match (last, event) {
+        (RenderEvent::Text(last), RenderEvent::Text(text))
+        | (RenderEvent::Thinking(last), RenderEvent::Thinking(text))
+        | (RenderEvent::ToolInput(last), RenderEvent::ToolInput(text))
+        | (RenderEvent::ToolResultText(last), RenderEvent::ToolResultText(text)) => {
+            last.push_str(&text);
+            None
+        }
+        (_, event) => Some(event),
+    }
+}
+
+impl Renderer for ChannelRenderer {
+    fn start_agent(&mut self, context: &dyn StreamContext) {
+        self.send(RenderEvent::AgentStart {
+            label: context.label().map(str::to_string),
+            depth: context.depth(),
+        });
+    }
+
+    fn finish_agent(&mut self, context: &dyn StreamContext, stop_reason: Option<&StopReason>) {
+        self.send(RenderEvent::AgentFinish {
+            label: context.label().map(str::to_string),
+            depth: context.depth(),
+            stop_reason: stop_reason.copied(),
+        });
+    }
+
+    fn print_text(&mut self, _context: &dyn StreamContext, text: &str) {
+        self.send(RenderEvent::Text(text.to_string()));
+    }
+
+    fn print_thinking(&mut self, _context: &dyn StreamContext, text: &str) {
+        self.send(RenderEvent::Thinking(text.to_string()));
+    }
+
+    fn print_error(&mut self, _context: &dyn StreamContext, error: &str) {
+        self.send(RenderEvent::Error(error.to_string()));
+    }
+
+    fn print_info(&mut self, _context: &dyn StreamContext, info: &str) {
+        self.send(RenderEvent::Info(info.to_string()));
+    }
+
+    fn start_tool_use(&mut self, _context: &dyn StreamContext, name: &str, id: &str) {
+        self.send(RenderEvent::ToolUseStart {
+            name: name.to_string(),
+            id: id.to_string(),
+        });
+    }
+
+    fn print_tool_input(&mut self, _context: &dyn StreamContext, partial_json: &str) {
+        self.send(RenderEvent::ToolInput(partial_json.to_string()));
+    }
+
+    fn finish_tool_use(&mut self, _context: &dyn StreamContext) {
+        self.send(RenderEvent::ToolUseFinish);
+    }
+
+    fn start_tool_result(
+        &mut self,
+        _context: &dyn StreamContext,
+        tool_use_id: &str,
+        is_error: bool,
+    ) {
+        self.send(RenderEvent::ToolResultStart {
+            tool_use_id: tool_use_id.to_string(),
+            is_error,
+        });
+    }
+
+    fn print_tool_result_text(&mut self, _context: &dyn StreamContext, text: &str) {
+        self.send(RenderEvent::ToolResultText(text.to_string()));
+    }
+
+    fn finish_tool_result(&mut self, _context: &dyn StreamContext) {
+        self.send(RenderEvent::ToolResultFinish);
+    }
+
+    fn finish_response(&mut self, _context: &dyn StreamContext) {
+        self.send(RenderEvent::ResponseFinish);
+    }
+
+    fn print_interrupted(&mut self, _context: &dyn StreamContext) {
+        self.send(RenderEvent::Interrupted);
+    }
+
+    fn should_interrupt(&self) -> bool {
+        self.events.is_closed()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -447,4 +693,139 @@ mod tests {
         let renderer = PlainTextRenderer::with_color(false);
         assert!(!renderer.use_color);
     }
+
+    #[test]
+    fn channel_renderer_forwards_events_in_order() {
+        let (mut renderer, mut events) = ChannelRenderer::channel(16);
+        let context = AgentStreamContext::root("Main");
+        renderer.start_agent(&context);
+        renderer.print_thinking(&context, "hmm");
+        renderer.print_text(&context, "Let me check.");
+        renderer.start_tool_use(&context, "get_weather", "toolu_1");
+        renderer.print_tool_input(&context, r#"{"city":"#);
+        renderer.print_tool_input(&context, r#""Paris"}"#);
+        renderer.finish_tool_use(&context);
+        renderer.start_tool_result(&context, "toolu_1", false);
+        renderer.print_tool_result_text(&context, "sunny");
+        renderer.finish_tool_result(&context);
+        renderer.finish_response(&context);
+        renderer.finish_agent(&context, Some(&StopReason::EndTurn));
+        drop(renderer);
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(
+            received,
+            vec![
+                RenderEvent::AgentStart {
+                    label: Some("Main".to_string()),
+                    depth: 0,
+                },
+                RenderEvent::Thinking("hmm".to_string()),
+                RenderEvent::Text("Let me check.".to_string()),
+                RenderEvent::ToolUseStart {
+                    name: "get_weather".to_string(),
+                    id: "toolu_1".to_string(),
+                },
+                RenderEvent::ToolInput(r#"{"city":"#.to_string()),
+                RenderEvent::ToolInput(r#""Paris"}"#.to_string()),
+                RenderEvent::ToolUseFinish,
+                RenderEvent::ToolResultStart {
+                    tool_use_id: "toolu_1".to_string(),
+                    is_error: false,
+                },
+                RenderEvent::ToolResultText("sunny".to_string()),
+                RenderEvent::ToolResultFinish,
+                RenderEvent::ResponseFinish,
+                RenderEvent::AgentFinish {
+                    label: Some("Main".to_string()),
+                    depth: 0,
+                    stop_reason: Some(StopReason::EndTurn),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn channel_renderer_interrupts_when_receiver_is_dropped() {
+        let (renderer, events) = ChannelRenderer::channel(1);
+        assert!(!renderer.should_interrupt());
+        drop(events);
+        assert!(renderer.should_interrupt());
+    }
+
+    #[tokio::test]
+    async fn channel_renderer_coalesces_a_streamed_turn_when_full() {
+        use serde_json::json;
+
+        use crate::test_support::{MockServer, Reply, sse_events};
+        use crate::{Agent, Anthropic, Budget, MessageParam};
+
+        let text_delta = |text: &str| {
+            json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "text_delta", "text": text}})
+        };
+        let server = MockServer::start([Reply::sse(sse_events(&[
+            json!({"type": "message_start", "message": {
+                "id": "msg_1", "type": "message", "role": "assistant",
+                "model": "claude-haiku-4-5", "content": [], "stop_reason": null,
+                "usage": {"input_tokens": 10, "output_tokens": 1}
+            }}),
+            json!({"type": "content_block_start", "index": 0,
+                "content_block": {"type": "text", "text": ""}}),
+            text_delta("Hel"),
+            text_delta("lo, "),
+            text_delta("wor"),
+            text_delta("ld"),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "message_delta",
+                "delta": {"stop_reason": "end_turn", "stop_sequence": null},
+                "usage": {"output_tokens": 4}}),
+            json!({"type": "message_stop"}),
+        ]))]);
+        let client = Anthropic::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_base_url(server.url())
+            .with_max_retries(0);
+        let budget = Arc::new(Budget::new_flat_rate(u64::MAX, 1));
+        let mut messages = vec![MessageParam::user("hi")];
+
+        // Nobody reads while the turn streams, so everything past the second event backs up.
+        let (mut renderer, mut events) = ChannelRenderer::channel(2);
+        ().take_turn_streaming_root(&client, &mut messages, &budget, &mut renderer)
+            .await
+            .unwrap();
+        assert!(!renderer.should_interrupt());
+
+        let flush = async move {
+            renderer.flush().await;
+        };
+        let collect = async {
+            let mut received = Vec::new();
+            while let Some(event) = events.recv().await {
+                received.push(event);
+            }
+            received
+        };
+        let ((), received) = tokio::join!(flush, collect);
+        assert_eq!(
+            received,
+            vec![
+                RenderEvent::AgentStart {
+                    label: Some("()".to_string()),
+                    depth: 0,
+                },
+                RenderEvent::Text("Hel".to_string()),
+                RenderEvent::Text("lo, world".to_string()),
+                RenderEvent::ResponseFinish,
+                RenderEvent::AgentFinish {
+                    label: Some("()".to_string()),
+                    depth: 0,
+                    stop_reason: Some(StopReason::EndTurn),
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,59 @@
+//! Client-side caching of message responses.
+//!
+//! A [`ResponseCache`] registered with
+//! [`Anthropic::with_response_cache`](crate::Anthropic::with_response_cache) is consulted before
+//! every [`send`](crate::Anthropic::send).  Requests are keyed by
+//! [`MessageCreateParams::cache_key`](crate::MessageCreateParams::cache_key), so identical
+//! prompts are answered once and served locally afterwards.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::Message;
+
+/// Stores messages by request key.
+///
+/// Implementations decide eviction and persistence; the client only reads and writes.
+pub trait ResponseCache: Send + Sync {
+    /// Returns the message stored under `key`, if any.
+    fn get(&self, key: &str) -> Option<Message>;
+
+    /// Stores `message` under `key`.
+    fn put(&self, key: &str, message: &Message);
+}
+
+/// An unbounded in-memory [`ResponseCache`].
+#[derive(Debug, Default)]
+pub struct MemoryResponseCache {
+    messages: Mutex<HashMap<String, Message>>,
+}
+
+impl MemoryResponseCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached messages.
+    pub fn len(&self) -> usize {
+        self.messages.lock().unwrap().len()
+    }
+
+    /// Returns true if nothing has been cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCache for MemoryResponseCache {
+    fn get(&self, key: &str) -> Option<Message> {
+        self.messages.lock().unwrap().get(key).cloned()
+    }
+
+    fn put(&self, key: &str, message: &Message) {
+        self.messages
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), message.clone());
+    }
+}
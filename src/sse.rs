@@ -29,6 +29,8 @@ struct SseState {
     buffer: String,
     last_activity: Instant,
     total_bytes_processed: usize,
+    max_total_bytes: usize,
+    exceeded: bool,
     start: Instant,
     first_byte: Option<Instant>,
 }
@@ -46,6 +48,18 @@ struct SseState {
 /// - Graceful error recovery
 /// - UTF-8 validation with partial byte handling
 pub fn process_sse<S>(byte_stream: S) -> impl Stream<Item = Result<MessageStreamEvent>>
+where
+    S: Stream<Item = std::result::Result<Bytes, reqwest::Error>> + Unpin + 'static,
+{
+    process_sse_limited(byte_stream, usize::MAX)
+}
+
+/// Like [`process_sse`], but ends with [`Error::ResponseTooLarge`] once more than
+/// `max_total_bytes` have been read from `byte_stream`.
+pub fn process_sse_limited<S>(
+    byte_stream: S,
+    max_total_bytes: usize,
+) -> impl Stream<Item = Result<MessageStreamEvent>>
 where
     S: Stream<Item = std::result::Result<Bytes, reqwest::Error>> + Unpin + 'static,
 {
@@ -60,12 +74,18 @@ where
         buffer: String::new(),
         last_activity: Instant::now(),
         total_bytes_processed: 0,
+        max_total_bytes,
+        exceeded: false,
         start: Instant::now(),
         first_byte: None,
     };
 
     stream::unfold((stream, state), move |(mut stream, mut state)| async move {
         loop {
+            if state.exceeded {
+                return None;
+            }
+
             // Check for timeout
             if state.last_activity.elapsed() > CHUNK_TIMEOUT {
                 return Some((
@@ -113,6 +133,12 @@ where
                     state.last_activity = Instant::now();
                     state.total_bytes_processed += bytes.len();
                     STREAM_BYTES.count(bytes.len() as u64);
+                    if state.total_bytes_processed > state.max_total_bytes {
+                        STREAM_ERRORS.click();
+                        state.exceeded = true;
+                        let limit = state.max_total_bytes;
+                        return Some((Err(Error::response_too_large(limit)), (stream, state)));
+                    }
                     if state.first_byte.is_none() {
                         let now = Instant::now();
                         state.first_byte = Some(now);
@@ -315,13 +341,15 @@ fn parse_event_type(
         }
 
         _ => {
-            // Handle unknown event types gracefully - log but don't fail the stream
-            if event_type.starts_with("event:") {
+            // Surface unknown event types without failing the stream
+            if let Some(name) = event_type.strip_prefix("event:") {
+                let raw = serde_json::from_str(event_data)
+                    .unwrap_or_else(|_| serde_json::Value::String(event_data.to_string()));
                 Some((
-                    Err(Error::serialization(
-                        format!("Unknown SSE event type: {}", event_type.trim()),
-                        None,
-                    )),
+                    Ok(MessageStreamEvent::Unknown {
+                        r#type: name.trim().to_string(),
+                        raw,
+                    }),
                     rest,
                 ))
             } else {
@@ -398,16 +426,27 @@ mod tests {
 
     #[tokio::test]
     async fn handle_unknown_event_type() {
-        let data = b"event: unknown_event\ndata: {}\n\n";
-        let stream = Box::pin(stream::once(async { Ok(Bytes::from(&data[..])) }));
+        let data = concat!(
+            "event: unknown_event\n",
+            "data: {\"type\": \"unknown_event\", \"n\": 1}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\": \"message_stop\"}\n\n",
+        );
+        let stream = Box::pin(stream::once(async { Ok(Bytes::from(data)) }));
 
         let mut sse_stream = Box::pin(process_sse(stream));
-        let event = sse_stream.next().await.unwrap();
+        let event = sse_stream.next().await.unwrap().unwrap();
+        assert_eq!(
+            event,
+            MessageStreamEvent::Unknown {
+                r#type: "unknown_event".to_string(),
+                raw: serde_json::json!({"type": "unknown_event", "n": 1}),
+            }
+        );
 
-        assert!(event.is_err());
-        if let Err(e) = event {
-            assert!(e.to_string().contains("Unknown SSE event type"));
-        }
+        // The stream continues past the unknown event.
+        let event = sse_stream.next().await.unwrap();
+        assert!(matches!(event, Ok(MessageStreamEvent::MessageStop(_))));
     }
 
     #[tokio::test]
@@ -0,0 +1,278 @@
+//! A scripted HTTP server shared by the crate's tests.
+//!
+//! [`MockServer::start`] binds a local port and answers one connection per [`Reply`], in order,
+//! recording every [`Request`] it reads.  The server runs on its own thread and runtime so that
+//! async and blocking tests can use it alike.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+
+/////////////////////////////////////////////// Reply //////////////////////////////////////////////
+
+/// One scripted response.
+pub(crate) struct Reply {
+    status: &'static str,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+    stall: bool,
+    silent: bool,
+}
+
+impl Reply {
+    /// A `200 OK` with a JSON body.
+    pub(crate) fn json(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            headers: vec![("content-type".into(), "application/json".into())],
+            body: body.into(),
+            delay: Duration::ZERO,
+            stall: false,
+            silent: false,
+        }
+    }
+
+    /// A `200 OK` server-sent event stream.
+    pub(crate) fn sse(body: impl Into<Vec<u8>>) -> Self {
+        let mut reply = Self::json(body);
+        reply.headers = vec![("content-type".into(), "text/event-stream".into())];
+        reply
+    }
+
+    /// Never answers; holds the connection until the client closes it.
+    pub(crate) fn silent() -> Self {
+        let mut reply = Self::json(Vec::new());
+        reply.silent = true;
+        reply
+    }
+
+    /// Replaces the status line, e.g. `"503 Service Unavailable"`.
+    pub(crate) fn with_status(mut self, status: &'static str) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds a response header.
+    #[cfg(feature = "compression")]
+    pub(crate) fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Waits `delay` after reading the request before answering.
+    pub(crate) fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sends the body without a content length and then holds the connection open until the
+    /// client closes it.
+    pub(crate) fn stalled(mut self) -> Self {
+        self.stall = true;
+        self
+    }
+}
+
+/// Renders `events` as a server-sent event stream, naming each event after its `type`.
+pub(crate) fn sse_events<'a>(events: impl IntoIterator<Item = &'a serde_json::Value>) -> String {
+    events
+        .into_iter()
+        .map(|event| {
+            format!(
+                "event: {}\ndata: {event}\n\n",
+                event["type"].as_str().unwrap()
+            )
+        })
+        .collect()
+}
+
+////////////////////////////////////////////// Request /////////////////////////////////////////////
+
+/// One request as the server read it.
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    head: String,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// The request line, e.g. `POST /v1/messages HTTP/1.1`.
+    pub(crate) fn request_line(&self) -> &str {
+        self.head.lines().next().unwrap_or_default()
+    }
+
+    /// The first value of header `name`, matched case-insensitively.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers(name).into_iter().next()
+    }
+
+    /// Every value of header `name`, in the order sent.
+    pub(crate) fn headers(&self, name: &str) -> Vec<&str> {
+        self.head
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name).then(|| value.trim())
+            })
+            .collect()
+    }
+
+    /// The raw body.
+    pub(crate) fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The body parsed as JSON.
+    pub(crate) fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+///////////////////////////////////////////// MockServer ///////////////////////////////////////////
+
+/// A local HTTP server that plays back scripted replies.
+pub(crate) struct MockServer {
+    url: String,
+    closed: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+    requests: oneshot::Receiver<Vec<Request>>,
+}
+
+impl MockServer {
+    /// Starts serving `replies`, one per connection in the order they are accepted.
+    pub(crate) fn start(replies: impl IntoIterator<Item = Reply>) -> Self {
+        let replies: Vec<Reply> = replies.into_iter().collect();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let closed = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = oneshot::channel();
+        let stats = (Arc::clone(&closed), Arc::clone(&max_in_flight));
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .unwrap();
+            let requests = runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let in_flight = Arc::new(AtomicUsize::new(0));
+                let mut connections = Vec::with_capacity(replies.len());
+                for reply in replies {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    let in_flight = Arc::clone(&in_flight);
+                    let (closed, max_in_flight) = (Arc::clone(&stats.0), Arc::clone(&stats.1));
+                    connections.push(tokio::spawn(async move {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(now, Ordering::SeqCst);
+                        let request = answer(socket, reply, &in_flight).await;
+                        closed.fetch_add(1, Ordering::SeqCst);
+                        request
+                    }));
+                }
+                let mut requests = Vec::with_capacity(connections.len());
+                for connection in connections {
+                    requests.push(connection.await.unwrap());
+                }
+                requests
+            });
+            let _ = tx.send(requests);
+        });
+        Self {
+            url,
+            closed,
+            max_in_flight,
+            requests: rx,
+        }
+    }
+
+    /// The base URL to point a client at.
+    pub(crate) fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// The number of connections that have been fully served or closed by the client.
+    pub(crate) fn closed_connections(&self) -> usize {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// The most connections that were awaiting a reply at once.
+    pub(crate) fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Waits for every reply to be served and returns the requests in accept order.
+    pub(crate) async fn requests(self) -> Vec<Request> {
+        self.requests.await.expect("mock server failed")
+    }
+
+    /// Like [`MockServer::requests`], for tests without a runtime.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking_requests(self) -> Vec<Request> {
+        self.requests.blocking_recv().expect("mock server failed")
+    }
+}
+
+async fn answer(mut socket: TcpStream, reply: Reply, in_flight: &AtomicUsize) -> Request {
+    let request = read_request(&mut socket).await;
+    tokio::time::sleep(reply.delay).await;
+    in_flight.fetch_sub(1, Ordering::SeqCst);
+    if !reply.silent {
+        let mut head = format!("HTTP/1.1 {}\r\n", reply.status);
+        for (name, value) in &reply.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        if !reply.stall {
+            head.push_str(&format!("content-length: {}\r\n", reply.body.len()));
+        }
+        head.push_str("connection: close\r\n\r\n");
+        // Clients may hang up mid-body, e.g. when rejecting an oversized response.
+        if socket.write_all(head.as_bytes()).await.is_err()
+            || socket.write_all(&reply.body).await.is_err()
+        {
+            return request;
+        }
+    }
+    if reply.stall || reply.silent {
+        let mut chunk = [0u8; 4096];
+        while matches!(socket.read(&mut chunk).await, Ok(n) if n > 0) {}
+    }
+    request
+}
+
+async fn read_request(socket: &mut TcpStream) -> Request {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut chunk).await.unwrap();
+        buf.extend_from_slice(&chunk[..n]);
+        let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+            assert!(n > 0, "connection closed mid-request");
+            continue;
+        };
+        let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+        let request = Request {
+            head,
+            body: Vec::new(),
+        };
+        let content_length = request
+            .header("content-length")
+            .map(|len| len.parse::<usize>().unwrap())
+            .unwrap_or(0);
+        let body_start = end + 4;
+        if buf.len() >= body_start + content_length || n == 0 {
+            let body_end = buf.len().min(body_start + content_length);
+            return Request {
+                body: buf[body_start..body_end].to_vec(),
+                ..request
+            };
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::MessageCreateParams;
+
+/// A single request within a message batch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchRequest {
+    /// Developer-provided ID used to match this request to its result.
+    ///
+    /// Must be unique within the batch.
+    pub custom_id: String,
+
+    /// The Messages API parameters for this request.
+    pub params: MessageCreateParams,
+}
+
+impl BatchRequest {
+    /// Creates a new batch request.
+    pub fn new(custom_id: impl Into<String>, params: MessageCreateParams) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            params,
+        }
+    }
+}
+
+/// Parameters for creating a message batch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BatchCreateParams {
+    /// The requests to process in the batch.
+    pub requests: Vec<BatchRequest>,
+}
+
+impl BatchCreateParams {
+    /// Creates batch parameters from the given requests.
+    pub fn new(requests: Vec<BatchRequest>) -> Self {
+        Self { requests }
+    }
+
+    /// Creates batch parameters from `(custom_id, params)` pairs.
+    pub fn from_requests<S: Into<String>>(
+        requests: impl IntoIterator<Item = (S, MessageCreateParams)>,
+    ) -> Self {
+        Self::new(
+            requests
+                .into_iter()
+                .map(|(custom_id, params)| BatchRequest::new(custom_id, params))
+                .collect(),
+        )
+    }
+
+    /// Serializes the requests as JSONL, one `{"custom_id", "params"}` object per line.
+    pub fn to_jsonl(&self) -> Result<String, crate::Error> {
+        let mut jsonl = String::new();
+        for request in &self.requests {
+            jsonl.push_str(&serde_json::to_string(request)?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+
+    /// Parses requests from JSONL produced by [`BatchCreateParams::to_jsonl`].
+    ///
+    /// Blank lines are ignored.
+    pub fn from_jsonl(jsonl: &str) -> Result<Self, crate::Error> {
+        Ok(Self::new(parse_jsonl(jsonl)?))
+    }
+}
+
+/// Parses each non-blank line of `jsonl` as a `T`, reporting the line number on failure.
+pub(crate) fn parse_jsonl<T: serde::de::DeserializeOwned>(
+    jsonl: &str,
+) -> Result<Vec<T>, crate::Error> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line).map_err(|err| {
+                crate::Error::serialization(
+                    format!("invalid JSONL on line {}: {err}", idx + 1),
+                    Some(Box::new(err)),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{KnownModel, MessageParam, Model};
+
+    fn params(text: &str) -> MessageCreateParams {
+        MessageCreateParams::new(
+            256,
+            vec![MessageParam::user(text)],
+            Model::Known(KnownModel::ClaudeHaiku45),
+        )
+    }
+
+    #[test]
+    fn to_jsonl_emits_one_line_per_request() {
+        let batch =
+            BatchCreateParams::from_requests(vec![("a", params("one")), ("b", params("two"))]);
+        let jsonl = batch.to_jsonl().unwrap();
+
+        let lines: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["custom_id"], "a");
+        assert_eq!(lines[0]["params"]["messages"][0]["content"], "one");
+        assert_eq!(lines[1]["custom_id"], "b");
+        assert_eq!(lines[1]["params"]["model"], "claude-haiku-4-5");
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        let batch = BatchCreateParams::from_requests(vec![
+            ("first".to_string(), params("one")),
+            ("second".to_string(), params("two")),
+        ]);
+        let jsonl = batch.to_jsonl().unwrap();
+        let parsed = BatchCreateParams::from_jsonl(&jsonl).unwrap();
+        assert_eq!(parsed, batch);
+    }
+
+    #[test]
+    fn from_jsonl_reports_line_numbers() {
+        let err = BatchCreateParams::from_jsonl("\nnot json\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// Parameters for listing message batches.
+///
+/// Batches are listed most recent first; the cursors page through that order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchListParams {
+    /// ID of the object to use as a cursor for pagination.
+    ///
+    /// When provided, returns the page of results immediately after this object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after_id: Option<String>,
+
+    /// ID of the object to use as a cursor for pagination.
+    ///
+    /// When provided, returns the page of results immediately before this object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before_id: Option<String>,
+
+    /// Number of items to return per page.
+    ///
+    /// Defaults to `20`. Ranges from `1` to `1000`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl BatchListParams {
+    /// Create a new, empty instance of BatchListParams.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the after_id parameter for pagination.
+    pub fn with_after_id(mut self, after_id: impl Into<String>) -> Self {
+        self.after_id = Some(after_id.into());
+        self
+    }
+
+    /// Set the before_id parameter for pagination.
+    pub fn with_before_id(mut self, before_id: impl Into<String>) -> Self {
+        self.before_id = Some(before_id.into());
+        self
+    }
+
+    /// Set the limit for the number of items to return per page.
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_list_params_serialization() {
+        let params = BatchListParams::new()
+            .with_after_id("msgbatch_1")
+            .with_limit(50);
+
+        let json = serde_json::to_value(&params).unwrap();
+        let expected = serde_json::json!({
+            "after_id": "msgbatch_1",
+            "limit": 50
+        });
+        assert_eq!(json, expected);
+        assert_eq!(
+            serde_json::to_value(BatchListParams::default()).unwrap(),
+            serde_json::json!({})
+        );
+    }
+}
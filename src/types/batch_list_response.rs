@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::MessageBatch;
+
+/// Response from the list message batches API endpoint.
+///
+/// Contains one page of batches and pagination information.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchListResponse {
+    /// Batches returned by the API.
+    pub data: Vec<MessageBatch>,
+
+    /// Indicates whether there are more results available.
+    pub has_more: bool,
+
+    /// The ID of the first object in the current page.
+    ///
+    /// Can be used for pagination when requesting the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_id: Option<String>,
+
+    /// The ID of the last object in the current page.
+    ///
+    /// Can be used for pagination when requesting the next page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_id: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_list_response_deserialization() {
+        let json = serde_json::json!({
+            "data": [{
+                "id": "msgbatch_1",
+                "type": "message_batch",
+                "processing_status": "ended",
+                "request_counts": {
+                    "processing": 0,
+                    "succeeded": 2,
+                    "errored": 0,
+                    "canceled": 0,
+                    "expired": 0
+                },
+                "created_at": "2025-06-01T12:00:00Z",
+                "expires_at": "2025-06-02T12:00:00Z",
+                "ended_at": "2025-06-01T13:00:00Z",
+                "results_url": null
+            }],
+            "has_more": true,
+            "first_id": "msgbatch_1",
+            "last_id": "msgbatch_1"
+        });
+        let response: BatchListResponse = serde_json::from_value(json).unwrap();
+
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.data[0].id, "msgbatch_1");
+        assert!(response.has_more);
+        assert_eq!(response.last_id.as_deref(), Some("msgbatch_1"));
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::Message;
+use crate::types::batch_create_params::parse_jsonl;
+
+/// The outcome of a single request in a message batch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchResult {
+    /// The request completed and produced a message.
+    Succeeded {
+        /// The message generated for the request.
+        message: Message,
+    },
+
+    /// The request failed.
+    Errored {
+        /// The error response body returned for the request.
+        error: serde_json::Value,
+    },
+
+    /// The batch was canceled before the request was processed.
+    Canceled,
+
+    /// The batch expired before the request was processed.
+    Expired,
+}
+
+/// A single line of a message batch results file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchResultEntry {
+    /// The `custom_id` of the request this result belongs to.
+    pub custom_id: String,
+
+    /// The outcome of the request.
+    pub result: BatchResult,
+}
+
+impl BatchResultEntry {
+    /// Returns the message if the request succeeded.
+    pub fn message(&self) -> Option<&Message> {
+        match &self.result {
+            BatchResult::Succeeded { message } => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a message batch results file, one [`BatchResultEntry`] per non-blank line.
+pub fn parse_batch_results_jsonl(jsonl: &str) -> Result<Vec<BatchResultEntry>, crate::Error> {
+    parse_jsonl(jsonl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ContentBlock, KnownModel, Model, StopReason, TextBlock, Usage};
+
+    #[test]
+    fn parse_results() {
+        let jsonl = concat!(
+            r#"{"custom_id":"a","result":{"type":"succeeded","message":{"id":"msg_1","type":"message","role":"assistant","model":"claude-haiku-4-5","content":[{"type":"text","text":"hi"}],"stop_reason":"end_turn","usage":{"input_tokens":3,"output_tokens":1}}}}"#,
+            "\n",
+            r#"{"custom_id":"b","result":{"type":"errored","error":{"type":"error","error":{"type":"invalid_request_error","message":"bad"}}}}"#,
+            "\n",
+            r#"{"custom_id":"c","result":{"type":"canceled"}}"#,
+            "\n",
+            r#"{"custom_id":"d","result":{"type":"expired"}}"#,
+            "\n",
+        );
+        let entries = parse_batch_results_jsonl(jsonl).unwrap();
+        assert_eq!(entries.len(), 4);
+
+        let message = entries[0].message().unwrap();
+        assert_eq!(message.stop_reason, Some(StopReason::EndTurn));
+        assert!(
+            matches!(&entries[1].result, BatchResult::Errored { error } if error["error"]["message"] == "bad")
+        );
+        assert_eq!(entries[2].result, BatchResult::Canceled);
+        assert_eq!(entries[3].result, BatchResult::Expired);
+        assert!(entries[3].message().is_none());
+    }
+
+    #[test]
+    fn results_round_trip() {
+        let message = Message::new(
+            "msg_1".to_string(),
+            vec![ContentBlock::Text(TextBlock::new("hi"))],
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(3, 1),
+        );
+        let entries = vec![
+            BatchResultEntry {
+                custom_id: "a".to_string(),
+                result: BatchResult::Succeeded { message },
+            },
+            BatchResultEntry {
+                custom_id: "b".to_string(),
+                result: BatchResult::Expired,
+            },
+        ];
+        let jsonl: String = entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect();
+        assert_eq!(parse_batch_results_jsonl(&jsonl).unwrap(), entries);
+    }
+}
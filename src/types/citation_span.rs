@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// A citation normalized to a span within a source document.
+///
+/// The unit of `start` and `end` depends on the kind of document that was cited: character
+/// indices for plain text, page numbers for PDFs, and block indices for custom content
+/// documents.  Produced by [`TextCitation::as_span`](crate::TextCitation::as_span).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CitationSpan {
+    /// The index of the cited document in the input context.
+    pub document_index: i32,
+
+    /// The start of the span (inclusive).
+    pub start: i32,
+
+    /// The end of the span.
+    pub end: i32,
+
+    /// The text that was cited.
+    pub cited_text: String,
+}
+
+impl CitationSpan {
+    /// Creates a new CitationSpan.
+    pub fn new(document_index: i32, start: i32, end: i32, cited_text: impl Into<String>) -> Self {
+        Self {
+            document_index,
+            start,
+            end,
+            cited_text: cited_text.into(),
+        }
+    }
+}
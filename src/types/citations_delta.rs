@@ -154,4 +154,56 @@ mod tests {
             _ => panic!("Expected CharLocation"),
         }
     }
+
+    #[test]
+    fn every_variant_round_trips() {
+        let citations = [
+            (
+                "char_location",
+                Citation::CharLocation(CitationCharLocation {
+                    cited_text: "a".to_string(),
+                    document_index: 0,
+                    document_title: None,
+                    end_char_index: 1,
+                    start_char_index: 0,
+                }),
+            ),
+            (
+                "page_location",
+                Citation::PageLocation(CitationPageLocation {
+                    cited_text: "b".to_string(),
+                    document_index: 1,
+                    document_title: Some("Doc".to_string()),
+                    end_page_number: 3,
+                    start_page_number: 2,
+                }),
+            ),
+            (
+                "content_block_location",
+                Citation::ContentBlockLocation(CitationContentBlockLocation {
+                    cited_text: "c".to_string(),
+                    document_index: 2,
+                    document_title: None,
+                    end_block_index: 1,
+                    start_block_index: 0,
+                }),
+            ),
+            (
+                "web_search_result_location",
+                Citation::WebSearchResultLocation(CitationWebSearchResultLocation {
+                    cited_text: "d".to_string(),
+                    encrypted_index: "idx".to_string(),
+                    title: None,
+                    url: "https://example.com".to_string(),
+                }),
+            ),
+        ];
+        for (tag, citation) in citations {
+            let delta = CitationsDelta { citation };
+            let json = serde_json::to_value(&delta).unwrap();
+            assert_eq!(json["citation"]["type"], tag);
+            let back: CitationsDelta = serde_json::from_value(json).unwrap();
+            assert_eq!(back, delta);
+        }
+    }
 }
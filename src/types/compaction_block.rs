@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::CacheControlEphemeral;
+
+/// A summary of earlier conversation produced by server-side context compaction.
+///
+/// When a request opts into the [`CompactionBlock::BETA`] beta, the server may replace the start
+/// of a long conversation with a compaction block.  Send the block back unchanged on the next
+/// turn; the server drops everything before it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactionBlock {
+    /// The summarized context, or `None` if compaction did not produce a summary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// Create a cache control breakpoint at this content block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControlEphemeral>,
+}
+
+impl CompactionBlock {
+    /// The beta that enables server-side context compaction.
+    pub const BETA: &'static str = "compact-2026-01-12";
+
+    /// Creates a new CompactionBlock with the specified summary.
+    pub fn new<S: Into<String>>(content: S) -> Self {
+        Self {
+            content: Some(content.into()),
+            cache_control: None,
+        }
+    }
+
+    /// Add cache control to this compaction block.
+    pub fn with_cache_control(mut self, cache_control: CacheControlEphemeral) -> Self {
+        self.cache_control = Some(cache_control);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, to_value};
+
+    #[test]
+    fn compaction_block_serialization() {
+        let block = CompactionBlock::new("The user asked about Rust lifetimes.");
+        assert_eq!(
+            to_value(&block).unwrap(),
+            json!({"content": "The user asked about Rust lifetimes."})
+        );
+    }
+
+    #[test]
+    fn deserialization_without_content() {
+        let block: CompactionBlock =
+            serde_json::from_value(json!({"type": "compaction", "content": null})).unwrap();
+        assert_eq!(block.content, None);
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// A compaction delta, carrying a piece of a compaction block's summary in a streaming response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactionDelta {
+    /// The summary fragment to append to the compaction block.
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+impl CompactionDelta {
+    /// Create a new `CompactionDelta` with the given content.
+    pub fn new(content: String) -> Self {
+        Self {
+            content: Some(content),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, to_value};
+
+    #[test]
+    fn compaction_delta_serialization() {
+        let delta = CompactionDelta::new("Earlier, the user".to_string());
+        assert_eq!(
+            to_value(&delta).unwrap(),
+            json!({"content": "Earlier, the user"})
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::types::{
-    DocumentBlock, ImageBlock, RedactedThinkingBlock, ServerToolUseBlock, TextBlock, ThinkingBlock,
-    ToolResultBlock, ToolUseBlock, WebSearchToolResultBlock,
+    CompactionBlock, DocumentBlock, ImageBlock, RedactedThinkingBlock, ServerToolUseBlock,
+    TextBlock, ThinkingBlock, ToolResultBlock, ToolUseBlock, WebSearchToolResultBlock,
 };
 
 /// A block of content in a message.
@@ -47,6 +47,10 @@ pub enum ContentBlock {
     /// A block containing redacted thinking data
     #[serde(rename = "redacted_thinking")]
     RedactedThinking(RedactedThinkingBlock),
+
+    /// A summary of earlier context produced by server-side compaction
+    #[serde(rename = "compaction")]
+    Compaction(CompactionBlock),
 }
 
 impl ContentBlock {
@@ -95,6 +99,11 @@ impl ContentBlock {
         matches!(self, ContentBlock::RedactedThinking(_))
     }
 
+    /// Returns true if this block is a compaction block
+    pub fn is_compaction(&self) -> bool {
+        matches!(self, ContentBlock::Compaction(_))
+    }
+
     /// Returns a reference to the inner TextBlock if this is a Text variant,
     /// or None otherwise.
     pub fn as_text(&self) -> Option<&TextBlock> {
@@ -175,6 +184,15 @@ impl ContentBlock {
             _ => None,
         }
     }
+
+    /// Returns a reference to the inner CompactionBlock if this is a Compaction variant,
+    /// or None otherwise.
+    pub fn as_compaction(&self) -> Option<&CompactionBlock> {
+        match self {
+            ContentBlock::Compaction(block) => Some(block),
+            _ => None,
+        }
+    }
 }
 
 /// Helper methods to create ContentBlock variants
@@ -232,6 +250,12 @@ impl From<RedactedThinkingBlock> for ContentBlock {
     }
 }
 
+impl From<CompactionBlock> for ContentBlock {
+    fn from(block: CompactionBlock) -> Self {
+        ContentBlock::Compaction(block)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,6 +372,7 @@ mod tests {
         assert!(content_block.as_document().is_none());
         assert!(content_block.as_thinking().is_none());
         assert!(content_block.as_redacted_thinking().is_none());
+        assert!(content_block.as_compaction().is_none());
 
         let text_ref = content_block.as_text().unwrap();
         assert_eq!(text_ref.text, "This is some text content.");
@@ -366,4 +391,102 @@ mod tests {
 
         assert_eq!(json, expected);
     }
+
+    fn every_variant() -> Vec<(&'static str, ContentBlock)> {
+        vec![
+            ("text", TextBlock::new("hello").into()),
+            (
+                "image",
+                ImageBlock::new_with_url(crate::types::UrlImageSource::new(
+                    "https://example.com/image.jpg".to_string(),
+                ))
+                .into(),
+            ),
+            (
+                "tool_use",
+                ToolUseBlock::new("toolu_1", "search", serde_json::json!({"q": "rust"})).into(),
+            ),
+            (
+                "server_tool_use",
+                ServerToolUseBlock::new_web_search("srvtoolu_1", "rust").into(),
+            ),
+            (
+                "web_search_tool_result",
+                WebSearchToolResultBlock::new_with_results(
+                    vec![crate::types::WebSearchResultBlock::new(
+                        "encrypted",
+                        "Rust",
+                        "https://www.rust-lang.org",
+                    )],
+                    "srvtoolu_1",
+                )
+                .into(),
+            ),
+            (
+                "tool_result",
+                ToolResultBlock::new("toolu_1".to_string())
+                    .with_string_content("done".to_string())
+                    .with_error(false)
+                    .into(),
+            ),
+            (
+                "document",
+                DocumentBlock::new_with_plain_text(crate::types::PlainTextSource::new(
+                    "some text".to_string(),
+                ))
+                .into(),
+            ),
+            ("thinking", ThinkingBlock::new("hmm", "sig").into()),
+            (
+                "redacted_thinking",
+                RedactedThinkingBlock::new("opaque").into(),
+            ),
+            ("compaction", CompactionBlock::new("summary").into()),
+        ]
+    }
+
+    #[test]
+    fn every_variant_round_trips() {
+        for (tag, block) in every_variant() {
+            let json = serde_json::to_string(&block).unwrap();
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(value["type"], tag, "wrong tag in {json}");
+            assert_eq!(
+                json.matches(r#""type":"#).count(),
+                1 + nested_type_keys(&value),
+                "duplicate type discriminator in {json}"
+            );
+            let back: ContentBlock = serde_json::from_str(&json).unwrap();
+            assert_eq!(back, block, "{tag} did not round-trip");
+        }
+    }
+
+    /// Counts the "type" keys below the top level of a JSON value.
+    fn nested_type_keys(value: &serde_json::Value) -> usize {
+        fn count(value: &serde_json::Value) -> usize {
+            match value {
+                serde_json::Value::Object(map) => {
+                    usize::from(map.contains_key("type")) + map.values().map(count).sum::<usize>()
+                }
+                serde_json::Value::Array(items) => items.iter().map(count).sum(),
+                _ => 0,
+            }
+        }
+        match value {
+            serde_json::Value::Object(map) => map.values().map(count).sum(),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn tool_result_has_single_type_key() {
+        let block = ContentBlock::from(
+            ToolResultBlock::new("toolu_1".to_string()).with_string_content("ok".to_string()),
+        );
+        let json = serde_json::to_string(&block).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"tool_result","tool_use_id":"toolu_1","content":"ok"}"#
+        );
+    }
 }
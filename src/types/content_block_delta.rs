@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::{CitationsDelta, InputJsonDelta, SignatureDelta, TextDelta, ThinkingDelta};
+use crate::types::{
+    CitationsDelta, CompactionDelta, InputJsonDelta, SignatureDelta, TextDelta, ThinkingDelta,
+};
 
 /// A raw content block delta, representing a streaming update to a content block.
 ///
@@ -28,6 +30,10 @@ pub enum ContentBlockDelta {
     /// A signature delta.
     #[serde(rename = "signature_delta")]
     SignatureDelta(SignatureDelta),
+
+    /// A compaction delta.
+    #[serde(rename = "compaction_delta")]
+    CompactionDelta(CompactionDelta),
 }
 
 impl ContentBlockDelta {
@@ -55,6 +61,11 @@ impl ContentBlockDelta {
     pub fn from_signature_delta(signature_delta: SignatureDelta) -> Self {
         ContentBlockDelta::SignatureDelta(signature_delta)
     }
+
+    /// Create a new `ContentBlockDelta` from a compaction delta.
+    pub fn from_compaction_delta(compaction_delta: CompactionDelta) -> Self {
+        ContentBlockDelta::CompactionDelta(compaction_delta)
+    }
 }
 
 #[cfg(test)]
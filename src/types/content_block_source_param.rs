@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::Content;
+use crate::types::{Content, ContentBlock};
 
 /// Parameter for a content block source.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -46,6 +46,31 @@ impl ContentBlockSourceParam {
     pub fn new_with_item(content: Content) -> Self {
         Self::new_with_array(vec![content])
     }
+
+    /// Create a new `ContentBlockSourceParam` from a single string of text.
+    pub fn from_text(text: impl Into<String>) -> Self {
+        Self::new_with_string(text.into())
+    }
+
+    /// Create a new `ContentBlockSourceParam` from text and image content blocks.
+    ///
+    /// Each block becomes a separate chunk that citations can point at by index.  Content
+    /// documents may only hold text and images; any other block is a validation error.
+    pub fn from_blocks(blocks: Vec<ContentBlock>) -> Result<Self, crate::Error> {
+        let content = blocks
+            .into_iter()
+            .enumerate()
+            .map(|(idx, block)| match block {
+                ContentBlock::Text(text) => Ok(Content::Text(text)),
+                ContentBlock::Image(image) => Ok(Content::Image(image)),
+                _ => Err(crate::Error::validation(
+                    "document content may only contain text and image blocks",
+                    Some(format!("content[{idx}]")),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new_with_array(content))
+    }
 }
 
 impl std::str::FromStr for ContentBlockSourceParam {
@@ -59,7 +84,7 @@ impl std::str::FromStr for ContentBlockSourceParam {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ImageBlock, TextBlock, UrlImageSource};
+    use crate::types::{DocumentBlock, ImageBlock, TextBlock, ThinkingBlock, UrlImageSource};
     use serde_json::{json, to_value};
 
     #[test]
@@ -152,4 +177,63 @@ mod tests {
             _ => panic!("Expected String variant"),
         }
     }
+
+    #[test]
+    fn document_from_text_round_trip() {
+        let document = DocumentBlock::from_content(ContentBlockSourceParam::from_text("Notes"))
+            .with_citations_enabled();
+        let json = to_value(&document).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "source": {
+                    "type": "content",
+                    "content": "Notes"
+                },
+                "citations": {
+                    "enabled": true
+                }
+            })
+        );
+        let parsed: DocumentBlock = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, document);
+    }
+
+    #[test]
+    fn document_from_blocks_round_trip() {
+        let source = ContentBlockSourceParam::from_blocks(vec![
+            ContentBlock::Text(TextBlock::new("First chunk")),
+            ContentBlock::Text(TextBlock::new("Second chunk")),
+        ])
+        .unwrap();
+        let document = DocumentBlock::from_content(source);
+        let json = to_value(&document).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "source": {
+                    "type": "content",
+                    "content": [
+                        {"type": "text", "text": "First chunk"},
+                        {"type": "text", "text": "Second chunk"}
+                    ]
+                }
+            })
+        );
+        let parsed: DocumentBlock = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, document);
+    }
+
+    #[test]
+    fn from_blocks_rejects_other_blocks() {
+        let err = ContentBlockSourceParam::from_blocks(vec![
+            ContentBlock::Text(TextBlock::new("ok")),
+            ContentBlock::Thinking(ThinkingBlock::new("hmm", "sig")),
+        ])
+        .unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("content[1]"), "{err}");
+    }
 }
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::types::{
     Base64PdfSource, CacheControlEphemeral, CitationsConfig, ContentBlockSourceParam,
-    PlainTextSource, UrlPdfSource,
+    FilePdfSource, PlainTextSource, UrlPdfSource,
 };
 
 /// The source type for a document block, which can be one of several types.
@@ -24,6 +24,10 @@ pub enum DocumentSource {
     /// A URL PDF source.
     #[serde(rename = "url")]
     UrlPdf(UrlPdfSource),
+
+    /// A PDF uploaded through the Files API.
+    #[serde(rename = "file")]
+    FilePdf(FilePdfSource),
 }
 
 impl From<Base64PdfSource> for DocumentSource {
@@ -50,6 +54,12 @@ impl From<UrlPdfSource> for DocumentSource {
     }
 }
 
+impl From<FilePdfSource> for DocumentSource {
+    fn from(source: FilePdfSource) -> Self {
+        DocumentSource::FilePdf(source)
+    }
+}
+
 /// Parameters for a document block.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DocumentBlock {
@@ -100,11 +110,29 @@ impl DocumentBlock {
         Self::new(DocumentSource::ContentBlock(source))
     }
 
+    /// Create a new `DocumentBlock` whose content is given directly rather than as a file.
+    ///
+    /// With citations enabled, citations into such a document are
+    /// [content block locations](crate::CitationContentBlockLocation).
+    pub fn from_content(source: ContentBlockSourceParam) -> Self {
+        Self::new_with_content_block(source)
+    }
+
     /// Create a new `DocumentBlock` with a URL PDF source.
     pub fn new_with_url_pdf(source: UrlPdfSource) -> Self {
         Self::new(DocumentSource::UrlPdf(source))
     }
 
+    /// Create a new `DocumentBlock` with a Files API PDF source.
+    pub fn new_with_file_pdf(source: FilePdfSource) -> Self {
+        Self::new(DocumentSource::FilePdf(source))
+    }
+
+    /// Create a new `DocumentBlock` referencing a PDF uploaded through the Files API.
+    pub fn from_file_id(file_id: impl Into<String>) -> Self {
+        Self::new_with_file_pdf(FilePdfSource::new(file_id))
+    }
+
     /// Add a cache control to this document block.
     pub fn with_cache_control(mut self, cache_control: CacheControlEphemeral) -> Self {
         self.cache_control = Some(cache_control);
@@ -117,6 +145,14 @@ impl DocumentBlock {
         self
     }
 
+    /// Enable citations for this document block.
+    ///
+    /// Text blocks in the response that draw on this document carry citations pointing back
+    /// into it; when streaming, they arrive as `citations_delta` events.
+    pub fn with_citations_enabled(self) -> Self {
+        self.with_citations(CitationsConfig::enabled())
+    }
+
     /// Add context to this document block.
     pub fn with_context(mut self, context: String) -> Self {
         self.context = Some(context);
@@ -135,6 +171,45 @@ mod tests {
     use super::*;
     use serde_json::{json, to_value};
 
+    #[test]
+    fn document_block_from_file_id_round_trip() {
+        let document_block =
+            DocumentBlock::from_file_id("file_pdf123").with_title("Report".to_string());
+        let json = to_value(&document_block).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "source": {
+                    "type": "file",
+                    "file_id": "file_pdf123"
+                },
+                "title": "Report"
+            })
+        );
+        let parsed: DocumentBlock = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, document_block);
+    }
+
+    #[test]
+    fn document_block_with_citations_enabled() {
+        let document_block = DocumentBlock::from_file_id("file_pdf123").with_citations_enabled();
+        let json = to_value(&document_block).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "source": {
+                    "type": "file",
+                    "file_id": "file_pdf123"
+                },
+                "citations": {
+                    "enabled": true
+                }
+            })
+        );
+    }
+
     #[test]
     fn document_block_with_base64_pdf() {
         let base64_source =
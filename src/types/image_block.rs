@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::{Base64ImageSource, CacheControlEphemeral, UrlImageSource};
+use crate::types::{Base64ImageSource, CacheControlEphemeral, FileImageSource, UrlImageSource};
 
-/// The source type for an image block, which can be either Base64 encoded or a URL.
+/// The source type for an image block: Base64 encoded data, a URL, or an uploaded file.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type")]
 pub enum ImageSource {
@@ -13,6 +13,10 @@ pub enum ImageSource {
     /// A URL image source.
     #[serde(rename = "url")]
     Url(UrlImageSource),
+
+    /// An image uploaded through the Files API.
+    #[serde(rename = "file")]
+    File(FileImageSource),
 }
 
 /// Parameters for an image block.
@@ -45,6 +49,16 @@ impl ImageBlock {
         Self::new(ImageSource::Url(source))
     }
 
+    /// Create a new `ImageBlock` with a Files API image source.
+    pub fn new_with_file(source: FileImageSource) -> Self {
+        Self::new(ImageSource::File(source))
+    }
+
+    /// Create a new `ImageBlock` referencing an image uploaded through the Files API.
+    pub fn from_file_id(file_id: impl Into<String>) -> Self {
+        Self::new_with_file(FileImageSource::new(file_id))
+    }
+
     /// Add a cache control to this image block.
     pub fn with_cache_control(mut self, cache_control: CacheControlEphemeral) -> Self {
         self.cache_control = Some(cache_control);
@@ -98,6 +112,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn image_block_from_file_id_round_trip() {
+        let image_block = ImageBlock::from_file_id("file_abc123");
+        let json = to_value(&image_block).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "source": {
+                    "type": "file",
+                    "file_id": "file_abc123"
+                }
+            })
+        );
+        let parsed: ImageBlock = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, image_block);
+    }
+
     #[test]
     fn image_block_with_cache_control() {
         let url_source = UrlImageSource::new("https://example.com/image.jpg".to_string());
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::{ContentBlock, MessageRole, Model, StopReason, Usage};
+use crate::Budget;
+use crate::types::{
+    CompactionBlock, ContentBlock, MessageRole, Model, StopReason, TextCitation, Usage,
+    UsageServiceTier,
+};
 
 /// A message generated by the assistant.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -70,12 +74,93 @@ impl Message {
         self.stop_sequence = Some(stop_sequence);
         self
     }
+
+    /// Returns every citation attached to the text blocks of this message, in order.
+    pub fn citations(&self) -> Vec<&TextCitation> {
+        self.content
+            .iter()
+            .filter_map(ContentBlock::as_text)
+            .flat_map(|block| block.citations.iter().flatten())
+            .collect()
+    }
+
+    /// Returns the text the model produced when it refused to respond.
+    ///
+    /// This is `None` unless `stop_reason` is [`StopReason::Refusal`] and the message has at
+    /// least one text block; multiple text blocks are concatenated.
+    pub fn refusal_text(&self) -> Option<String> {
+        if self.stop_reason != Some(StopReason::Refusal) {
+            return None;
+        }
+        let mut texts = self
+            .content
+            .iter()
+            .filter_map(ContentBlock::as_text)
+            .peekable();
+        texts.peek()?;
+        Some(texts.map(|block| block.text.as_str()).collect())
+    }
+
+    /// Returns the stop sequence that ended generation.
+    ///
+    /// This is `None` unless `stop_reason` is [`StopReason::StopSequence`], so streamed and
+    /// non-streamed messages report a match the same way.
+    pub fn stop_sequence(&self) -> Option<&str> {
+        if self.stop_reason != Some(StopReason::StopSequence) {
+            return None;
+        }
+        self.stop_sequence.as_deref()
+    }
+
+    /// Returns the service tier that served this message, if the API reported one.
+    pub fn service_tier(&self) -> Option<UsageServiceTier> {
+        self.usage.service_tier
+    }
+
+    /// Returns the cost of this message's usage in micro-cents at `budget`'s rates.
+    ///
+    /// This only prices the message; it does not consume anything from `budget`.
+    pub fn cost(&self, budget: &Budget) -> u64 {
+        budget.calculate_cost(&self.usage)
+    }
+
+    /// Returns true if the assistant produced no content blocks.
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Returns the number of content blocks in this message.
+    pub fn content_block_count(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Returns the number of client tool uses the model requested.
+    ///
+    /// Server tool uses, such as web search, are run by the API and not counted.
+    pub fn tool_use_count(&self) -> usize {
+        self.content
+            .iter()
+            .filter(|block| block.is_tool_use())
+            .count()
+    }
+
+    /// Returns true if the message has a thinking or redacted thinking block.
+    pub fn has_thinking(&self) -> bool {
+        self.content
+            .iter()
+            .any(|block| block.is_thinking() || block.is_redacted_thinking())
+    }
+
+    /// Returns the compaction block the server returned, if it compacted the context.
+    pub fn compaction(&self) -> Option<&CompactionBlock> {
+        self.content.iter().find_map(ContentBlock::as_compaction)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::TextBlock;
+    use crate::types::{KnownModel, TextBlock};
     use serde_json::{json, to_value};
 
     #[test]
@@ -190,6 +275,168 @@ mod tests {
 
         assert_eq!(message.usage.input_tokens, 50);
         assert_eq!(message.usage.output_tokens, 100);
+        assert_eq!(message.service_tier(), None);
         // We'll verify server_tool_use in a future PR when ServerToolUsage is properly implemented
     }
+
+    #[test]
+    fn service_tier_from_usage() {
+        let json = json!({
+            "id": "msg_tier",
+            "content": [],
+            "model": "claude-haiku-4-5",
+            "role": "assistant",
+            "stop_reason": "end_turn",
+            "type": "message",
+            "usage": {"input_tokens": 5, "output_tokens": 1, "service_tier": "priority"}
+        });
+        let message: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(message.service_tier(), Some(UsageServiceTier::Priority));
+        assert_eq!(
+            to_value(&message).unwrap()["usage"]["service_tier"],
+            "priority"
+        );
+    }
+
+    #[test]
+    fn citations_across_text_blocks() {
+        let first = TextBlock::new("first")
+            .with_citation(TextCitation::char_location("a".to_string(), 0, 0, 1, None))
+            .with_citation(TextCitation::page_location("b".to_string(), 1, 1, 2, None));
+        let second = TextBlock::new("second").with_citation(TextCitation::content_block_location(
+            "c".to_string(),
+            2,
+            0,
+            1,
+            None,
+        ));
+        let message = Message::new(
+            "msg_1".to_string(),
+            vec![
+                ContentBlock::Text(first),
+                ContentBlock::Text(TextBlock::new("uncited")),
+                ContentBlock::Text(second),
+            ],
+            Model::Known(crate::types::KnownModel::ClaudeHaiku45),
+            Usage::new(1, 1),
+        );
+
+        let cited: Vec<&str> = message
+            .citations()
+            .into_iter()
+            .map(TextCitation::cited_text)
+            .collect();
+        assert_eq!(cited, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn stop_sequence_only_for_stop_sequence_stops() {
+        let message = Message::new(
+            "msg_1".to_string(),
+            Vec::new(),
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(3, 1),
+        )
+        .with_stop_sequence("###".to_string());
+        assert_eq!(message.stop_sequence(), None);
+        let message = message.with_stop_reason(StopReason::StopSequence);
+        assert_eq!(message.stop_sequence(), Some("###"));
+    }
+
+    #[test]
+    fn cost_uses_budget_rates() {
+        let message = Message::new(
+            "msg_1".to_string(),
+            Vec::new(),
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(100, 50)
+                .with_cache_creation_input_tokens(20)
+                .with_cache_read_input_tokens(10),
+        );
+        let budget = Budget::new_with_rates(1_000_000, 300, 1500, 375, 30);
+        assert_eq!(message.cost(&budget), 112_800);
+        assert_eq!(budget.remaining_micro_cents(), 1_000_000);
+    }
+
+    #[test]
+    fn is_empty_checks_content_blocks() {
+        let model = Model::Known(KnownModel::ClaudeHaiku45);
+        let empty = Message::new(
+            "msg_1".to_string(),
+            Vec::new(),
+            model.clone(),
+            Usage::new(3, 0),
+        );
+        assert!(empty.is_empty());
+        let message = Message::new(
+            "msg_2".to_string(),
+            vec![ContentBlock::Text(TextBlock::new(""))],
+            model,
+            Usage::new(3, 1),
+        );
+        assert!(!message.is_empty());
+    }
+
+    #[test]
+    fn refusal_text_only_for_refusals() {
+        let message = Message::new(
+            "msg_1".to_string(),
+            vec![
+                ContentBlock::Text(TextBlock::new("I can't help ")),
+                ContentBlock::Text(TextBlock::new("with that.")),
+            ],
+            Model::Known(crate::types::KnownModel::ClaudeHaiku45),
+            Usage::new(1, 1),
+        );
+        assert_eq!(message.refusal_text(), None);
+
+        let refusal = message.with_stop_reason(StopReason::Refusal);
+        assert_eq!(
+            refusal.refusal_text().as_deref(),
+            Some("I can't help with that.")
+        );
+
+        let empty = Message::new(
+            "msg_2".to_string(),
+            vec![],
+            Model::Known(crate::types::KnownModel::ClaudeHaiku45),
+            Usage::new(1, 0),
+        )
+        .with_stop_reason(StopReason::Refusal);
+        assert_eq!(empty.refusal_text(), None);
+    }
+
+    #[test]
+    fn counts_blocks_of_a_mixed_message() {
+        use crate::types::{ServerToolUseBlock, ThinkingBlock, ToolUseBlock};
+
+        let message = Message::new(
+            "msg_mixed".to_string(),
+            vec![
+                ContentBlock::Thinking(ThinkingBlock::new("Look it up.", "sig")),
+                ContentBlock::Text(TextBlock::new("Checking.")),
+                ContentBlock::ServerToolUse(ServerToolUseBlock::new_web_search(
+                    "srvtoolu_1",
+                    "rust",
+                )),
+                ContentBlock::ToolUse(ToolUseBlock::new("toolu_1", "search", json!({"q": "a"}))),
+                ContentBlock::ToolUse(ToolUseBlock::new("toolu_2", "search", json!({"q": "b"}))),
+            ],
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(1, 1),
+        );
+        assert_eq!(message.content_block_count(), 5);
+        assert_eq!(message.tool_use_count(), 2);
+        assert!(message.has_thinking());
+
+        let plain = Message::new(
+            "msg_plain".to_string(),
+            vec![ContentBlock::Text(TextBlock::new("Hi."))],
+            Model::Known(KnownModel::ClaudeHaiku45),
+            Usage::new(1, 1),
+        );
+        assert_eq!(plain.content_block_count(), 1);
+        assert_eq!(plain.tool_use_count(), 0);
+        assert!(!plain.has_thinking());
+    }
 }
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::types::BatchResultEntry;
+
+/// A message batch, as returned when retrieving a batch's status.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageBatch {
+    /// Unique batch identifier.
+    pub id: String,
+
+    /// Processing status of the batch.
+    pub processing_status: BatchProcessingStatus,
+
+    /// How many requests in the batch are in each state.
+    pub request_counts: RequestCounts,
+
+    /// RFC 3339 datetime string representing the time at which the batch was created.
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+
+    /// RFC 3339 datetime string representing the time at which the batch will expire.
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+
+    /// RFC 3339 datetime string representing the time at which processing ended.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub ended_at: Option<OffsetDateTime>,
+
+    /// URL of the batch's results file, available once processing has ended.
+    #[serde(default)]
+    pub results_url: Option<String>,
+}
+
+/// Processing status of a message batch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchProcessingStatus {
+    /// Requests are still being processed.
+    InProgress,
+
+    /// Cancellation was requested and in-flight requests are being stopped.
+    Canceling,
+
+    /// Every request has finished and results are available.
+    Ended,
+}
+
+/// Tallies of the requests in a message batch by state.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestCounts {
+    /// Requests still being processed.
+    pub processing: u64,
+
+    /// Requests that completed successfully.
+    pub succeeded: u64,
+
+    /// Requests that failed.
+    pub errored: u64,
+
+    /// Requests that were canceled.
+    pub canceled: u64,
+
+    /// Requests that expired.
+    pub expired: u64,
+}
+
+impl RequestCounts {
+    /// Returns the number of requests that are no longer processing.
+    pub fn completed(&self) -> u64 {
+        self.succeeded + self.errored + self.canceled + self.expired
+    }
+
+    /// Returns the number of requests in the batch.
+    pub fn total(&self) -> u64 {
+        self.processing + self.completed()
+    }
+}
+
+/// An update from [`Anthropic::stream_batch_results_live`](crate::Anthropic::stream_batch_results_live).
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchProgress {
+    /// The batch's request counts as of the latest poll.
+    Processing(RequestCounts),
+
+    /// The result of one request, yielded once the batch has ended.
+    Result(BatchResultEntry),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn message_batch_deserialization() {
+        let batch: MessageBatch = serde_json::from_value(serde_json::json!({
+            "id": "msgbatch_01",
+            "type": "message_batch",
+            "processing_status": "in_progress",
+            "request_counts": {
+                "processing": 688,
+                "succeeded": 300,
+                "errored": 10,
+                "canceled": 0,
+                "expired": 2
+            },
+            "created_at": "2025-06-01T12:00:00Z",
+            "expires_at": "2025-06-02T12:00:00Z",
+            "ended_at": null,
+            "cancel_initiated_at": null,
+            "archived_at": null,
+            "results_url": null
+        }))
+        .unwrap();
+
+        assert_eq!(batch.processing_status, BatchProcessingStatus::InProgress);
+        assert_eq!(batch.created_at, datetime!(2025-06-01 12:00:00 UTC));
+        assert_eq!(batch.ended_at, None);
+        assert_eq!(batch.request_counts.completed(), 312);
+        assert_eq!(batch.request_counts.total(), 1000);
+    }
+}
@@ -63,7 +63,7 @@ pub struct MessageCountTokensParams {
     /// content blocks that represent the model's use of those tools. You can then run
     /// those tools using the tool input generated by the model and then optionally
     /// return results back to the model using `tool_result` content blocks.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "crate::types::is_none_or_empty")]
     pub tools: Option<Vec<ToolUnionParam>>,
 }
 
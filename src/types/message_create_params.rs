@@ -1,10 +1,38 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::types::{
-    MessageParam, Metadata, Model, OutputFormat, SystemPrompt, TextBlock, ThinkingConfig,
-    ToolChoice, ToolUnionParam,
+    KnownModel, MessageParam, MessageParamContent, MessageRole, Metadata, Model, OutputFormat,
+    ServiceTier, SystemPrompt, TextBlock, ThinkingConfig, ToolChoice, ToolUnionParam,
 };
 
+/// The beta required for `output_format` and strict tools.
+pub(crate) const STRUCTURED_OUTPUTS_BETA: &str = "structured-outputs-2025-11-13";
+
+/// The beta that raises Claude 3.7 Sonnet's output limit.
+const OUTPUT_128K_BETA: &str = "output-128k-2025-02-19";
+
+/// The top-level request fields this type models, which extra fields may not override.
+const KNOWN_FIELDS: &[&str] = &[
+    "max_tokens",
+    "messages",
+    "model",
+    "metadata",
+    "output_format",
+    "service_tier",
+    "stop_sequences",
+    "system",
+    "temperature",
+    "thinking",
+    "tool_choice",
+    "tools",
+    "top_k",
+    "top_p",
+    "stream",
+];
+
 /// Security limits for DoS prevention
 const MAX_MESSAGE_COUNT: usize = 1000;
 const MAX_MESSAGE_LENGTH: usize = 1_000_000; // 1MB per message
@@ -57,6 +85,13 @@ pub struct MessageCreateParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output_format: Option<OutputFormat>,
 
+    /// The service tier to use for this request.
+    ///
+    /// The tier that served the request is reported in the response's
+    /// [`Usage::service_tier`](crate::Usage::service_tier).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<ServiceTier>,
+
     /// Custom text sequences that will cause the model to stop generating.
     ///
     /// Our models will normally stop when they have naturally completed their turn,
@@ -66,7 +101,7 @@ pub struct MessageCreateParams {
     /// text, you can use the `stop_sequences` parameter. If the model encounters one of
     /// the custom sequences, the response `stop_reason` value will be `"stop_sequence"`
     /// and the response `stop_sequence` value will contain the matched stop sequence.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "crate::types::is_none_or_empty")]
     pub stop_sequences: Option<Vec<String>>,
 
     /// System prompt.
@@ -113,7 +148,7 @@ pub struct MessageCreateParams {
     /// content blocks that represent the model's use of those tools. You can then run
     /// those tools using the tool input generated by the model and then optionally
     /// return results back to the model using `tool_result` content blocks.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "crate::types::is_none_or_empty")]
     pub tools: Option<Vec<ToolUnionParam>>,
 
     /// Only sample from the top K options for each subsequent token.
@@ -143,6 +178,20 @@ pub struct MessageCreateParams {
     /// See [streaming](https://docs.anthropic.com/en/api/messages-streaming) for
     /// details.
     pub stream: bool,
+
+    /// Beta features to enable for this request.
+    ///
+    /// These are sent in the `anthropic-beta` header rather than the request body, merged
+    /// with the client's default betas; see [`MessageCreateParams::merge_betas`].
+    #[serde(skip)]
+    pub betas: Option<Vec<String>>,
+
+    /// Additional top-level fields to send in the request body.
+    ///
+    /// An escape hatch for API parameters this type does not model yet; see
+    /// [`MessageCreateParams::with_extra`].  Keys must not collide with modeled fields.
+    #[serde(flatten, default)]
+    pub extra: HashMap<String, Value>,
 }
 
 impl MessageCreateParams {
@@ -167,6 +216,42 @@ impl MessageCreateParams {
             Some(field_name.to_string()),
         ))
     }
+
+    /// Validate top_p, which must be in (0.0, 1.0].
+    fn validate_top_p(top_p: f32) -> Result<(), crate::Error> {
+        Self::validate_float_range(top_p, "top_p")?;
+        if top_p == 0.0 {
+            return Err(crate::Error::validation(
+                "top_p must be greater than 0.0",
+                Some("top_p".to_string()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate a sampling configuration the way [`Self::validate`] does.
+    ///
+    /// Both values must be in 0.0 to 1.0, `top_p` must be greater than 0.0, and at most one of
+    /// the two may be set.
+    pub fn validate_sampling(
+        temperature: Option<f32>,
+        top_p: Option<f32>,
+    ) -> Result<(), crate::Error> {
+        if let Some(temp) = temperature {
+            Self::validate_float_range(temp, "temperature")?;
+        }
+        if let Some(top_p) = top_p {
+            Self::validate_top_p(top_p)?;
+        }
+        if temperature.is_some() && top_p.is_some() {
+            return Err(crate::Error::validation(
+                "set temperature or top_p, not both",
+                Some("top_p".to_string()),
+            ));
+        }
+        Ok(())
+    }
+
     /// Create a new message creation parameters with streaming disabled.
     pub fn new(max_tokens: u32, messages: Vec<MessageParam>, model: Model) -> Self {
         Self {
@@ -175,6 +260,7 @@ impl MessageCreateParams {
             model,
             metadata: None,
             output_format: None,
+            service_tier: None,
             stop_sequences: None,
             system: None,
             temperature: None,
@@ -184,6 +270,8 @@ impl MessageCreateParams {
             top_k: None,
             top_p: None,
             stream: false,
+            betas: None,
+            extra: HashMap::new(),
         }
     }
 
@@ -195,6 +283,7 @@ impl MessageCreateParams {
             model,
             metadata: None,
             output_format: None,
+            service_tier: None,
             stop_sequences: None,
             system: None,
             temperature: None,
@@ -204,6 +293,8 @@ impl MessageCreateParams {
             top_k: None,
             top_p: None,
             stream: true,
+            betas: None,
+            extra: HashMap::new(),
         }
     }
 
@@ -213,6 +304,14 @@ impl MessageCreateParams {
         self
     }
 
+    /// Set `metadata.user_id`, keeping any other metadata.
+    ///
+    /// The ID is checked against [`Metadata::MAX_USER_ID_LENGTH`] by [`Self::validate`].
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.metadata.get_or_insert_with(Metadata::new).user_id = Some(user_id.into());
+        self
+    }
+
     /// Add output format for structured outputs.
     ///
     /// When set, constrains Claude's response to follow a specific JSON schema,
@@ -241,12 +340,80 @@ impl MessageCreateParams {
         self
     }
 
+    /// Set the service tier for the request.
+    pub fn with_service_tier(mut self, service_tier: ServiceTier) -> Self {
+        self.service_tier = Some(service_tier);
+        self
+    }
+
     /// Add stop sequences to the parameters.
-    pub fn with_stop_sequences(mut self, stop_sequences: Vec<String>) -> Self {
-        self.stop_sequences = Some(stop_sequences);
+    ///
+    /// The sequences are checked by [`MessageCreateParams::validate`] when the request is
+    /// sent: there may be at most 100 of them and none may be empty.
+    pub fn with_stop_sequences(
+        mut self,
+        stop_sequences: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.stop_sequences = Some(stop_sequences.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Enable beta features for this request.
+    pub fn with_betas(mut self, betas: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.betas = Some(betas.into_iter().map(Into::into).collect());
         self
     }
 
+    /// Add a top-level field to the request body that this type does not model.
+    ///
+    /// Returns an error if `key` names a field that is already modeled; set that field
+    /// directly instead.
+    pub fn with_extra(
+        mut self,
+        key: impl Into<String>,
+        value: Value,
+    ) -> Result<Self, crate::Error> {
+        let key = key.into();
+        Self::validate_extra_key(&key)?;
+        self.extra.insert(key, value);
+        Ok(self)
+    }
+
+    fn validate_extra_key(key: &str) -> Result<(), crate::Error> {
+        if KNOWN_FIELDS.contains(&key) {
+            return Err(crate::Error::validation(
+                format!("extra field {key:?} collides with a request field"),
+                Some(format!("extra.{key}")),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Computes the betas to send with this request.
+    ///
+    /// The result is `default_betas`, then this request's [`betas`](Self::betas), then the
+    /// structured outputs beta if [`requires_structured_outputs_beta`] is true, with
+    /// duplicates removed and first occurrences kept in order.
+    ///
+    /// [`requires_structured_outputs_beta`]: Self::requires_structured_outputs_beta
+    pub fn merge_betas(&self, default_betas: &[String]) -> Vec<String> {
+        let structured_outputs = self
+            .requires_structured_outputs_beta()
+            .then_some(STRUCTURED_OUTPUTS_BETA);
+        let mut merged: Vec<String> = Vec::new();
+        for beta in default_betas
+            .iter()
+            .map(String::as_str)
+            .chain(self.betas.iter().flatten().map(String::as_str))
+            .chain(structured_outputs)
+        {
+            if !merged.iter().any(|seen| seen == beta) {
+                merged.push(beta.to_string());
+            }
+        }
+        merged
+    }
+
     /// Add a system prompt as a string.
     pub fn with_system_string(mut self, system: String) -> Self {
         self.system = Some(SystemPrompt::from_string(system));
@@ -297,8 +464,11 @@ impl MessageCreateParams {
     }
 
     /// Add top_p to the parameters.
+    ///
+    /// `top_p` must be in (0.0, 1.0].  Prefer setting only one of `temperature` and `top_p`;
+    /// [`Self::validate`] rejects requests that set both.
     pub fn with_top_p(mut self, top_p: f32) -> Result<Self, crate::Error> {
-        Self::validate_float_range(top_p, "top_p")?;
+        Self::validate_top_p(top_p)?;
         self.top_p = Some(top_p);
         Ok(self)
     }
@@ -309,13 +479,49 @@ impl MessageCreateParams {
         self
     }
 
+    /// Make sampling as repeatable as the API allows, for tests and evals.
+    ///
+    /// Sets `temperature` to 0 and clears `top_p` and `top_k`, so the model always prefers its
+    /// most likely token.  The Messages API has no seed parameter and does not promise
+    /// identical output for identical requests, so responses can still differ from run to run;
+    /// compare outputs with that in mind.  Extended thinking requires the default temperature,
+    /// so leave [`Self::thinking`] unset on deterministic requests.
+    pub fn deterministic(mut self) -> Self {
+        self.temperature = Some(0.0);
+        self.top_p = None;
+        self.top_k = None;
+        self
+    }
+
     /// Validate all parameters before sending to the API with security checks.
     ///
     /// Performs comprehensive validation including DoS prevention measures:
     /// - Limits on message count and size to prevent resource exhaustion
-    /// - Validation of all numeric ranges
+    /// - Validation of all numeric ranges, and that `temperature` and `top_p` are not both set
     /// - Security checks on string inputs
+    /// - `max_tokens` within the output limit of known models
     pub fn validate(&self) -> Result<(), crate::Error> {
+        self.validate_with_betas(&[])
+    }
+
+    /// Returns the output limit that applies to this request, if the model's limit is known.
+    fn max_output_tokens(&self, default_betas: &[String]) -> Option<u32> {
+        match self.model {
+            Model::Known(KnownModel::Claude37SonnetLatest | KnownModel::Claude37Sonnet20250219)
+                if self
+                    .merge_betas(default_betas)
+                    .iter()
+                    .any(|beta| beta == OUTPUT_128K_BETA) =>
+            {
+                Some(128_000)
+            }
+            _ => self.model.max_output_tokens(),
+        }
+    }
+
+    /// Validates the parameters as [`MessageCreateParams::validate`] does, also counting
+    /// `default_betas` sent by the client.
+    pub(crate) fn validate_with_betas(&self, default_betas: &[String]) -> Result<(), crate::Error> {
         // Basic parameter validation
         if self.max_tokens == 0 {
             return Err(crate::Error::validation(
@@ -335,6 +541,25 @@ impl MessageCreateParams {
             ));
         }
 
+        if self.model.to_string().trim().is_empty() {
+            return Err(crate::Error::validation(
+                "model must not be empty",
+                Some("model".to_string()),
+            ));
+        }
+
+        if let Some(max_output_tokens) = self.max_output_tokens(default_betas)
+            && self.max_tokens > max_output_tokens
+        {
+            return Err(crate::Error::validation(
+                format!(
+                    "max_tokens {} exceeds the output limit of {max_output_tokens} for {}",
+                    self.max_tokens, self.model
+                ),
+                Some("max_tokens".to_string()),
+            ));
+        }
+
         if self.messages.is_empty() {
             return Err(crate::Error::validation(
                 "At least one message is required",
@@ -370,14 +595,29 @@ impl MessageCreateParams {
             }
         }
 
-        // Validate floating point parameters
-        if let Some(temp) = self.temperature {
-            Self::validate_float_range(temp, "temperature")?;
+        // Tool use ids must be unique across the whole conversation, not just one turn.
+        crate::validate_tool_use_id_blocks(
+            self.messages
+                .iter()
+                .filter(|message| message.role == MessageRole::Assistant)
+                .filter_map(|message| match &message.content {
+                    MessageParamContent::Array(blocks) => Some(blocks.iter()),
+                    MessageParamContent::String(_) => None,
+                })
+                .flatten(),
+        )?;
+
+        if let Some(ref metadata) = self.metadata {
+            metadata.validate()?;
         }
-        if let Some(top_p) = self.top_p {
-            Self::validate_float_range(top_p, "top_p")?;
+
+        for key in self.extra.keys() {
+            Self::validate_extra_key(key)?;
         }
 
+        // Validate floating point parameters
+        Self::validate_sampling(self.temperature, self.top_p)?;
+
         // Validate top_k is reasonable
         if let Some(top_k) = self.top_k
             && top_k > 1000
@@ -402,6 +642,13 @@ impl MessageCreateParams {
             }
 
             for (i, seq) in stop_sequences.iter().enumerate() {
+                if seq.is_empty() {
+                    return Err(crate::Error::validation(
+                        format!("Stop sequence {i} is empty"),
+                        Some(format!("stop_sequences[{i}]")),
+                    ));
+                }
+
                 if seq.len() > MAX_STOP_SEQUENCE_LENGTH {
                     return Err(crate::Error::validation(
                         format!(
@@ -452,6 +699,11 @@ impl MessageCreateParams {
                 Some("tools".to_string()),
             ));
         }
+        for tool in self.tools.iter().flatten() {
+            if let ToolUnionParam::WebSearch20250305(web_search) = tool {
+                web_search.validate()?;
+            }
+        }
 
         // Validate thinking config with security checks
         if let Some(ref thinking) = self.thinking {
@@ -555,18 +807,106 @@ impl MessageCreateParams {
 
         false
     }
+
+    /// Returns a stable key identifying this request, for caching responses client-side.
+    ///
+    /// The key hashes the request body as it would be sent, including model, system prompt,
+    /// messages, tools, sampling settings, and extra fields, but not `stream`, so a streaming
+    /// and a non-streaming request for the same prompt share a key.  The request's own betas
+    /// are part of the key too; see [`cache_key_with_betas`](Self::cache_key_with_betas) to
+    /// include a client's default betas.  Object keys are hashed in sorted order, so the key
+    /// does not depend on the order extra fields were added, and it is the same across runs
+    /// and builds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request cannot be serialized, which only a custom `Serialize` impl in
+    /// an extra field can cause; such a request could not be sent either.
+    pub fn cache_key(&self) -> String {
+        self.cache_key_with_betas(&[])
+            .expect("a request serializes to JSON")
+    }
+
+    /// Like [`cache_key`](Self::cache_key), with the betas merged with `default_betas` as by
+    /// [`merge_betas`](Self::merge_betas).
+    ///
+    /// Returns an error if the request cannot be serialized.
+    pub fn cache_key_with_betas(&self, default_betas: &[String]) -> Result<String, crate::Error> {
+        let mut body = serde_json::to_value(self).map_err(|e| {
+            crate::Error::serialization(
+                format!("Failed to serialize request for its cache key: {e}"),
+                Some(Box::new(e)),
+            )
+        })?;
+        if let Value::Object(fields) = &mut body {
+            fields.remove("stream");
+        }
+        let betas = self.merge_betas(default_betas);
+        let mut hasher = Fnv128::default();
+        hash_canonical(&body, &mut hasher);
+        hash_canonical(&Value::from(betas), &mut hasher);
+        Ok(format!("{:032x}", hasher.0))
+    }
 }
 
-impl Default for MessageCreateParams {
+/// A 128-bit FNV-1a hasher, used for keys that must not change between builds.
+struct Fnv128(u128);
+
+impl Default for Fnv128 {
     fn default() -> Self {
-        use crate::types::KnownModel;
+        Self(0x6c62272e07bb014262b821756295c58d)
+    }
+}
 
+impl Fnv128 {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u128::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000000001000000000000000000013b);
+        }
+    }
+}
+
+/// Feeds `value` to `hasher` as compact JSON with object keys in sorted order.
+fn hash_canonical(value: &Value, hasher: &mut Fnv128) {
+    match value {
+        Value::Array(values) => {
+            hasher.write(b"[");
+            for (idx, value) in values.iter().enumerate() {
+                if idx > 0 {
+                    hasher.write(b",");
+                }
+                hash_canonical(value, hasher);
+            }
+            hasher.write(b"]");
+        }
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by(|lhs, rhs| lhs.0.cmp(rhs.0));
+            hasher.write(b"{");
+            for (idx, (key, value)) in fields.into_iter().enumerate() {
+                if idx > 0 {
+                    hasher.write(b",");
+                }
+                hasher.write(Value::String(key.clone()).to_string().as_bytes());
+                hasher.write(b":");
+                hash_canonical(value, hasher);
+            }
+            hasher.write(b"}");
+        }
+        scalar => hasher.write(scalar.to_string().as_bytes()),
+    }
+}
+
+impl Default for MessageCreateParams {
+    fn default() -> Self {
         Self {
             max_tokens: 1024,
             messages: vec![],
             model: Model::Known(KnownModel::Claude37SonnetLatest),
             metadata: None,
             output_format: None,
+            service_tier: None,
             stop_sequences: None,
             system: None,
             temperature: None,
@@ -576,6 +916,8 @@ impl Default for MessageCreateParams {
             top_k: None,
             top_p: None,
             stream: false,
+            betas: None,
+            extra: HashMap::new(),
         }
     }
 }
@@ -583,7 +925,6 @@ impl Default for MessageCreateParams {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{KnownModel, MessageRole};
     use serde_json::{json, to_value};
 
     #[test]
@@ -808,4 +1149,441 @@ mod tests {
             "params without output_format or strict tools should not require structured outputs beta"
         );
     }
+
+    fn params_with_stop_sequences(stop_sequences: Vec<String>) -> MessageCreateParams {
+        MessageCreateParams::new(
+            16,
+            vec![MessageParam::user("hi")],
+            Model::Known(KnownModel::ClaudeHaiku45),
+        )
+        .with_stop_sequences(stop_sequences)
+    }
+
+    #[test]
+    fn with_stop_sequences_accepts_str_iterators() {
+        let params = MessageCreateParams::new(
+            16,
+            vec![MessageParam::user("hi")],
+            Model::Known(KnownModel::ClaudeHaiku45),
+        )
+        .with_stop_sequences(["END", "###"]);
+        assert_eq!(
+            params.stop_sequences,
+            Some(vec!["END".to_string(), "###".to_string()])
+        );
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn stop_sequences_count_boundary() {
+        let at_limit = params_with_stop_sequences(
+            (0..MAX_STOP_SEQUENCES)
+                .map(|i| format!("stop{i}"))
+                .collect(),
+        );
+        assert!(at_limit.validate().is_ok());
+
+        let over_limit = params_with_stop_sequences(
+            (0..=MAX_STOP_SEQUENCES)
+                .map(|i| format!("stop{i}"))
+                .collect(),
+        );
+        let err = over_limit.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("stop_sequences"), "{err}");
+    }
+
+    #[test]
+    fn stop_sequences_length_boundary() {
+        let at_limit = params_with_stop_sequences(vec!["x".repeat(MAX_STOP_SEQUENCE_LENGTH)]);
+        assert!(at_limit.validate().is_ok());
+
+        let over_limit = params_with_stop_sequences(vec!["x".repeat(MAX_STOP_SEQUENCE_LENGTH + 1)]);
+        assert!(over_limit.validate().unwrap_err().is_validation());
+    }
+
+    #[test]
+    fn empty_stop_sequence_is_rejected() {
+        let params = params_with_stop_sequences(vec!["END".to_string(), String::new()]);
+        let err = params.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("stop_sequences[1]"), "{err}");
+    }
+
+    #[test]
+    fn max_tokens_within_model_limit() {
+        let mut params = MessageCreateParams::simple("Hello", KnownModel::Claude3Haiku20240307);
+        params.max_tokens = 4_096;
+        assert!(params.validate().is_ok());
+
+        params.max_tokens = 4_097;
+        let err = params.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("output limit of 4096"), "{err}");
+    }
+
+    #[test]
+    fn max_tokens_limit_allows_extended_output_beta() {
+        let mut params = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest);
+        params.max_tokens = 100_000;
+        assert!(params.validate().is_err());
+        assert!(
+            params
+                .validate_with_betas(&[OUTPUT_128K_BETA.to_string()])
+                .is_ok()
+        );
+        assert!(params.with_betas([OUTPUT_128K_BETA]).validate().is_ok());
+    }
+
+    #[test]
+    fn max_tokens_limit_skips_custom_models() {
+        let mut params = MessageCreateParams::simple("Hello", Model::custom("proxy-model"));
+        params.max_tokens = 500_000;
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn empty_custom_model_is_rejected() {
+        let params = MessageCreateParams::simple("Hello", Model::custom(""));
+        let err = params.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("model"), "{err}");
+
+        let params = MessageCreateParams::simple("Hello", Model::custom("my-fine-tune"));
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn sampling_parameter_ranges() {
+        let params = || MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45);
+        assert!(params().with_temperature(0.0).is_ok());
+        assert!(params().with_temperature(1.0).is_ok());
+        assert!(params().with_temperature(1.1).is_err());
+        assert!(params().with_temperature(-0.1).is_err());
+        assert!(params().with_temperature(f32::NAN).is_err());
+
+        assert!(params().with_top_p(1.0).is_ok());
+        assert!(params().with_top_p(0.0).is_err());
+        assert!(params().with_top_p(1.5).is_err());
+
+        // Fields set directly are checked by validate.
+        let mut direct = params();
+        direct.temperature = Some(2.0);
+        assert!(direct.validate().is_err());
+        let mut direct = params();
+        direct.top_p = Some(0.0);
+        let err = direct.validate().unwrap_err();
+        assert!(err.to_string().contains("top_p"), "{err}");
+
+        let top_k = params().with_top_k(0);
+        assert!(top_k.validate().is_ok());
+        assert!(params().with_top_k(1001).validate().is_err());
+    }
+
+    #[test]
+    fn temperature_and_top_p_are_exclusive() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45)
+            .with_temperature(0.5)
+            .unwrap()
+            .with_top_p(0.9)
+            .unwrap();
+        let err = params.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("not both"), "{err}");
+
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45)
+            .with_top_p(0.9)
+            .unwrap();
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn deterministic_pins_sampling() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45)
+            .with_top_p(0.9)
+            .unwrap()
+            .with_top_k(40)
+            .deterministic();
+        assert_eq!(params.temperature, Some(0.0));
+        assert_eq!(params.top_p, None);
+        assert_eq!(params.top_k, None);
+        let json = to_value(&params).unwrap();
+        assert_eq!(json["temperature"], json!(0.0));
+        assert!(json.get("top_p").is_none());
+        assert!(json.get("top_k").is_none());
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn with_user_id_sets_metadata() {
+        let model = Model::Known(KnownModel::ClaudeHaiku45);
+        let params = MessageCreateParams::simple("Hello", model.clone()).with_user_id("user-123");
+        assert_eq!(
+            to_value(&params).unwrap()["metadata"],
+            json!({"user_id": "user-123"})
+        );
+        assert!(params.validate().is_ok());
+
+        let params = MessageCreateParams::simple("Hello", model).with_user_id("u".repeat(300));
+        let err = params.validate().unwrap_err();
+        assert!(err.to_string().contains("metadata.user_id"), "{err}");
+    }
+
+    #[test]
+    fn extra_fields_are_merged_into_the_body() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45)
+            .with_extra("inference_geo", json!("us"))
+            .unwrap();
+        let body = to_value(&params).unwrap();
+        assert_eq!(body["inference_geo"], "us");
+        assert_eq!(body["max_tokens"], 1024);
+        assert!(body.get("extra").is_none());
+        assert!(params.validate().is_ok());
+
+        let round_trip: MessageCreateParams = serde_json::from_value(body).unwrap();
+        assert_eq!(round_trip, params);
+    }
+
+    #[test]
+    fn extra_fields_cannot_override_known_fields() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45);
+        let err = params
+            .clone()
+            .with_extra("model", json!("other"))
+            .unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("\"model\""), "{err}");
+
+        let mut direct = params;
+        direct.extra.insert("max_tokens".to_string(), json!(5));
+        assert!(direct.validate().is_err());
+    }
+
+    #[test]
+    fn merge_betas_client_only() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest);
+        let defaults = vec!["a-beta".to_string(), "b-beta".to_string()];
+        assert_eq!(params.merge_betas(&defaults), defaults);
+    }
+
+    #[test]
+    fn merge_betas_request_only() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest)
+            .with_betas(["a-beta", "a-beta", "b-beta"]);
+        assert_eq!(params.merge_betas(&[]), vec!["a-beta", "b-beta"]);
+    }
+
+    #[test]
+    fn merge_betas_client_and_request() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest)
+            .with_betas(["b-beta", "c-beta"]);
+        let defaults = vec!["a-beta".to_string(), "b-beta".to_string()];
+        assert_eq!(
+            params.merge_betas(&defaults),
+            vec!["a-beta", "b-beta", "c-beta"]
+        );
+    }
+
+    #[test]
+    fn merge_betas_adds_structured_outputs() {
+        use crate::types::OutputFormat;
+
+        let params = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest)
+            .with_output_format(OutputFormat::json_schema(json!({"type": "object"})))
+            .with_betas(["a-beta", STRUCTURED_OUTPUTS_BETA]);
+        let defaults = vec!["z-beta".to_string()];
+        assert_eq!(
+            params.merge_betas(&defaults),
+            vec!["z-beta", "a-beta", STRUCTURED_OUTPUTS_BETA]
+        );
+
+        let plain = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest);
+        assert!(plain.merge_betas(&[]).is_empty());
+    }
+
+    #[test]
+    fn cache_key_is_stable_and_distinguishes_requests() {
+        let params = || {
+            MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45)
+                .with_system("Be brief.")
+                .with_service_tier(ServiceTier::Auto)
+                .with_extra("container", json!({"b": 1, "a": 2}))
+                .unwrap()
+        };
+        let key = params().cache_key();
+        assert_eq!(key.len(), 32);
+        assert_eq!(key, params().cache_key());
+        assert_eq!(key, params().with_stream(true).cache_key());
+
+        assert_ne!(key, params().with_message("Again").cache_key());
+        assert_ne!(key, params().with_system("Be verbose.").cache_key());
+        assert_ne!(
+            key,
+            params()
+                .with_service_tier(ServiceTier::StandardOnly)
+                .cache_key()
+        );
+        assert_ne!(
+            key,
+            MessageCreateParams {
+                model: Model::Known(KnownModel::ClaudeSonnet45),
+                ..params()
+            }
+            .cache_key()
+        );
+
+        let beta = params().with_betas(["files-api-2025-04-14"]);
+        assert_ne!(key, beta.cache_key());
+        assert_eq!(
+            beta.cache_key(),
+            params()
+                .cache_key_with_betas(&["files-api-2025-04-14".to_string()])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn validate_rejects_conflicting_web_search_domains() {
+        use crate::types::WebSearchTool20250305;
+
+        let mut web_search =
+            WebSearchTool20250305::new().with_allowed_domains(vec!["docs.rs".to_string()]);
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45)
+            .with_tools(vec![ToolUnionParam::WebSearch20250305(web_search.clone())]);
+        assert!(params.validate().is_ok());
+
+        web_search.blocked_domains = Some(vec!["example.com".to_string()]);
+        let params = params.with_tools(vec![ToolUnionParam::WebSearch20250305(web_search)]);
+        assert!(params.validate().unwrap_err().is_validation());
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_tool_use_ids_across_assistant_messages() {
+        use crate::types::{ContentBlock, ToolResultBlock, ToolUseBlock};
+
+        let tool_use = |id: &str| ContentBlock::ToolUse(ToolUseBlock::new(id, "lookup", json!({})));
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45).with_message(
+            MessageParam::new_with_blocks(
+                vec![tool_use("toolu_a"), tool_use("toolu_b")],
+                MessageRole::Assistant,
+            ),
+        );
+        assert!(params.validate().is_ok());
+
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45).with_message(
+            MessageParam::new_with_blocks(
+                vec![tool_use("toolu_a"), tool_use("toolu_a")],
+                MessageRole::Assistant,
+            ),
+        );
+        let err = params.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("toolu_a"), "{err}");
+
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45)
+            .with_message(MessageParam::new_with_blocks(
+                vec![tool_use("toolu_a")],
+                MessageRole::Assistant,
+            ))
+            .with_message(MessageParam::new_with_blocks(
+                vec![ContentBlock::ToolResult(ToolResultBlock::new(
+                    "toolu_a".to_string(),
+                ))],
+                MessageRole::User,
+            ))
+            .with_message(MessageParam::new_with_blocks(
+                vec![tool_use("toolu_a")],
+                MessageRole::Assistant,
+            ));
+        let err = params.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("toolu_a"), "{err}");
+    }
+
+    #[test]
+    fn minimal_request_omits_absent_fields() {
+        use crate::types::{ContentBlock, ToolResultBlock, ToolUseBlock};
+
+        let params = MessageCreateParams::new(
+            16,
+            vec![
+                MessageParam::user("What is 2 + 2?"),
+                MessageParam::new_with_blocks(
+                    vec![
+                        ContentBlock::Text(TextBlock::new("Let me add.")),
+                        ContentBlock::ToolUse(ToolUseBlock::new(
+                            "toolu_1",
+                            "add",
+                            json!({"a": 2, "b": 2}),
+                        )),
+                    ],
+                    MessageRole::Assistant,
+                ),
+                MessageParam::new_with_blocks(
+                    vec![ContentBlock::ToolResult(
+                        ToolResultBlock::new("toolu_1".to_string())
+                            .with_string_content("4".to_string()),
+                    )],
+                    MessageRole::User,
+                ),
+            ],
+            Model::Known(KnownModel::ClaudeHaiku45),
+        )
+        .with_tools(vec![])
+        .with_stop_sequences(Vec::<String>::new());
+
+        let json = to_value(&params).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "max_tokens": 16,
+                "messages": [
+                    {"role": "user", "content": "What is 2 + 2?"},
+                    {"role": "assistant", "content": [
+                        {"type": "text", "text": "Let me add."},
+                        {"type": "tool_use", "id": "toolu_1", "name": "add", "input": {"a": 2, "b": 2}}
+                    ]},
+                    {"role": "user", "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_1", "content": "4"}
+                    ]}
+                ],
+                "model": "claude-haiku-4-5",
+                "stream": false
+            })
+        );
+        assert!(!json.to_string().contains("null"), "{json}");
+    }
+
+    #[test]
+    fn service_tier_is_serialized() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::ClaudeHaiku45);
+        assert!(to_value(&params).unwrap().get("service_tier").is_none());
+
+        for (tier, name) in [
+            (ServiceTier::Auto, "auto"),
+            (ServiceTier::StandardOnly, "standard_only"),
+            (ServiceTier::Priority, "priority"),
+        ] {
+            let params = params.clone().with_service_tier(tier);
+            let body = to_value(&params).unwrap();
+            assert_eq!(body["service_tier"], name);
+            assert!(body.get("extra").is_none());
+            let round_trip: MessageCreateParams = serde_json::from_value(body).unwrap();
+            assert_eq!(round_trip.service_tier, Some(tier));
+            assert!(round_trip.extra.is_empty());
+        }
+
+        let err = params
+            .with_extra("service_tier", json!("auto"))
+            .unwrap_err();
+        assert!(err.is_validation());
+    }
+
+    #[test]
+    fn betas_are_not_serialized() {
+        let params = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest)
+            .with_betas(["a-beta"]);
+        let json = to_value(&params).unwrap();
+        assert!(json.get("betas").is_none());
+    }
 }
@@ -82,26 +82,6 @@ pub struct MessageCreateTemplate {
 }
 
 impl MessageCreateTemplate {
-    /// Helper function to validate a float value is within the 0.0-1.0 range.
-    #[inline]
-    fn validate_float_range(value: f32, field_name: &str) -> Result<(), crate::Error> {
-        if (0.0..=1.0).contains(&value) && value.is_finite() {
-            return Ok(());
-        }
-
-        if value.is_nan() {
-            return Err(crate::Error::validation(
-                format!("{field_name} cannot be NaN"),
-                Some(field_name.to_string()),
-            ));
-        }
-
-        Err(crate::Error::validation(
-            format!("{field_name} must be between 0.0 and 1.0, got {value}"),
-            Some(field_name.to_string()),
-        ))
-    }
-
     /// Create a new empty template.
     pub fn new() -> Self {
         Self::default()
@@ -145,7 +125,7 @@ impl MessageCreateTemplate {
 
     /// Set the temperature field.
     pub fn with_temperature(mut self, temperature: f32) -> Result<Self, crate::Error> {
-        Self::validate_float_range(temperature, "temperature")?;
+        MessageCreateParams::validate_sampling(Some(temperature), None)?;
         self.temperature = Some(temperature);
         Ok(self)
     }
@@ -174,9 +154,9 @@ impl MessageCreateTemplate {
         self
     }
 
-    /// Set the top_p field.
+    /// Set the top_p field, which must be in (0.0, 1.0].
     pub fn with_top_p(mut self, top_p: f32) -> Result<Self, crate::Error> {
-        Self::validate_float_range(top_p, "top_p")?;
+        MessageCreateParams::validate_sampling(None, Some(top_p))?;
         self.top_p = Some(top_p);
         Ok(self)
     }
@@ -235,7 +215,8 @@ impl MessageCreateTemplate {
     ///
     /// Fields that are `Some` in the template will override the corresponding
     /// fields in the params. Fields that are `None` in the template will leave
-    /// the params unchanged.
+    /// the params unchanged, so a template without `messages` keeps the
+    /// conversation already in `params`.
     pub fn apply(self, mut params: MessageCreateParams) -> MessageCreateParams {
         if let Some(max_tokens) = self.max_tokens {
             params.max_tokens = max_tokens;
@@ -344,6 +325,7 @@ mod tests {
         let result = MessageCreateTemplate::new().with_top_p(-0.1);
 
         assert!(result.is_err());
+        assert!(MessageCreateTemplate::new().with_top_p(0.0).is_err());
     }
 
     #[test]
@@ -402,6 +384,38 @@ mod tests {
         assert!(params.system.is_some());
     }
 
+    #[test]
+    fn template_apply_preserves_messages() {
+        use crate::types::{ThinkingConfig, ToolUnionParam};
+
+        let tools = vec![ToolUnionParam::new_custom_tool(
+            "lookup".to_string(),
+            serde_json::json!({"type": "object"}),
+        )];
+        let template = MessageCreateTemplate::new()
+            .with_model(KnownModel::ClaudeHaiku45)
+            .with_max_tokens(512)
+            .with_system("Be terse.")
+            .with_tools(tools.clone())
+            .with_thinking(ThinkingConfig::enabled(1024))
+            .with_temperature(0.2)
+            .unwrap();
+
+        let params = MessageCreateParams::simple("Hello", KnownModel::Claude37SonnetLatest)
+            .with_message(MessageParam::assistant("Hi there."))
+            .with_message(MessageParam::user("What's new?"));
+        let messages = params.messages.clone();
+        let params = template.apply(params);
+
+        assert_eq!(params.messages, messages);
+        assert_eq!(params.model, Model::Known(KnownModel::ClaudeHaiku45));
+        assert_eq!(params.max_tokens, 512);
+        assert_eq!(params.system, Some(SystemPrompt::from("Be terse.")));
+        assert_eq!(params.tools, Some(tools));
+        assert_eq!(params.thinking, Some(ThinkingConfig::enabled(1024)));
+        assert_eq!(params.temperature, Some(0.2));
+    }
+
     #[test]
     fn template_merge_overrides_fields() {
         let base = MessageCreateTemplate::new()
@@ -1,6 +1,8 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-use crate::types::ContentBlock;
+use crate::types::{ContentBlock, ThinkingBlock};
 
 /// The content of a message, which can be either a string or an array of content blocks.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -59,6 +61,87 @@ impl MessageParam {
     pub fn assistant(content: impl Into<String>) -> Self {
         Self::new_with_string(content.into(), MessageRole::Assistant)
     }
+
+    /// Create an assistant `MessageParam` that starts with `thinking`, followed by `blocks`.
+    ///
+    /// Use this to replay an assistant turn that used extended thinking, for example when
+    /// resending a tool result: the thinking block's signature must be sent back verbatim.
+    /// Returns a validation error if the signature is empty or if `blocks` contains content
+    /// that only a user may send (tool results, images, or documents).
+    pub fn assistant_with_thinking(
+        thinking: ThinkingBlock,
+        blocks: Vec<ContentBlock>,
+    ) -> Result<Self, crate::Error> {
+        if thinking.signature.is_empty() {
+            return Err(crate::Error::validation(
+                "thinking block must carry the signature returned by the API",
+                Some("thinking.signature".to_string()),
+            ));
+        }
+        if let Some(idx) = blocks
+            .iter()
+            .position(|block| block.is_tool_result() || block.is_image() || block.is_document())
+        {
+            return Err(crate::Error::validation(
+                format!("block {idx} cannot appear in an assistant message"),
+                Some(format!("blocks[{idx}]")),
+            ));
+        }
+        let mut content = Vec::with_capacity(blocks.len() + 1);
+        content.push(ContentBlock::Thinking(thinking));
+        content.extend(blocks);
+        Ok(Self::new_with_blocks(content, MessageRole::Assistant))
+    }
+
+    /// Returns a one-line summary of this message, truncating text to `max_len` characters.
+    ///
+    /// The summary has the form `role: "text" [+N blocks]`, where the text is the string
+    /// content or the first text block, and `N` counts the remaining blocks.  Messages with
+    /// no text render as `role: [N blocks]`.
+    pub fn summary(&self, max_len: usize) -> String {
+        let (text, other_blocks) = match &self.content {
+            MessageParamContent::String(text) => (Some(text.as_str()), 0),
+            MessageParamContent::Array(blocks) => {
+                let text = blocks.iter().find_map(|block| block.as_text());
+                let others = blocks.len() - usize::from(text.is_some());
+                (text.map(|block| block.text.as_str()), others)
+            }
+        };
+        let blocks = |n: usize| if n == 1 { "block" } else { "blocks" };
+        match text {
+            Some(text) => {
+                let mut summary = format!("{}: {:?}", self.role, truncate(text, max_len));
+                if other_blocks > 0 {
+                    summary += &format!(" [+{other_blocks} {}]", blocks(other_blocks));
+                }
+                summary
+            }
+            None => format!("{}: [{other_blocks} {}]", self.role, blocks(other_blocks)),
+        }
+    }
+}
+
+/// Truncates `text` to at most `max_len` characters, marking truncation with "...".
+fn truncate(text: &str, max_len: usize) -> String {
+    match text.char_indices().nth(max_len) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+impl fmt::Display for MessageParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary(usize::MAX))
+    }
+}
+
+impl fmt::Display for MessageRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageRole::User => write!(f, "user"),
+            MessageRole::Assistant => write!(f, "assistant"),
+        }
+    }
 }
 
 impl From<&str> for MessageParam {
@@ -73,6 +156,10 @@ impl From<String> for MessageParam {
     }
 }
 
+/// Converts a response into an assistant message for the next request.
+///
+/// The content blocks are carried over unchanged, so thinking signatures and redacted thinking
+/// data are resent exactly as the API returned them.
 impl From<crate::types::Message> for MessageParam {
     fn from(message: crate::types::Message) -> Self {
         Self::new_with_blocks(message.content, MessageRole::Assistant)
@@ -88,7 +175,10 @@ impl<T: AsRef<str>> From<T> for MessageParamContent {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ImageBlock, KnownModel, Message, Model, TextBlock, Usage};
+    use crate::types::{
+        ImageBlock, KnownModel, Message, Model, RedactedThinkingBlock, TextBlock, ToolResultBlock,
+        ToolUseBlock, Usage,
+    };
     use serde_json::{json, to_value};
 
     #[test]
@@ -268,4 +358,97 @@ mod tests {
             _ => panic!("Expected Array variant"),
         }
     }
+
+    #[test]
+    fn summary_of_string_content() {
+        let message = MessageParam::user("hello");
+        assert_eq!(message.summary(80), r#"user: "hello""#);
+        assert_eq!(message.to_string(), r#"user: "hello""#);
+
+        let message = MessageParam::assistant("a long reply that goes on");
+        assert_eq!(message.summary(6), r#"assistant: "a long...""#);
+    }
+
+    #[test]
+    fn summary_of_array_content() {
+        let message = MessageParam::new_with_blocks(
+            vec![
+                ContentBlock::Text(TextBlock::new("hello")),
+                ContentBlock::ToolUse(crate::types::ToolUseBlock::new("t1", "bash", json!({}))),
+                ContentBlock::ToolUse(crate::types::ToolUseBlock::new("t2", "bash", json!({}))),
+            ],
+            MessageRole::User,
+        );
+        assert_eq!(message.summary(80), r#"user: "hello" [+2 blocks]"#);
+
+        let message = MessageParam::new_with_blocks(
+            vec![ContentBlock::ToolResult(
+                crate::types::ToolResultBlock::new("t1".to_string()),
+            )],
+            MessageRole::User,
+        );
+        assert_eq!(message.to_string(), "user: [1 block]");
+    }
+
+    #[test]
+    fn assistant_with_thinking_puts_thinking_first() {
+        let thinking = ThinkingBlock::new("let me check", "sig-123");
+        let tool_use = ToolUseBlock::new("toolu_1", "lookup", json!({"q": "x"}));
+        let message = MessageParam::assistant_with_thinking(
+            thinking.clone(),
+            vec![ContentBlock::ToolUse(tool_use.clone())],
+        )
+        .unwrap();
+        assert_eq!(message.role, MessageRole::Assistant);
+        assert_eq!(
+            message.content,
+            MessageParamContent::Array(vec![
+                ContentBlock::Thinking(thinking),
+                ContentBlock::ToolUse(tool_use),
+            ])
+        );
+    }
+
+    #[test]
+    fn assistant_with_thinking_validates() {
+        let err = MessageParam::assistant_with_thinking(ThinkingBlock::new("hmm", ""), vec![])
+            .unwrap_err();
+        assert!(err.is_validation());
+
+        let err = MessageParam::assistant_with_thinking(
+            ThinkingBlock::new("hmm", "sig"),
+            vec![
+                ContentBlock::Text(TextBlock::new("ok")),
+                ContentBlock::ToolResult(ToolResultBlock::new("toolu_1".to_string())),
+            ],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("blocks[1]"), "{err}");
+    }
+
+    #[test]
+    fn redacted_thinking_round_trips_byte_for_byte() {
+        let data = "EmwKAhgBEgy3va3pzix/LafPsn4aDFIT2Xlxh0L5L8rLVyIwxtE3rAFBa8cr3qpP+/==";
+        let message = Message::new(
+            "msg_1".to_string(),
+            vec![
+                ContentBlock::RedactedThinking(RedactedThinkingBlock::new(data)),
+                ContentBlock::Text(TextBlock::new("answer")),
+            ],
+            Model::Known(KnownModel::ClaudeSonnet45),
+            Usage::new(1, 1),
+        );
+
+        let param = MessageParam::from(message);
+        let json = serde_json::to_string(&param).unwrap();
+        let round_tripped: MessageParam = serde_json::from_str(&json).unwrap();
+
+        let MessageParamContent::Array(blocks) = round_tripped.content else {
+            panic!("expected block content");
+        };
+        let ContentBlock::RedactedThinking(redacted) = &blocks[0] else {
+            panic!("expected redacted thinking first, got {:?}", blocks[0]);
+        };
+        assert_eq!(redacted.data.as_bytes(), data.as_bytes());
+    }
 }
@@ -1,4 +1,8 @@
-use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
 use crate::types::{
     ContentBlockDeltaEvent, ContentBlockStartEvent, ContentBlockStopEvent, MessageDeltaEvent,
@@ -11,52 +15,120 @@ use crate::types::{
 /// messages from the Anthropic API. Events are delivered in a specific order:
 /// message_start, then potentially multiple content_block events, and finally
 /// message_stop.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(tag = "type")]
+#[derive(Debug, Clone, PartialEq)]
 pub enum MessageStreamEvent {
     /// A periodic ping event to keep the connection alive.
     ///
     /// These events have no payload and can be safely ignored.
-    #[serde(rename = "ping")]
     Ping,
 
     /// Indicates the start of a new message in the stream.
     ///
     /// This event contains the initial message metadata including ID, model,
     /// role, and initial usage statistics.
-    #[serde(rename = "message_start")]
     MessageStart(MessageStartEvent),
 
     /// Provides incremental updates to the message being generated.
     ///
     /// This includes updates to stop_reason, stop_sequence, and usage statistics.
-    #[serde(rename = "message_delta")]
     MessageDelta(MessageDeltaEvent),
 
     /// Marks the beginning of a new content block within the message.
     ///
     /// Content blocks can be text, tool_use, or other content types.
-    #[serde(rename = "content_block_start")]
     ContentBlockStart(ContentBlockStartEvent),
 
     /// Provides incremental updates to the current content block.
     ///
     /// For text blocks, this contains partial text. For tool_use blocks,
     /// this contains partial JSON input.
-    #[serde(rename = "content_block_delta")]
     ContentBlockDelta(ContentBlockDeltaEvent),
 
     /// Indicates that the current content block is complete.
     ///
     /// After this event, either a new content_block_start or message_stop will follow.
-    #[serde(rename = "content_block_stop")]
     ContentBlockStop(ContentBlockStopEvent),
 
     /// Marks the end of the message stream.
     ///
     /// This is always the final event in a successful stream.
-    #[serde(rename = "message_stop")]
     MessageStop(MessageStopEvent),
+
+    /// An event of a type this version of the crate does not recognize.
+    ///
+    /// The API may add event types at any time; they are surfaced here instead of failing the
+    /// stream, and can be ignored like [`MessageStreamEvent::Ping`].
+    Unknown {
+        /// The event's `type`.
+        r#type: String,
+
+        /// The event's payload, exactly as received.
+        raw: Value,
+    },
+}
+
+/// The event types with a dedicated [`MessageStreamEvent`] variant, tagged by `type`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum KnownEvent<'a> {
+    Ping,
+    MessageStart(Cow<'a, MessageStartEvent>),
+    MessageDelta(Cow<'a, MessageDeltaEvent>),
+    ContentBlockStart(Cow<'a, ContentBlockStartEvent>),
+    ContentBlockDelta(Cow<'a, ContentBlockDeltaEvent>),
+    ContentBlockStop(Cow<'a, ContentBlockStopEvent>),
+    MessageStop(Cow<'a, MessageStopEvent>),
+}
+
+const KNOWN_EVENT_TYPES: &[&str] = &[
+    "ping",
+    "message_start",
+    "message_delta",
+    "content_block_start",
+    "content_block_delta",
+    "content_block_stop",
+    "message_stop",
+];
+
+impl Serialize for MessageStreamEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let known = match self {
+            Self::Ping => KnownEvent::Ping,
+            Self::MessageStart(event) => KnownEvent::MessageStart(Cow::Borrowed(event)),
+            Self::MessageDelta(event) => KnownEvent::MessageDelta(Cow::Borrowed(event)),
+            Self::ContentBlockStart(event) => KnownEvent::ContentBlockStart(Cow::Borrowed(event)),
+            Self::ContentBlockDelta(event) => KnownEvent::ContentBlockDelta(Cow::Borrowed(event)),
+            Self::ContentBlockStop(event) => KnownEvent::ContentBlockStop(Cow::Borrowed(event)),
+            Self::MessageStop(event) => KnownEvent::MessageStop(Cow::Borrowed(event)),
+            Self::Unknown { raw, .. } => return raw.serialize(serializer),
+        };
+        known.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageStreamEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        let Some(r#type) = raw.get("type").and_then(Value::as_str) else {
+            return Err(D::Error::missing_field("type"));
+        };
+        if !KNOWN_EVENT_TYPES.contains(&r#type) {
+            return Ok(Self::Unknown {
+                r#type: r#type.to_string(),
+                raw,
+            });
+        }
+        let known = KnownEvent::deserialize(raw).map_err(D::Error::custom)?;
+        Ok(match known {
+            KnownEvent::Ping => Self::Ping,
+            KnownEvent::MessageStart(event) => Self::MessageStart(event.into_owned()),
+            KnownEvent::MessageDelta(event) => Self::MessageDelta(event.into_owned()),
+            KnownEvent::ContentBlockStart(event) => Self::ContentBlockStart(event.into_owned()),
+            KnownEvent::ContentBlockDelta(event) => Self::ContentBlockDelta(event.into_owned()),
+            KnownEvent::ContentBlockStop(event) => Self::ContentBlockStop(event.into_owned()),
+            KnownEvent::MessageStop(event) => Self::MessageStop(event.into_owned()),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -170,4 +242,43 @@ mod tests {
             _ => panic!("Expected ContentBlockStop variant"),
         }
     }
+
+    #[test]
+    fn unknown_event_type_is_preserved() {
+        let json = json!({
+            "type": "message_checkpoint",
+            "checkpoint": {"id": 7}
+        });
+
+        let event: MessageStreamEvent = from_value(json.clone()).unwrap();
+        assert_eq!(
+            event,
+            MessageStreamEvent::Unknown {
+                r#type: "message_checkpoint".to_string(),
+                raw: json.clone(),
+            }
+        );
+        assert_eq!(serde_json::to_value(&event).unwrap(), json);
+    }
+
+    #[test]
+    fn known_event_round_trip() {
+        let json = json!({"type": "content_block_stop", "index": 2});
+        let event: MessageStreamEvent = from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&event).unwrap(), json);
+
+        let ping: MessageStreamEvent = from_value(json!({"type": "ping"})).unwrap();
+        assert_eq!(ping, MessageStreamEvent::Ping);
+        assert_eq!(
+            serde_json::to_value(&ping).unwrap(),
+            json!({"type": "ping"})
+        );
+    }
+
+    #[test]
+    fn malformed_known_event_is_an_error() {
+        let result = from_value::<MessageStreamEvent>(json!({"type": "content_block_stop"}));
+        assert!(result.is_err());
+        assert!(from_value::<MessageStreamEvent>(json!({"index": 0})).is_err());
+    }
 }
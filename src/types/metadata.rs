@@ -15,6 +15,9 @@ pub struct Metadata {
 }
 
 impl Metadata {
+    /// The longest `user_id` the API accepts, in characters.
+    pub const MAX_USER_ID_LENGTH: usize = 256;
+
     /// Creates a new empty Metadata instance
     pub fn new() -> Self {
         Self::default()
@@ -26,6 +29,38 @@ impl Metadata {
             user_id: Some(user_id.into()),
         }
     }
+
+    /// Creates a new Metadata instance with the specified user_id, validating it first.
+    pub fn try_with_user_id<S: Into<String>>(user_id: S) -> Result<Self, crate::Error> {
+        let metadata = Self::with_user_id(user_id);
+        metadata.validate()?;
+        Ok(metadata)
+    }
+
+    /// Checks that `user_id`, if set, is non-empty and at most [`Self::MAX_USER_ID_LENGTH`]
+    /// characters.
+    pub fn validate(&self) -> Result<(), crate::Error> {
+        let Some(user_id) = &self.user_id else {
+            return Ok(());
+        };
+        if user_id.is_empty() {
+            return Err(crate::Error::validation(
+                "metadata.user_id must not be empty",
+                Some("metadata.user_id".to_string()),
+            ));
+        }
+        let len = user_id.chars().count();
+        if len > Self::MAX_USER_ID_LENGTH {
+            return Err(crate::Error::validation(
+                format!(
+                    "metadata.user_id is {len} characters, exceeding the limit of {}",
+                    Self::MAX_USER_ID_LENGTH
+                ),
+                Some("metadata.user_id".to_string()),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -46,6 +81,21 @@ mod tests {
         assert_eq!(json, serde_json::json!({"user_id":"user-123"}));
     }
 
+    #[test]
+    fn user_id_length_is_validated() {
+        let longest = "u".repeat(Metadata::MAX_USER_ID_LENGTH);
+        let metadata = Metadata::try_with_user_id(longest.clone()).unwrap();
+        assert_eq!(metadata.user_id, Some(longest));
+
+        let err =
+            Metadata::try_with_user_id("u".repeat(Metadata::MAX_USER_ID_LENGTH + 1)).unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("257 characters"), "{err}");
+
+        assert!(Metadata::try_with_user_id("").is_err());
+        assert!(Metadata::new().validate().is_ok());
+    }
+
     #[test]
     fn deserialization() {
         let json = serde_json::json!({"user_id":"user-123"});
@@ -1,12 +1,19 @@
 mod base64_image_source;
 mod base64_pdf_source;
+mod batch_create_params;
+mod batch_list_params;
+mod batch_list_response;
+mod batch_result;
 mod cache_control_ephemeral;
 mod citation_char_location;
 mod citation_content_block_location;
 mod citation_page_location;
+mod citation_span;
 mod citation_web_search_result_location;
 mod citations_config;
 mod citations_delta;
+mod compaction_block;
+mod compaction_delta;
 mod content;
 mod content_block;
 mod content_block_delta;
@@ -15,9 +22,12 @@ mod content_block_source_param;
 mod content_block_start_event;
 mod content_block_stop_event;
 mod document_block;
+mod file_image_source;
+mod file_pdf_source;
 mod image_block;
 mod input_json_delta;
 mod message;
+mod message_batch;
 mod message_count_tokens_params;
 mod message_create_params;
 mod message_create_template;
@@ -38,6 +48,7 @@ mod plain_text_source;
 mod redacted_thinking_block;
 mod server_tool_usage;
 mod server_tool_use_block;
+mod service_tier;
 mod signature_delta;
 mod stop_reason;
 mod system_prompt;
@@ -47,6 +58,7 @@ mod text_delta;
 mod thinking_block;
 mod thinking_config;
 mod thinking_delta;
+mod token_rates;
 mod tool_bash_20241022;
 mod tool_bash_20250124;
 mod tool_choice;
@@ -69,13 +81,20 @@ mod web_search_tool_result_error;
 // Exports
 pub use base64_image_source::{Base64ImageSource, ImageMediaType};
 pub use base64_pdf_source::Base64PdfSource;
+pub use batch_create_params::{BatchCreateParams, BatchRequest};
+pub use batch_list_params::BatchListParams;
+pub use batch_list_response::BatchListResponse;
+pub use batch_result::{BatchResult, BatchResultEntry, parse_batch_results_jsonl};
 pub use cache_control_ephemeral::CacheControlEphemeral;
 pub use citation_char_location::CitationCharLocation;
 pub use citation_content_block_location::CitationContentBlockLocation;
 pub use citation_page_location::CitationPageLocation;
+pub use citation_span::CitationSpan;
 pub use citation_web_search_result_location::CitationWebSearchResultLocation;
 pub use citations_config::CitationsConfig;
 pub use citations_delta::{Citation, CitationsDelta};
+pub use compaction_block::CompactionBlock;
+pub use compaction_delta::CompactionDelta;
 pub use content::Content;
 pub use content_block::ContentBlock;
 pub use content_block_delta::ContentBlockDelta;
@@ -84,9 +103,12 @@ pub use content_block_source_param::{ContentBlockSourceContent, ContentBlockSour
 pub use content_block_start_event::ContentBlockStartEvent;
 pub use content_block_stop_event::ContentBlockStopEvent;
 pub use document_block::{DocumentBlock, DocumentSource};
+pub use file_image_source::FileImageSource;
+pub use file_pdf_source::FilePdfSource;
 pub use image_block::{ImageBlock, ImageSource};
 pub use input_json_delta::InputJsonDelta;
 pub use message::Message;
+pub use message_batch::{BatchProcessingStatus, BatchProgress, MessageBatch, RequestCounts};
 pub use message_count_tokens_params::MessageCountTokensParams;
 pub use message_create_params::MessageCreateParams;
 pub use message_create_template::MessageCreateTemplate;
@@ -107,6 +129,7 @@ pub use plain_text_source::PlainTextSource;
 pub use redacted_thinking_block::RedactedThinkingBlock;
 pub use server_tool_usage::ServerToolUsage;
 pub use server_tool_use_block::ServerToolUseBlock;
+pub use service_tier::{ServiceTier, UsageServiceTier};
 pub use signature_delta::SignatureDelta;
 pub use stop_reason::StopReason;
 pub use system_prompt::SystemPrompt;
@@ -116,6 +139,7 @@ pub use text_delta::TextDelta;
 pub use thinking_block::ThinkingBlock;
 pub use thinking_config::ThinkingConfig;
 pub use thinking_delta::ThinkingDelta;
+pub use token_rates::TokenRates;
 pub use tool_bash_20241022::ToolBash20241022;
 pub use tool_bash_20250124::ToolBash20250124;
 pub use tool_choice::ToolChoice;
@@ -134,3 +158,8 @@ pub use web_search_tool_20250305::{UserLocation, WebSearchTool20250305};
 pub use web_search_tool_result_block::WebSearchToolResultBlock;
 pub use web_search_tool_result_block_content::WebSearchToolResultBlockContent;
 pub use web_search_tool_result_error::{WebSearchErrorCode, WebSearchToolResultError};
+
+/// Returns true for `None` and for an empty vector, both of which the API treats as absent.
+pub(crate) fn is_none_or_empty<T>(value: &Option<Vec<T>>) -> bool {
+    value.as_ref().is_none_or(Vec::is_empty)
+}
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize, Serializer};
 use std::fmt;
 use std::str::FromStr;
 
+use crate::types::TokenRates;
+
 /// Represents an Anthropic model identifier.
 ///
 /// This can be a predefined model version or a custom string value
@@ -150,6 +152,186 @@ impl<'de> Deserialize<'de> for Model {
     }
 }
 
+impl Model {
+    /// Creates a model from an arbitrary identifier, such as a fine-tuned or proxied model.
+    ///
+    /// The identifier is sent to the API verbatim, even if it names a known model.
+    pub fn custom(id: impl Into<String>) -> Self {
+        Model::Custom(id.into())
+    }
+
+    /// Returns true if the model supports extended thinking.
+    ///
+    /// Custom models are assumed not to support thinking.
+    pub fn supports_thinking(&self) -> bool {
+        match self {
+            Model::Known(known) => known.supports_thinking(),
+            Model::Custom(_) => false,
+        }
+    }
+
+    /// Returns true if the model accepts image input.
+    ///
+    /// Custom models are assumed to support vision.
+    pub fn supports_vision(&self) -> bool {
+        match self {
+            Model::Known(known) => known.supports_vision(),
+            Model::Custom(_) => true,
+        }
+    }
+
+    /// Returns the context window of the model in tokens.
+    ///
+    /// Custom models fall back to [`KnownModel::DEFAULT_CONTEXT_WINDOW`].
+    pub fn context_window(&self) -> u32 {
+        match self {
+            Model::Known(known) => known.context_window(),
+            Model::Custom(_) => KnownModel::DEFAULT_CONTEXT_WINDOW,
+        }
+    }
+
+    /// Returns the context window of the model in tokens when sending `betas`.
+    ///
+    /// See [`KnownModel::context_window_with_beta`].  Custom models fall back to
+    /// [`KnownModel::DEFAULT_CONTEXT_WINDOW`] regardless of betas.
+    pub fn context_window_with_beta(&self, betas: &[String]) -> u32 {
+        match self {
+            Model::Known(known) => known.context_window_with_beta(betas),
+            Model::Custom(_) => KnownModel::DEFAULT_CONTEXT_WINDOW,
+        }
+    }
+
+    /// Returns the most output tokens the model will produce in one response.
+    ///
+    /// Returns `None` for custom models, whose limits are unknown.
+    pub fn max_output_tokens(&self) -> Option<u32> {
+        match self {
+            Model::Known(known) => Some(known.max_output_tokens()),
+            Model::Custom(_) => None,
+        }
+    }
+
+    /// Returns the list price of the model.
+    ///
+    /// Custom models fall back to [`KnownModel::FALLBACK_PRICING`].
+    pub fn default_pricing(&self) -> TokenRates {
+        match self {
+            Model::Known(known) => known.default_pricing(),
+            Model::Custom(_) => KnownModel::FALLBACK_PRICING,
+        }
+    }
+}
+
+impl KnownModel {
+    /// The context window shared by all currently known models.
+    pub const DEFAULT_CONTEXT_WINDOW: u32 = 200_000;
+
+    /// The beta that raises the context window of supported models to
+    /// [`Self::EXTENDED_CONTEXT_WINDOW`].
+    pub const CONTEXT_1M_BETA: &'static str = "context-1m-2025-08-07";
+
+    /// The context window of models that support the [`Self::CONTEXT_1M_BETA`] beta.
+    pub const EXTENDED_CONTEXT_WINDOW: u32 = 1_000_000;
+
+    /// Pricing used for models whose price is not known (Sonnet-class list price).
+    pub const FALLBACK_PRICING: TokenRates = TokenRates::new(300, 1500, 375, 30);
+
+    /// Returns true if the model supports extended thinking.
+    pub fn supports_thinking(&self) -> bool {
+        !matches!(
+            self,
+            KnownModel::Claude3OpusLatest
+                | KnownModel::Claude3Opus20240229
+                | KnownModel::Claude3Haiku20240307
+        )
+    }
+
+    /// Returns true if the model accepts image input.
+    pub fn supports_vision(&self) -> bool {
+        true
+    }
+
+    /// Returns the context window of the model in tokens.
+    pub fn context_window(&self) -> u32 {
+        Self::DEFAULT_CONTEXT_WINDOW
+    }
+
+    /// Returns the context window of the model in tokens when sending `betas`.
+    ///
+    /// Claude Sonnet 4 and 4.5 accept [`Self::EXTENDED_CONTEXT_WINDOW`] tokens when `betas`
+    /// contains [`Self::CONTEXT_1M_BETA`]; otherwise this is [`Self::context_window`].
+    pub fn context_window_with_beta(&self, betas: &[String]) -> u32 {
+        let supports_1m = matches!(
+            self,
+            KnownModel::ClaudeSonnet420250514
+                | KnownModel::ClaudeSonnet40
+                | KnownModel::Claude4Sonnet20250514
+                | KnownModel::ClaudeSonnet45
+                | KnownModel::ClaudeSonnet4520250929
+        );
+        if supports_1m && betas.iter().any(|beta| beta == Self::CONTEXT_1M_BETA) {
+            Self::EXTENDED_CONTEXT_WINDOW
+        } else {
+            self.context_window()
+        }
+    }
+
+    /// Returns the most output tokens the model will produce in one response.
+    ///
+    /// This is the standard limit; Claude 3.7 Sonnet accepts up to 128,000 with the
+    /// `output-128k-2025-02-19` beta.
+    pub fn max_output_tokens(&self) -> u32 {
+        match self {
+            KnownModel::ClaudeOpus40
+            | KnownModel::ClaudeOpus420250514
+            | KnownModel::Claude4Opus20250514
+            | KnownModel::ClaudeOpus4120250805 => 32_000,
+            KnownModel::Claude3OpusLatest
+            | KnownModel::Claude3Opus20240229
+            | KnownModel::Claude3Haiku20240307 => 4_096,
+            KnownModel::ClaudeOpus4520251101
+            | KnownModel::ClaudeOpus45
+            | KnownModel::Claude37SonnetLatest
+            | KnownModel::Claude37Sonnet20250219
+            | KnownModel::ClaudeHaiku45
+            | KnownModel::ClaudeHaiku4520251001
+            | KnownModel::ClaudeSonnet420250514
+            | KnownModel::ClaudeSonnet40
+            | KnownModel::Claude4Sonnet20250514
+            | KnownModel::ClaudeSonnet45
+            | KnownModel::ClaudeSonnet4520250929 => 64_000,
+        }
+    }
+
+    /// Returns the list price of the model in micro-cents per token.
+    ///
+    /// Cache creation is priced at the five-minute cache write rate.
+    pub fn default_pricing(&self) -> TokenRates {
+        match self {
+            KnownModel::ClaudeOpus4520251101 | KnownModel::ClaudeOpus45 => {
+                TokenRates::new(500, 2500, 625, 50)
+            }
+            KnownModel::ClaudeOpus40
+            | KnownModel::ClaudeOpus420250514
+            | KnownModel::Claude4Opus20250514
+            | KnownModel::ClaudeOpus4120250805
+            | KnownModel::Claude3OpusLatest
+            | KnownModel::Claude3Opus20240229 => TokenRates::new(1500, 7500, 1875, 150),
+            KnownModel::Claude37SonnetLatest
+            | KnownModel::Claude37Sonnet20250219
+            | KnownModel::ClaudeSonnet420250514
+            | KnownModel::ClaudeSonnet40
+            | KnownModel::Claude4Sonnet20250514
+            | KnownModel::ClaudeSonnet45
+            | KnownModel::ClaudeSonnet4520250929 => TokenRates::new(300, 1500, 375, 30),
+            KnownModel::ClaudeHaiku45 | KnownModel::ClaudeHaiku4520251001 => {
+                TokenRates::new(100, 500, 125, 10)
+            }
+            KnownModel::Claude3Haiku20240307 => TokenRates::new(25, 125, 30, 3),
+        }
+    }
+}
+
 impl From<KnownModel> for Model {
     fn from(model: KnownModel) -> Self {
         Model::Known(model)
@@ -217,6 +399,20 @@ mod tests {
         assert_eq!(json, r#""claude-4-custom""#);
     }
 
+    #[test]
+    fn custom_model_round_trip() {
+        let model = Model::custom("my-org/fine-tuned-model");
+        let json = serde_json::to_string(&model).unwrap();
+        assert_eq!(json, r#""my-org/fine-tuned-model""#);
+        let parsed: Model = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, model);
+
+        let model = Model::from(KnownModel::ClaudeHaiku45);
+        let json = serde_json::to_string(&model).unwrap();
+        assert_eq!(json, r#""claude-haiku-4-5""#);
+        assert_eq!(serde_json::from_str::<Model>(&json).unwrap(), model);
+    }
+
     #[test]
     fn model_deserialization() {
         let json = r#""claude-3-7-sonnet-latest""#;
@@ -323,4 +519,62 @@ mod tests {
         let model = Model::Custom("claude-4-custom".to_string());
         assert_eq!(model.to_string(), "claude-4-custom");
     }
+
+    #[test]
+    fn sonnet_capabilities_and_pricing() {
+        let sonnet = KnownModel::ClaudeSonnet4520250929;
+        assert!(sonnet.supports_thinking());
+        assert!(sonnet.supports_vision());
+        assert_eq!(sonnet.context_window(), 200_000);
+        assert_eq!(
+            sonnet.default_pricing(),
+            TokenRates::new(300, 1500, 375, 30)
+        );
+    }
+
+    #[test]
+    fn haiku_capabilities_and_pricing() {
+        let haiku = KnownModel::ClaudeHaiku45;
+        assert!(haiku.supports_thinking());
+        assert!(haiku.supports_vision());
+        assert_eq!(haiku.context_window(), 200_000);
+        assert_eq!(haiku.default_pricing(), TokenRates::new(100, 500, 125, 10));
+
+        let haiku3 = KnownModel::Claude3Haiku20240307;
+        assert!(!haiku3.supports_thinking());
+        assert_eq!(haiku3.default_pricing(), TokenRates::new(25, 125, 30, 3));
+    }
+
+    #[test]
+    fn custom_model_fallbacks() {
+        let model = Model::Custom("claude-future".to_string());
+        assert!(!model.supports_thinking());
+        assert!(model.supports_vision());
+        assert_eq!(model.context_window(), KnownModel::DEFAULT_CONTEXT_WINDOW);
+        assert_eq!(model.default_pricing(), KnownModel::FALLBACK_PRICING);
+    }
+
+    #[test]
+    fn context_window_with_1m_beta() {
+        let betas = vec![KnownModel::CONTEXT_1M_BETA.to_string()];
+        let sonnet = KnownModel::ClaudeSonnet45;
+        assert_eq!(sonnet.context_window_with_beta(&[]), 200_000);
+        assert_eq!(sonnet.context_window_with_beta(&betas), 1_000_000);
+        assert_eq!(
+            Model::Known(KnownModel::ClaudeSonnet40).context_window_with_beta(&betas),
+            1_000_000
+        );
+
+        // Models without 1M support and custom models ignore the beta.
+        assert_eq!(
+            KnownModel::ClaudeHaiku45.context_window_with_beta(&betas),
+            200_000
+        );
+        assert_eq!(
+            Model::custom("proxy-model").context_window_with_beta(&betas),
+            KnownModel::DEFAULT_CONTEXT_WINDOW
+        );
+        let other = vec!["output-128k-2025-02-19".to_string()];
+        assert_eq!(sonnet.context_window_with_beta(&other), 200_000);
+    }
 }
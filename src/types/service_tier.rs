@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// The service tier requested for a message.
+///
+/// The tier trades latency against price; see
+/// [service tiers](https://docs.anthropic.com/en/api/service-tiers).  The tier that actually
+/// served the request is reported as a [`UsageServiceTier`] in the response usage.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceTier {
+    /// Use priority capacity when available, falling back to standard.
+    Auto,
+
+    /// Only use standard capacity.
+    StandardOnly,
+
+    /// Use priority capacity.
+    Priority,
+}
+
+/// The service tier that served a message, as reported in its usage.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageServiceTier {
+    /// Standard capacity.
+    Standard,
+
+    /// Priority capacity.
+    Priority,
+
+    /// The message was processed as part of a batch.
+    Batch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, to_value};
+
+    #[test]
+    fn service_tier_serialization() {
+        for (tier, name) in [
+            (ServiceTier::Auto, "auto"),
+            (ServiceTier::StandardOnly, "standard_only"),
+            (ServiceTier::Priority, "priority"),
+        ] {
+            assert_eq!(to_value(tier).unwrap(), json!(name));
+            assert_eq!(
+                serde_json::from_value::<ServiceTier>(json!(name)).unwrap(),
+                tier
+            );
+        }
+    }
+
+    #[test]
+    fn usage_service_tier_deserialization() {
+        for (tier, name) in [
+            (UsageServiceTier::Standard, "standard"),
+            (UsageServiceTier::Priority, "priority"),
+            (UsageServiceTier::Batch, "batch"),
+        ] {
+            assert_eq!(
+                serde_json::from_value::<UsageServiceTier>(json!(name)).unwrap(),
+                tier
+            );
+            assert_eq!(to_value(tier).unwrap(), json!(name));
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
 
 use crate::types::TextBlock;
@@ -41,6 +44,84 @@ impl SystemPrompt {
             .collect();
         Self::Blocks(system_blocks)
     }
+
+    /// Read a string system prompt from the file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            crate::Error::io(
+                format!("failed to read system prompt {}", path.display()),
+                err,
+            )
+        })?;
+        Ok(Self::String(content))
+    }
+
+    /// Replace each `{{key}}` in the prompt's text with `vars[key]`.
+    ///
+    /// Whitespace inside the braces is ignored, so `{{ key }}` also matches.  Placeholders
+    /// with no value in `vars` are left as they are; use [`render_strict`](Self::render_strict)
+    /// to reject them instead.
+    pub fn render(&self, vars: &HashMap<&str, &str>) -> SystemPrompt {
+        self.substitute(vars, &mut Vec::new())
+    }
+
+    /// Like [`render`](Self::render), but returns a validation error naming every placeholder
+    /// that has no value in `vars`.
+    pub fn render_strict(&self, vars: &HashMap<&str, &str>) -> Result<SystemPrompt, crate::Error> {
+        let mut missing = Vec::new();
+        let rendered = self.substitute(vars, &mut missing);
+        if missing.is_empty() {
+            Ok(rendered)
+        } else {
+            Err(crate::Error::validation(
+                format!("unresolved system prompt variables: {}", missing.join(", ")),
+                Some("system".to_string()),
+            ))
+        }
+    }
+
+    fn substitute(&self, vars: &HashMap<&str, &str>, missing: &mut Vec<String>) -> SystemPrompt {
+        match self {
+            Self::String(text) => Self::String(substitute(text, vars, missing)),
+            Self::Blocks(blocks) => Self::Blocks(
+                blocks
+                    .iter()
+                    .map(|block| {
+                        let mut block = block.clone();
+                        block.block.text = substitute(&block.block.text, vars, missing);
+                        block
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Replaces `{{key}}` placeholders in `text`, recording keys absent from `vars` in `missing`.
+fn substitute(text: &str, vars: &HashMap<&str, &str>, missing: &mut Vec<String>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + 2 + len + 2];
+        let key = rest[start + 2..start + 2 + len].trim();
+        rendered.push_str(&rest[..start]);
+        match vars.get(key) {
+            Some(value) => rendered.push_str(value),
+            None => {
+                if !missing.iter().any(|seen| seen == key) {
+                    missing.push(key.to_string());
+                }
+                rendered.push_str(placeholder);
+            }
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+    rendered.push_str(rest);
+    rendered
 }
 
 impl From<String> for SystemPrompt {
@@ -119,4 +200,66 @@ mod tests {
         let prompt: SystemPrompt = blocks.into();
         assert_eq!(prompt, SystemPrompt::Blocks(expected_blocks));
     }
+
+    #[test]
+    fn render_substitutes_variables() {
+        let vars = HashMap::from([("name", "Claudius"), ("tone", "terse")]);
+        let prompt = SystemPrompt::from("You are {{name}}. Be {{ tone }}. {{name}} obeys.");
+        assert_eq!(
+            prompt.render(&vars),
+            SystemPrompt::from("You are Claudius. Be terse. Claudius obeys.")
+        );
+
+        let blocks = SystemPrompt::from_blocks(vec![
+            TextBlock::new("Hello {{name}}"),
+            TextBlock::new("No placeholders"),
+        ]);
+        assert_eq!(
+            blocks.render(&vars),
+            SystemPrompt::from_blocks(vec![
+                TextBlock::new("Hello Claudius"),
+                TextBlock::new("No placeholders"),
+            ])
+        );
+    }
+
+    #[test]
+    fn render_handles_missing_variables() {
+        let vars = HashMap::from([("name", "Claudius")]);
+        let prompt =
+            SystemPrompt::from("{{name}} speaks {{language}} about {{topic}} {{ unterminated");
+        assert_eq!(
+            prompt.render(&vars),
+            SystemPrompt::from("Claudius speaks {{language}} about {{topic}} {{ unterminated")
+        );
+
+        let err = prompt.render_strict(&vars).unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("language, topic"), "{err}");
+
+        let complete = SystemPrompt::from("{{name}}");
+        assert_eq!(
+            complete.render_strict(&vars).unwrap(),
+            SystemPrompt::from("Claudius")
+        );
+    }
+
+    #[test]
+    fn from_file_reads_prompt() {
+        let path =
+            std::env::temp_dir().join(format!("claudius-system-prompt-{}.txt", std::process::id()));
+        std::fs::write(&path, "You are {{name}}.").unwrap();
+        let prompt = SystemPrompt::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            prompt.render(&HashMap::from([("name", "Claudius")])),
+            SystemPrompt::from("You are Claudius.")
+        );
+
+        let err = SystemPrompt::from_file(&path).unwrap_err();
+        assert!(
+            err.to_string().contains("failed to read system prompt"),
+            "{err}"
+        );
+    }
 }
@@ -13,7 +13,7 @@ pub struct TextBlock {
     /// The type of citation returned will depend on the type of document being cited.
     /// Citing a PDF results in `page_location`, plain text results in `char_location`,
     /// and content document results in `content_block_location`.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "crate::types::is_none_or_empty")]
     pub citations: Option<Vec<TextCitation>>,
 
     /// The text content.
@@ -68,6 +68,18 @@ impl TextBlock {
     pub fn has_citations(&self) -> bool {
         self.citation_count() > 0
     }
+
+    /// Pairs the quoted source text of each citation with the citation, in order.
+    ///
+    /// The block's own text is the claim the citations support; the quoted span is what to
+    /// show as a footnote or tooltip when rendering the answer.
+    pub fn cited_text(&self) -> Vec<(&str, &TextCitation)> {
+        self.citations
+            .iter()
+            .flatten()
+            .map(|citation| (citation.cited_text(), citation))
+            .collect()
+    }
 }
 
 impl FromStr for TextBlock {
@@ -187,6 +199,36 @@ mod tests {
         assert!(text_block.has_citations());
     }
 
+    #[test]
+    fn cited_text_pairs_quotes_with_citations() {
+        assert!(TextBlock::new("Uncited").cited_text().is_empty());
+
+        let page = TextCitation::page_location(
+            "Grass is green.".to_string(),
+            1,
+            3,
+            4,
+            Some("Botany".to_string()),
+        );
+        let text_block = TextBlock::with_citations(
+            "The sky is blue and grass is green.",
+            vec![TextCitation::char_location(
+                "The sky is blue.".to_string(),
+                0,
+                0,
+                16,
+                None,
+            )],
+        )
+        .with_citation(page.clone());
+
+        let pairs = text_block.cited_text();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].0, "The sky is blue.");
+        assert!(matches!(pairs[0].1, TextCitation::CharLocation(_)));
+        assert_eq!(pairs[1], ("Grass is green.", &page));
+    }
+
     #[test]
     fn text_block_with_cache_control() {
         let cache_control = CacheControlEphemeral::new();
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::types::{
-    CitationCharLocation, CitationContentBlockLocation, CitationPageLocation,
-    CitationWebSearchResultLocation,
+    Citation, CitationCharLocation, CitationContentBlockLocation, CitationPageLocation,
+    CitationSpan, CitationWebSearchResultLocation,
 };
 
 /// A citation reference in a TextBlock.
@@ -101,6 +101,57 @@ impl TextCitation {
     }
 }
 
+impl TextCitation {
+    /// Returns the text that was cited.
+    pub fn cited_text(&self) -> &str {
+        match self {
+            Self::CharLocation(loc) => &loc.cited_text,
+            Self::PageLocation(loc) => &loc.cited_text,
+            Self::ContentBlockLocation(loc) => &loc.cited_text,
+            Self::WebSearchResultLocation(loc) => &loc.cited_text,
+        }
+    }
+
+    /// Normalizes this citation to a span within a document.
+    ///
+    /// Returns `None` for web search result citations, which refer to a URL rather than a
+    /// document in the request.
+    pub fn as_span(&self) -> Option<CitationSpan> {
+        match self {
+            Self::CharLocation(loc) => Some(CitationSpan::new(
+                loc.document_index,
+                loc.start_char_index,
+                loc.end_char_index,
+                loc.cited_text.clone(),
+            )),
+            Self::PageLocation(loc) => Some(CitationSpan::new(
+                loc.document_index,
+                loc.start_page_number,
+                loc.end_page_number,
+                loc.cited_text.clone(),
+            )),
+            Self::ContentBlockLocation(loc) => Some(CitationSpan::new(
+                loc.document_index,
+                loc.start_block_index,
+                loc.end_block_index,
+                loc.cited_text.clone(),
+            )),
+            Self::WebSearchResultLocation(_) => None,
+        }
+    }
+}
+
+impl From<Citation> for TextCitation {
+    fn from(citation: Citation) -> Self {
+        match citation {
+            Citation::CharLocation(loc) => Self::CharLocation(loc),
+            Citation::PageLocation(loc) => Self::PageLocation(loc),
+            Citation::ContentBlockLocation(loc) => Self::ContentBlockLocation(loc),
+            Citation::WebSearchResultLocation(loc) => Self::WebSearchResultLocation(loc),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -333,4 +384,67 @@ mod tests {
 
         assert_eq!(json_value, expected);
     }
+
+    #[test]
+    fn every_variant_round_trips() {
+        let citations = [
+            (
+                "char_location",
+                TextCitation::char_location("a".to_string(), 0, 0, 1, None),
+            ),
+            (
+                "page_location",
+                TextCitation::page_location("b".to_string(), 1, 2, 3, Some("Doc".to_string())),
+            ),
+            (
+                "content_block_location",
+                TextCitation::content_block_location("c".to_string(), 2, 0, 1, None),
+            ),
+            (
+                "web_search_result_location",
+                TextCitation::web_search_result_location(
+                    "d".to_string(),
+                    "idx".to_string(),
+                    "https://example.com".to_string(),
+                    None,
+                ),
+            ),
+        ];
+        for (tag, citation) in citations {
+            let json = serde_json::to_value(&citation).unwrap();
+            assert_eq!(json["type"], tag);
+            let back: TextCitation = serde_json::from_value(json).unwrap();
+            assert_eq!(back, citation);
+        }
+    }
+
+    #[test]
+    fn as_span_normalizes_document_locations() {
+        let char_location = TextCitation::char_location("abc".to_string(), 0, 3, 6, None);
+        assert_eq!(
+            char_location.as_span(),
+            Some(CitationSpan::new(0, 3, 6, "abc"))
+        );
+
+        let page_location = TextCitation::page_location("def".to_string(), 1, 2, 4, None);
+        assert_eq!(
+            page_location.as_span(),
+            Some(CitationSpan::new(1, 2, 4, "def"))
+        );
+
+        let block_location = TextCitation::content_block_location("ghi".to_string(), 2, 0, 1, None);
+        assert_eq!(
+            block_location.as_span(),
+            Some(CitationSpan::new(2, 0, 1, "ghi"))
+        );
+
+        let web_location = TextCitation::web_search_result_location(
+            "jkl".to_string(),
+            "idx".to_string(),
+            "https://example.com".to_string(),
+            None,
+        );
+        assert_eq!(web_location.as_span(), None);
+        assert_eq!(web_location.cited_text(), "jkl");
+    }
 }
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// Per-token prices in micro-cents (1/1,000,000 of a cent).
+///
+/// A price of $3 per million tokens corresponds to 300 micro-cents per token.  Server tools
+/// are billed per request rather than per token; their rates default to zero.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TokenRates {
+    /// Cost per input token.
+    pub input_micro_cents: u64,
+
+    /// Cost per output token.
+    pub output_micro_cents: u64,
+
+    /// Cost per token written to the prompt cache.
+    pub cache_creation_micro_cents: u64,
+
+    /// Cost per token read from the prompt cache.
+    pub cache_read_micro_cents: u64,
+
+    /// Cost per web search request made by the server-side web search tool.
+    #[serde(default)]
+    pub web_search_request_micro_cents: u64,
+}
+
+impl TokenRates {
+    /// Creates a new set of token rates.
+    pub const fn new(
+        input_micro_cents: u64,
+        output_micro_cents: u64,
+        cache_creation_micro_cents: u64,
+        cache_read_micro_cents: u64,
+    ) -> Self {
+        Self {
+            input_micro_cents,
+            output_micro_cents,
+            cache_creation_micro_cents,
+            cache_read_micro_cents,
+            web_search_request_micro_cents: 0,
+        }
+    }
+
+    /// Sets the cost per web search request.
+    pub const fn with_web_search_request_micro_cents(mut self, micro_cents: u64) -> Self {
+        self.web_search_request_micro_cents = micro_cents;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialization() {
+        let rates = TokenRates::new(300, 1500, 375, 30);
+        let json = serde_json::to_value(rates).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "input_micro_cents": 300,
+                "output_micro_cents": 1500,
+                "cache_creation_micro_cents": 375,
+                "cache_read_micro_cents": 30,
+                "web_search_request_micro_cents": 0
+            })
+        );
+        let back: TokenRates = serde_json::from_value(json).unwrap();
+        assert_eq!(back, rates);
+    }
+
+    #[test]
+    fn web_search_rate_defaults_to_zero() {
+        let rates: TokenRates = serde_json::from_value(serde_json::json!({
+            "input_micro_cents": 300,
+            "output_micro_cents": 1500,
+            "cache_creation_micro_cents": 375,
+            "cache_read_micro_cents": 30
+        }))
+        .unwrap();
+        assert_eq!(rates, TokenRates::new(300, 1500, 375, 30));
+        assert_eq!(
+            rates
+                .with_web_search_request_micro_cents(1_000_000)
+                .web_search_request_micro_cents,
+            1_000_000
+        );
+    }
+}
@@ -212,4 +212,62 @@ mod tests {
             _ => panic!("Expected Tool variant"),
         }
     }
+
+    #[test]
+    fn tool_choice_any_with_disable_parallel() {
+        let param = ToolChoice::any_with_disable_parallel(true);
+        let json = to_value(&param).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "type": "any",
+                "disable_parallel_tool_use": true
+            })
+        );
+    }
+
+    #[test]
+    fn tool_choice_tool_with_disable_parallel() {
+        let param = ToolChoice::tool_with_disable_parallel("my_tool", true);
+        let json = to_value(&param).unwrap();
+
+        assert_eq!(
+            json,
+            json!({
+                "type": "tool",
+                "name": "my_tool",
+                "disable_parallel_tool_use": true
+            })
+        );
+    }
+
+    #[test]
+    fn tool_choice_shapes_round_trip() {
+        let choices = [
+            ToolChoice::auto(),
+            ToolChoice::auto_with_disable_parallel(true),
+            ToolChoice::any(),
+            ToolChoice::any_with_disable_parallel(true),
+            ToolChoice::tool("my_tool"),
+            ToolChoice::tool_with_disable_parallel("my_tool", false),
+            ToolChoice::none(),
+        ];
+        for choice in choices {
+            let json = to_value(&choice).unwrap();
+            let parsed: ToolChoice = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed, choice);
+        }
+    }
+
+    #[test]
+    fn tool_choice_deserialization_none_and_any() {
+        let none: ToolChoice = serde_json::from_value(json!({"type": "none"})).unwrap();
+        assert_eq!(none, ToolChoice::None);
+
+        let any: ToolChoice =
+            serde_json::from_value(json!({"type": "any", "disable_parallel_tool_use": true}))
+                .unwrap();
+        assert_eq!(any, ToolChoice::any_with_disable_parallel(true));
+    }
 }
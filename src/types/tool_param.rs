@@ -100,6 +100,117 @@ impl ToolParam {
         self.strict = Some(strict);
         self
     }
+
+    /// Check `input` against this tool's `input_schema`.
+    ///
+    /// This is a lightweight check, not a full JSON Schema validator: it enforces `type`,
+    /// `required`, `properties`, `items`, and `enum`, and ignores every other keyword.  The
+    /// error names the offending location (e.g. `input.location`) so it can be returned to the
+    /// model as a tool result without running the tool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use claudius::ToolParam;
+    ///
+    /// let tool = ToolParam::new(
+    ///     "get_weather".to_string(),
+    ///     json!({
+    ///         "type": "object",
+    ///         "properties": { "location": { "type": "string" } },
+    ///         "required": ["location"]
+    ///     }),
+    /// );
+    /// assert!(tool.validate_input(&json!({"location": "Paris"})).is_ok());
+    /// assert!(tool.validate_input(&json!({})).is_err());
+    /// ```
+    pub fn validate_input(&self, input: &serde_json::Value) -> Result<(), String> {
+        validate_value(&self.input_schema, input, "input")
+    }
+}
+
+/// Checks `value`, found at `path`, against the subset of `schema` that
+/// [`ToolParam::validate_input`] understands.
+fn validate_value(
+    schema: &serde_json::Value,
+    value: &serde_json::Value,
+    path: &str,
+) -> Result<(), String> {
+    use serde_json::Value;
+
+    let Some(schema) = schema.as_object() else {
+        // `true`, `false`, and anything else unexpected are not checked.
+        return Ok(());
+    };
+    match schema.get("type") {
+        Some(Value::String(ty)) if !has_type(value, ty) => {
+            return Err(format!("{path} must be of type {ty}"));
+        }
+        Some(Value::Array(types)) => {
+            let types = types.iter().filter_map(Value::as_str).collect::<Vec<_>>();
+            if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+                return Err(format!(
+                    "{path} must be one of the types {}",
+                    types.join(", ")
+                ));
+            }
+        }
+        _ => {}
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum")
+        && !allowed.contains(value)
+    {
+        return Err(format!(
+            "{path} must be one of {}",
+            Value::Array(allowed.clone())
+        ));
+    }
+    if let Value::Object(fields) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    return Err(format!("{path} is missing required field {name:?}"));
+                }
+            }
+        }
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (name, field) in fields {
+                if let Some(property) = properties.get(name) {
+                    validate_value(property, field, &format!("{path}.{name}"))?;
+                }
+            }
+        }
+    }
+    if let (Value::Array(elements), Some(items)) = (value, schema.get("items")) {
+        for (idx, element) in elements.iter().enumerate() {
+            validate_value(items, element, &format!("{path}[{idx}]"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `value` is an instance of the JSON Schema type named `ty`.
+///
+/// Unknown type names match anything.
+fn has_type(value: &serde_json::Value, ty: &str) -> bool {
+    use serde_json::Value;
+
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => match value {
+            Value::Number(n) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => false,
+        },
+        _ => true,
+    }
 }
 
 #[cfg(test)]
@@ -234,4 +345,68 @@ mod tests {
             "strict should not be serialized when None"
         );
     }
+
+    #[test]
+    fn validate_input_rejects_missing_required_field() {
+        let tool = ToolParam::new(
+            "get_weather".to_string(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "location": { "type": "string" },
+                    "unit": { "type": "string", "enum": ["celsius", "fahrenheit"] }
+                },
+                "required": ["location"]
+            }),
+        );
+        assert_eq!(tool.validate_input(&json!({"location": "Paris"})), Ok(()));
+        assert_eq!(
+            tool.validate_input(&json!({"unit": "celsius"})),
+            Err("input is missing required field \"location\"".to_string())
+        );
+        assert_eq!(
+            tool.validate_input(&json!({"location": "Paris", "unit": "kelvin"})),
+            Err("input.unit must be one of [\"celsius\",\"fahrenheit\"]".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_input_rejects_wrong_types() {
+        let tool = ToolParam::new(
+            "schedule".to_string(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "attendees": { "type": "array", "items": { "type": "string" } },
+                    "duration": { "type": "integer" },
+                    "notes": { "type": ["string", "null"] }
+                }
+            }),
+        );
+        assert_eq!(
+            tool.validate_input(&json!({"title": "sync", "duration": 30, "notes": null})),
+            Ok(())
+        );
+        assert_eq!(
+            tool.validate_input(&json!("sync")),
+            Err("input must be of type object".to_string())
+        );
+        assert_eq!(
+            tool.validate_input(&json!({"title": 7})),
+            Err("input.title must be of type string".to_string())
+        );
+        assert_eq!(
+            tool.validate_input(&json!({"duration": 1.5})),
+            Err("input.duration must be of type integer".to_string())
+        );
+        assert_eq!(
+            tool.validate_input(&json!({"attendees": ["ana", 3]})),
+            Err("input.attendees[1] must be of type string".to_string())
+        );
+        assert_eq!(
+            tool.validate_input(&json!({"notes": 3})),
+            Err("input.notes must be one of the types string, null".to_string())
+        );
+    }
 }
@@ -8,8 +8,6 @@ use crate::types::{CacheControlEphemeral, Content};
 /// requested via a ToolUseBlock. It contains the tool's response, which can be
 /// either successful output or an error indication.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(tag = "type")]
-#[serde(rename = "tool_result")]
 pub struct ToolResultBlock {
     /// The ID of the tool use that this result is for.
     ///
@@ -62,6 +60,12 @@ impl From<&str> for ToolResultBlockContent {
     }
 }
 
+impl From<Vec<Content>> for ToolResultBlockContent {
+    fn from(value: Vec<Content>) -> Self {
+        ToolResultBlockContent::Array(value)
+    }
+}
+
 impl ToolResultBlock {
     /// Create a new `ToolResultBlock` with the given tool use ID.
     pub fn new(tool_use_id: String) -> Self {
@@ -73,6 +77,30 @@ impl ToolResultBlock {
         }
     }
 
+    /// Create a successful tool result with text content.
+    pub fn ok(tool_use_id: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::new(tool_use_id.into()).with_string_content(text.into())
+    }
+
+    /// Create a tool result reporting that the tool failed with `message`.
+    pub fn error(tool_use_id: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::ok(tool_use_id, message).with_error(true)
+    }
+
+    /// Create a successful tool result whose content is `value` serialized as JSON.
+    pub fn json(
+        tool_use_id: impl Into<String>,
+        value: &impl Serialize,
+    ) -> Result<Self, crate::Error> {
+        let text = serde_json::to_string(value).map_err(|e| {
+            crate::Error::serialization(
+                format!("failed to serialize tool result: {e}"),
+                Some(Box::new(e)),
+            )
+        })?;
+        Ok(Self::ok(tool_use_id, text))
+    }
+
     /// Add a cache control to this tool result block.
     pub fn with_cache_control(mut self, cache_control: CacheControlEphemeral) -> Self {
         self.cache_control = Some(cache_control);
@@ -130,12 +158,62 @@ mod tests {
             json,
             json!({
                 "tool_use_id": "tool_1",
-                "type": "tool_result",
                 "content": "Result of tool execution"
             })
         );
     }
 
+    #[test]
+    fn tool_result_block_ok_and_error() {
+        let block = ToolResultBlock::ok("tool_1", "done");
+        assert_eq!(
+            to_value(&block).unwrap(),
+            json!({"tool_use_id": "tool_1", "content": "done"})
+        );
+
+        let block = ToolResultBlock::error("tool_2", "file not found");
+        assert_eq!(
+            to_value(&block).unwrap(),
+            json!({"tool_use_id": "tool_2", "content": "file not found", "is_error": true})
+        );
+    }
+
+    #[test]
+    fn tool_result_block_json() {
+        #[derive(Serialize)]
+        struct Weather {
+            city: &'static str,
+            temperature: i32,
+        }
+
+        let block = ToolResultBlock::json(
+            "tool_1",
+            &Weather {
+                city: "Paris",
+                temperature: 21,
+            },
+        )
+        .unwrap();
+        assert_eq!(block.is_error, None);
+        assert_eq!(
+            block.content,
+            Some(ToolResultBlockContent::String(
+                r#"{"city":"Paris","temperature":21}"#.to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn tool_result_block_json_reports_serialization_errors() {
+        let mut map = std::collections::HashMap::new();
+        map.insert((1, 2), "tuple keys are not valid JSON object keys");
+        let err = ToolResultBlock::json("tool_1", &map).unwrap_err();
+        assert!(
+            err.to_string().contains("failed to serialize tool result"),
+            "{err}"
+        );
+    }
+
     #[test]
     fn tool_result_block_with_array_content() {
         let text_param = crate::types::TextBlock::new("Sample text content".to_string());
@@ -148,7 +226,6 @@ mod tests {
             json,
             json!({
                 "tool_use_id": "tool_1",
-                "type": "tool_result",
                 "content": [
                     {
                         "text": "Sample text content",
@@ -170,7 +247,6 @@ mod tests {
             json,
             json!({
                 "tool_use_id": "tool_1",
-                "type": "tool_result",
                 "content": "Error executing tool",
                 "is_error": true
             })
@@ -215,4 +291,38 @@ mod tests {
             _ => panic!("Expected String variant"),
         }
     }
+
+    #[test]
+    fn tool_result_block_with_image_round_trips() {
+        let image =
+            crate::types::ImageBlock::new_with_base64(crate::types::Base64ImageSource::new(
+                "iVBORw0KGgo=".to_string(),
+                crate::types::ImageMediaType::Png,
+            ));
+        let block = ToolResultBlock::new("tool_1".to_string()).with_array_content(vec![
+            Content::Text(crate::types::TextBlock::new("screenshot")),
+            Content::Image(image),
+        ]);
+
+        let json = to_value(&block).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "tool_use_id": "tool_1",
+                "content": [
+                    {"type": "text", "text": "screenshot"},
+                    {
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "data": "iVBORw0KGgo=",
+                            "media_type": "image/png"
+                        }
+                    }
+                ]
+            })
+        );
+        let round_trip: ToolResultBlock = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip, block);
+    }
 }
@@ -98,6 +98,22 @@ impl ToolUnionParam {
             | Self::WebSearch20250305(_) => false,
         }
     }
+
+    /// Returns the input schema sent for this tool.
+    ///
+    /// Built-in tools are described by the API itself and send no schema, so they return
+    /// `Value::Null`.
+    pub fn input_schema_json(&self) -> serde_json::Value {
+        match self {
+            Self::CustomTool(tool) => tool.input_schema.clone(),
+            Self::Bash20241022(_)
+            | Self::Bash20250124(_)
+            | Self::TextEditor20250124(_)
+            | Self::TextEditor20250429(_)
+            | Self::TextEditor20250728(_)
+            | Self::WebSearch20250305(_) => serde_json::Value::Null,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -329,4 +345,35 @@ mod tests {
             _ => panic!("Expected Bash20250124 variant"),
         }
     }
+
+    #[test]
+    fn every_variant_round_trips() {
+        let tools = [
+            (
+                "custom",
+                ToolUnionParam::new_custom_tool("search".to_string(), json!({"type": "object"})),
+            ),
+            ("bash_20241022", ToolUnionParam::new_bash_20241022_tool()),
+            ("bash_20250124", ToolUnionParam::new_bash_tool()),
+            (
+                "text_editor_20250124",
+                ToolUnionParam::new_text_editor_tool(),
+            ),
+            (
+                "text_editor_20250429",
+                ToolUnionParam::new_text_editor_20250429_tool(),
+            ),
+            (
+                "text_editor_20250728",
+                ToolUnionParam::new_text_editor_20250728_tool(),
+            ),
+            ("web_search_20250305", ToolUnionParam::new_web_search_tool()),
+        ];
+        for (tag, tool) in tools {
+            let json = to_value(&tool).unwrap();
+            assert_eq!(json["type"], tag);
+            let back: ToolUnionParam = serde_json::from_value(json).unwrap();
+            assert_eq!(back, tool);
+        }
+    }
 }
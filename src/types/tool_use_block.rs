@@ -1,3 +1,6 @@
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -33,11 +36,31 @@ impl ToolUseBlock {
         }
     }
 
+    /// Creates a new ToolUseBlock with a fresh `toolu_` id.
+    ///
+    /// Ids are unique within the process and random across processes, which suits tool uses
+    /// synthesized for tests or replays.
+    pub fn new_with_random_id<S: Into<String>>(name: S, input: Value) -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let random = std::collections::hash_map::RandomState::new()
+            .hash_one((std::time::SystemTime::now(), count));
+        Self::new(format!("toolu_{random:016x}{count:08x}"), name, input)
+    }
+
     /// Add a cache control to this tool use block.
     pub fn with_cache_control(mut self, cache_control: CacheControlEphemeral) -> Self {
         self.cache_control = Some(cache_control);
         self
     }
+
+    /// Deserializes the input into `T` without cloning it.
+    ///
+    /// `T` may borrow string fields straight out of [`input`](Self::input), which avoids
+    /// copying large arguments such as file contents.
+    pub fn parse_input<'a, T: Deserialize<'a>>(&'a self) -> Result<T, serde_json::Error> {
+        T::deserialize(&self.input)
+    }
 }
 
 #[cfg(test)]
@@ -45,6 +68,15 @@ mod tests {
     use super::*;
     use serde_json::{json, to_value};
 
+    #[test]
+    fn new_with_random_id_is_unique() {
+        let ids: std::collections::HashSet<_> = (0..1000)
+            .map(|_| ToolUseBlock::new_with_random_id("lookup", json!({})).id)
+            .collect();
+        assert_eq!(ids.len(), 1000);
+        assert!(ids.iter().all(|id| id.starts_with("toolu_")));
+    }
+
     #[test]
     fn tool_use_block_serialization() {
         let input_json = json!({
@@ -113,4 +145,40 @@ mod tests {
         });
         assert_eq!(block.input, expected_input);
     }
+
+    #[test]
+    fn parse_input_borrows_large_strings() {
+        #[derive(Deserialize)]
+        struct Create<'a> {
+            path: &'a str,
+            file_text: &'a str,
+        }
+        let file_text = "fn main() {}\n".repeat(1 << 16);
+        let block = ToolUseBlock::new(
+            "toolu_1",
+            "str_replace_based_edit_tool",
+            json!({"command": "create", "path": "src/main.rs", "file_text": file_text}),
+        );
+
+        // The parsed text is the input's own buffer, not a copy of it.
+        let create: Create = block.parse_input().unwrap();
+        assert_eq!(create.path, "src/main.rs");
+        assert_eq!(create.file_text.len(), file_text.len());
+        assert_eq!(
+            create.file_text.as_ptr(),
+            block.input["file_text"].as_str().unwrap().as_ptr()
+        );
+    }
+
+    #[test]
+    fn parse_input_reports_type_errors() {
+        #[derive(Debug, Deserialize)]
+        struct Search {
+            #[allow(dead_code)]
+            query: String,
+        }
+        let block = ToolUseBlock::new("toolu_1", "search", json!({"query": 5}));
+        let err = block.parse_input::<Search>().unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{err}");
+    }
 }
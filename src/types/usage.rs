@@ -2,7 +2,7 @@ use std::ops::Add;
 
 use serde::{Deserialize, Serialize};
 
-use crate::types::ServerToolUsage;
+use crate::types::{ServerToolUsage, TokenRates, UsageServiceTier};
 
 /// Usage information for API calls.
 ///
@@ -27,6 +27,12 @@ pub struct Usage {
     /// The number of server tool requests.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub server_tool_use: Option<ServerToolUsage>,
+
+    /// The service tier that served the request.
+    ///
+    /// Summing two usages keeps the tier of the right-hand side when it reports one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<UsageServiceTier>,
 }
 
 impl Usage {
@@ -38,6 +44,7 @@ impl Usage {
             input_tokens,
             output_tokens,
             server_tool_use: None,
+            service_tier: None,
         }
     }
 
@@ -58,6 +65,42 @@ impl Usage {
         self.server_tool_use = Some(server_tool_use);
         self
     }
+
+    /// Set the service tier that served the request.
+    pub fn with_service_tier(mut self, service_tier: UsageServiceTier) -> Self {
+        self.service_tier = Some(service_tier);
+        self
+    }
+
+    /// Returns the cost of this usage in micro-cents at `rates`.
+    ///
+    /// Server tool requests are charged at their per-request rates.  Negative counts are
+    /// treated as zero, and a total too large for a `u64` saturates at `u64::MAX`.
+    pub fn cost(&self, rates: &TokenRates) -> u64 {
+        let tokens = |count: i32| count.max(0) as u64;
+        let input_cost = tokens(self.input_tokens).saturating_mul(rates.input_micro_cents);
+        let output_cost = tokens(self.output_tokens).saturating_mul(rates.output_micro_cents);
+        let cache_creation_cost = tokens(self.cache_creation_input_tokens.unwrap_or(0))
+            .saturating_mul(rates.cache_creation_micro_cents);
+        let cache_read_cost = tokens(self.cache_read_input_tokens.unwrap_or(0))
+            .saturating_mul(rates.cache_read_micro_cents);
+        let web_search_cost =
+            tokens(self.web_search_requests()).saturating_mul(rates.web_search_request_micro_cents);
+
+        input_cost
+            .checked_add(output_cost)
+            .and_then(|sum| sum.checked_add(cache_creation_cost))
+            .and_then(|sum| sum.checked_add(cache_read_cost))
+            .and_then(|sum| sum.checked_add(web_search_cost))
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Returns the number of web search requests the server made, or zero if none were
+    /// reported.
+    pub fn web_search_requests(&self) -> i32 {
+        self.server_tool_use
+            .map_or(0, |server_tool_use| server_tool_use.web_search_requests)
+    }
 }
 
 /// Helper function to add two Option values where the contained type implements Add.
@@ -86,6 +129,7 @@ impl Add for Usage {
             input_tokens: self.input_tokens + rhs.input_tokens,
             output_tokens: self.output_tokens + rhs.output_tokens,
             server_tool_use: add_options(self.server_tool_use, rhs.server_tool_use),
+            service_tier: rhs.service_tier.or(self.service_tier),
         }
     }
 }
@@ -95,6 +139,29 @@ mod tests {
     use super::*;
     use serde_json::{json, to_value};
 
+    #[test]
+    fn cost_applies_each_rate() {
+        let rates = TokenRates::new(300, 1500, 375, 30);
+        let usage = Usage::new(100, 50)
+            .with_cache_creation_input_tokens(20)
+            .with_cache_read_input_tokens(10);
+        assert_eq!(usage.cost(&rates), 112_800);
+        assert_eq!(Usage::new(-5, 2).cost(&rates), 3000);
+        assert_eq!(
+            Usage::new(i32::MAX, i32::MAX).cost(&TokenRates::new(u64::MAX, 1, 0, 0)),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn cost_charges_web_search_requests() {
+        let rates = TokenRates::new(300, 1500, 375, 30).with_web_search_request_micro_cents(1_000);
+        let usage = Usage::new(100, 50).with_server_tool_use(ServerToolUsage::new(3));
+        assert_eq!(usage.web_search_requests(), 3);
+        assert_eq!(usage.cost(&rates), 30_000 + 75_000 + 3_000);
+        assert_eq!(Usage::new(100, 50).cost(&rates), 105_000);
+    }
+
     #[test]
     fn usage_minimal() {
         let usage = Usage::new(50, 100);
@@ -223,4 +290,18 @@ mod tests {
         assert_eq!(result.cache_read_input_tokens, Some(45));
         assert_eq!(result.server_tool_use, Some(ServerToolUsage::new(8)));
     }
+
+    #[test]
+    fn add_usage_keeps_latest_service_tier() {
+        let standard = Usage::new(1, 1).with_service_tier(UsageServiceTier::Standard);
+        let priority = Usage::new(1, 1).with_service_tier(UsageServiceTier::Priority);
+        assert_eq!(
+            (standard + priority).service_tier,
+            Some(UsageServiceTier::Priority)
+        );
+        assert_eq!(
+            (priority + Usage::new(1, 1)).service_tier,
+            Some(UsageServiceTier::Priority)
+        );
+    }
 }
@@ -45,6 +45,21 @@ impl WebSearchResultBlock {
         self
     }
 
+    /// Returns the URL of the web page.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the title of the web page.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the age of the page, if the search reported one.
+    pub fn page_age(&self) -> Option<&str> {
+        self.page_age.as_deref()
+    }
+
     /// Returns the domain (host) part of the URL if it can be parsed.
     pub fn domain(&self) -> Option<String> {
         url::Url::parse(&self.url)
@@ -163,6 +163,19 @@ impl WebSearchTool20250305 {
         self.user_location = Some(user_location);
         self
     }
+
+    /// Checks that `allowed_domains` and `blocked_domains` are not both set.
+    ///
+    /// The builders keep them exclusive, but a deserialized or hand-built tool may not.
+    pub fn validate(&self) -> Result<(), crate::Error> {
+        if self.allowed_domains.is_some() && self.blocked_domains.is_some() {
+            return Err(crate::Error::validation(
+                "web search tool cannot set both allowed_domains and blocked_domains",
+                Some("blocked_domains".to_string()),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Default for WebSearchTool20250305 {
@@ -260,6 +273,43 @@ mod tests {
         assert_eq!(user_location.country, Some("US".to_string()));
     }
 
+    #[test]
+    fn domain_filters_round_trip() {
+        let allowed = WebSearchTool20250305::new()
+            .with_allowed_domains(vec!["docs.rs".to_string()])
+            .with_max_uses(3);
+        let json = serde_json::to_value(&allowed).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"name": "web_search", "allowed_domains": ["docs.rs"], "max_uses": 3})
+        );
+        let back: WebSearchTool20250305 = serde_json::from_value(json).unwrap();
+        assert_eq!(back, allowed);
+        assert!(back.validate().is_ok());
+
+        let blocked =
+            WebSearchTool20250305::new().with_blocked_domains(vec!["example.com".to_string()]);
+        let json = serde_json::to_value(&blocked).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"name": "web_search", "blocked_domains": ["example.com"]})
+        );
+        let back: WebSearchTool20250305 = serde_json::from_value(json).unwrap();
+        assert_eq!(back, blocked);
+        assert!(back.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_allowed_and_blocked_domains() {
+        let tool: WebSearchTool20250305 = serde_json::from_str(
+            r#"{"name": "web_search", "allowed_domains": ["a.com"], "blocked_domains": ["b.com"]}"#,
+        )
+        .unwrap();
+        let err = tool.validate().unwrap_err();
+        assert!(err.is_validation());
+        assert!(err.to_string().contains("allowed_domains"), "{err}");
+    }
+
     #[test]
     fn allowed_blocked_domains_mutual_exclusivity() {
         // Test that setting allowed_domains clears blocked_domains
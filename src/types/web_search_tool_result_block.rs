@@ -1,13 +1,14 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::{CacheControlEphemeral, WebSearchToolResultBlockContent};
+use crate::types::{
+    CacheControlEphemeral, WebSearchResultBlock, WebSearchToolResultBlockContent,
+    WebSearchToolResultError,
+};
 
 /// A block containing the results of a web search tool operation.
 ///
 /// WebSearchToolResultBlock contains either a list of search results or an error.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(tag = "type")]
-#[serde(rename = "web_search_tool_result")]
 pub struct WebSearchToolResultBlock {
     /// The content of the web search tool result.
     pub content: WebSearchToolResultBlockContent,
@@ -72,12 +73,22 @@ impl WebSearchToolResultBlock {
     pub fn result_count(&self) -> usize {
         self.content.result_count()
     }
+
+    /// Returns the search results, or an empty vector if this is an error result.
+    pub fn results(&self) -> Vec<&WebSearchResultBlock> {
+        self.content.as_results().into_iter().flatten().collect()
+    }
+
+    /// Returns the error if the search failed.
+    pub fn error(&self) -> Option<&WebSearchToolResultError> {
+        self.content.as_error()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{WebSearchErrorCode, WebSearchResultBlock, WebSearchToolResultError};
+    use crate::types::WebSearchErrorCode;
     use serde_json::Value;
 
     #[test]
@@ -100,7 +111,7 @@ mod tests {
         // This avoids issues with key ordering
         let actual: Value = serde_json::from_str(&json).unwrap();
         let expected: Value = serde_json::from_str(
-            r#"{"type":"web_search_tool_result","content":[{"type":"web_search_result","encrypted_content":"encrypted-data-1","page_age":"2 days ago","title":"Example Page 1","url":"https://example.com/page1"}],"tool_use_id":"tool-123"}"#
+            r#"{"content":[{"type":"web_search_result","encrypted_content":"encrypted-data-1","page_age":"2 days ago","title":"Example Page 1","url":"https://example.com/page1"}],"tool_use_id":"tool-123"}"#
         ).unwrap();
 
         assert_eq!(actual, expected);
@@ -120,8 +131,9 @@ mod tests {
         // Parse both the actual and expected JSON to Values for comparison
         let actual: Value = serde_json::from_str(&json).unwrap();
         let expected: Value = serde_json::from_str(
-            r#"{"type":"web_search_tool_result","content":{"error_code":"invalid_tool_input"},"tool_use_id":"tool-123"}"#
-        ).unwrap();
+            r#"{"content":{"error_code":"invalid_tool_input"},"tool_use_id":"tool-123"}"#,
+        )
+        .unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -172,6 +184,48 @@ mod tests {
         assert!(block.cache_control.is_none());
     }
 
+    #[test]
+    fn results_and_error_accessors() {
+        let blocks: Vec<WebSearchToolResultBlock> = serde_json::from_str(
+            r#"[
+                {
+                    "type": "web_search_tool_result",
+                    "tool_use_id": "srvtoolu_1",
+                    "content": [
+                        {"type": "web_search_result", "encrypted_content": "abc", "title": "Rust", "url": "https://www.rust-lang.org/", "page_age": "1 week ago"},
+                        {"type": "web_search_result", "encrypted_content": "def", "title": "Crates", "url": "https://crates.io/"}
+                    ]
+                },
+                {
+                    "type": "web_search_tool_result",
+                    "tool_use_id": "srvtoolu_2",
+                    "content": {"type": "web_search_tool_result_error", "error_code": "max_uses_exceeded"}
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let results = blocks[0].results();
+        let summary: Vec<_> = results
+            .iter()
+            .map(|result| (result.title(), result.url(), result.page_age()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Rust", "https://www.rust-lang.org/", Some("1 week ago")),
+                ("Crates", "https://crates.io/", None),
+            ]
+        );
+        assert!(blocks[0].error().is_none());
+
+        assert!(blocks[1].results().is_empty());
+        assert_eq!(
+            blocks[1].error().map(|error| &error.error_code),
+            Some(&WebSearchErrorCode::MaxUsesExceeded)
+        );
+    }
+
     #[test]
     fn with_cache_control() {
         let results = vec![